@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS note_revisions (
+    id INTEGER PRIMARY KEY,
+    note_id TEXT NOT NULL,
+    content TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_revisions_note_id ON note_revisions(note_id, id DESC);
@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS idx_notes_list_order
+    ON notes(deleted_at, pinned DESC, updated_at DESC, id DESC);
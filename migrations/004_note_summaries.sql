@@ -0,0 +1,4 @@
+ALTER TABLE notes ADD COLUMN title TEXT;
+ALTER TABLE notes ADD COLUMN excerpt TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_notes_missing_title ON notes(id) WHERE title IS NULL;
@@ -0,0 +1,12 @@
+-- Rebuild the full-text index so that case and accents are ignored
+-- (`idee` finds `idée`). Trashed notes are indexed too.
+DROP TABLE IF EXISTS notes_fts;
+
+CREATE VIRTUAL TABLE notes_fts USING fts5(
+    note_id UNINDEXED,
+    content,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO notes_fts (note_id, content)
+SELECT id, content FROM notes;
@@ -0,0 +1,10 @@
+-- Secondary index of every three-character sequence, used to find fuzzy
+-- search candidates by fragment.
+CREATE VIRTUAL TABLE IF NOT EXISTS notes_trigram USING fts5(
+    note_id UNINDEXED,
+    content,
+    tokenize = 'trigram remove_diacritics 1'
+);
+
+INSERT INTO notes_trigram (note_id, content)
+SELECT id, content FROM notes;
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS saved_searches (
+    id INTEGER PRIMARY KEY,
+    name TEXT NOT NULL UNIQUE,
+    query TEXT NOT NULL,
+    mode TEXT NOT NULL DEFAULT 'simple',
+    created_at TEXT NOT NULL
+);
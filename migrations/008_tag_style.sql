@@ -0,0 +1,3 @@
+-- Optional chip color (#rrggbb) and emoji for each tag.
+ALTER TABLE tags ADD COLUMN color TEXT;
+ALTER TABLE tags ADD COLUMN icon TEXT;
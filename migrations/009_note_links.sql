@@ -0,0 +1,20 @@
+-- `[[links]]` between notes. `target` is the text between the brackets and
+-- `target_id` the note it resolved to when the source was saved.
+CREATE TABLE IF NOT EXISTS note_links (
+    source_id TEXT NOT NULL,
+    target TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    PRIMARY KEY (source_id, target),
+    FOREIGN KEY(source_id) REFERENCES notes(id) ON DELETE CASCADE,
+    FOREIGN KEY(target_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_links_target_id ON note_links(target_id);
+
+-- Notes written before links existed, parsed once at the next start.
+CREATE TABLE IF NOT EXISTS note_links_pending (
+    note_id TEXT PRIMARY KEY
+);
+
+INSERT OR IGNORE INTO note_links_pending (note_id)
+SELECT id FROM notes WHERE content LIKE '%[[%]]%';
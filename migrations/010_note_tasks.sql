@@ -0,0 +1,20 @@
+-- `- [ ]` checklist items of every note, rewritten when the note is saved.
+-- `line` is the line of the item in the note content, from 0.
+CREATE TABLE IF NOT EXISTS note_tasks (
+    note_id TEXT NOT NULL,
+    line INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    done INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (note_id, line),
+    FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_note_tasks_done ON note_tasks(done, note_id);
+
+-- Notes written before tasks existed, parsed once at the next start.
+CREATE TABLE IF NOT EXISTS note_tasks_pending (
+    note_id TEXT PRIMARY KEY
+);
+
+INSERT OR IGNORE INTO note_tasks_pending (note_id)
+SELECT id FROM notes WHERE content LIKE '%[ ]%' OR content LIKE '%[x]%';
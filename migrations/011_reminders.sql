@@ -0,0 +1,13 @@
+-- Notes to bring back at a given time. `due_at` is in unix seconds, stored as
+-- an integer so pending reminders can be ordered and compared; `fired_at` is
+-- set once the notification has been shown.
+CREATE TABLE IF NOT EXISTS reminders (
+    id INTEGER PRIMARY KEY,
+    note_id TEXT NOT NULL,
+    due_at INTEGER NOT NULL,
+    fired_at INTEGER,
+    created_at TEXT NOT NULL,
+    FOREIGN KEY(note_id) REFERENCES notes(id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS idx_reminders_pending ON reminders(fired_at, due_at);
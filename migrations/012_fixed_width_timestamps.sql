@@ -0,0 +1,15 @@
+-- Note dates are compared as text by the list order and page cursors, which
+-- only matches numeric order when every value has the same width. Imports
+-- used to store shorter (pre-2001) or negative values; pad and clamp them to
+-- ten digits like the ones written by the app.
+UPDATE notes
+SET created_at = printf('%010d', min(max(CAST(created_at AS INTEGER), 0), 9999999999))
+WHERE created_at <> printf('%010d', min(max(CAST(created_at AS INTEGER), 0), 9999999999));
+
+UPDATE notes
+SET updated_at = printf('%010d', min(max(CAST(updated_at AS INTEGER), 0), 9999999999))
+WHERE updated_at <> printf('%010d', min(max(CAST(updated_at AS INTEGER), 0), 9999999999));
+
+UPDATE note_revisions
+SET created_at = printf('%010d', min(max(CAST(created_at AS INTEGER), 0), 9999999999))
+WHERE created_at <> printf('%010d', min(max(CAST(created_at AS INTEGER), 0), 9999999999));
@@ -0,0 +1,5 @@
+-- SHA-256 of the trimmed content, used by imports to find notes they already
+-- hold. Existing notes get theirs computed at the next start.
+ALTER TABLE notes ADD COLUMN content_hash TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_notes_content_hash ON notes(content_hash);
@@ -1,12 +1,16 @@
-use std::cell::RefCell;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::ops::Range;
 use std::rc::Rc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use anyhow::Result;
 use gtk::gdk;
 use gtk::gio;
+use gtk::glib;
 use gtk::glib::variant::ToVariant;
-use gtk::glib::Propagation;
+use gtk::glib::{Propagation, VariantTy};
+use gtk::pango;
 use gtk::prelude::*;
 use gtk::{
     Align, Application, ApplicationWindow, Box as GtkBox, Button, Entry, Label, ListBox,
@@ -15,9 +19,22 @@ use gtk::{
 };
 use notify_rust::Notification;
 use rusqlite::Connection;
+use similar::{ChangeTag, TextDiff};
 
+use crate::colors;
 use crate::config::AppConfig;
 use crate::db;
+use crate::export::{self, ExportMode};
+use crate::hashtags;
+use crate::import;
+use crate::markdown::{self, Style};
+use crate::note_list::{NoteListModel, NoteObject};
+use crate::query::{self, QueryMode};
+use crate::reminders;
+use crate::wikilinks;
+use crate::worker::{DbWorker, LatestRequest};
+
+type TagChange = Box<dyn FnOnce(&mut Connection) -> Result<()> + Send>;
 
 fn clear_listbox(list_box: &ListBox) {
     while let Some(child) = list_box.first_child() {
@@ -25,13 +42,377 @@ fn clear_listbox(list_box: &ListBox) {
     }
 }
 
-fn note_title(content: &str) -> String {
-    content
-        .lines()
-        .find(|line| !line.trim().is_empty())
-        .map(|line| line.trim().chars().take(60).collect::<String>())
-        .filter(|title| !title.is_empty())
-        .unwrap_or_else(|| "(empty note)".to_string())
+fn search_mode_at(position: u32) -> QueryMode {
+    match position {
+        1 => QueryMode::Advanced,
+        2 => QueryMode::Fuzzy,
+        _ => QueryMode::Simple,
+    }
+}
+
+fn search_mode_position(mode: QueryMode) -> u32 {
+    match mode {
+        QueryMode::Simple => 0,
+        QueryMode::Advanced => 1,
+        QueryMode::Fuzzy => 2,
+    }
+}
+
+// One line of the tag tree. Parents nobody tagged a note with directly
+// still get a line, without a count.
+struct TagTreeRow {
+    name: String,
+    depth: usize,
+    notes: Option<i64>,
+    color: Option<String>,
+    icon: Option<String>,
+    has_children: bool,
+}
+
+// Lays `tags` out as a tree, leaving out the descendants of `collapsed` tags.
+fn tag_tree(tags: &[db::TagUsage], collapsed: &HashSet<String>) -> Vec<TagTreeRow> {
+    let mut sorted: Vec<&db::TagUsage> = tags.iter().collect();
+    sorted.sort_by(|a, b| a.name.split('/').cmp(b.name.split('/')));
+
+    let mut rows: Vec<TagTreeRow> = Vec::new();
+    let mut seen = HashSet::new();
+    for tag in sorted {
+        for (index, _) in tag.name.match_indices('/') {
+            let parent = &tag.name[..index];
+            if seen.insert(parent.to_string()) {
+                rows.push(TagTreeRow {
+                    name: parent.to_string(),
+                    depth: parent.matches('/').count(),
+                    notes: None,
+                    color: None,
+                    icon: None,
+                    has_children: true,
+                });
+            }
+        }
+        seen.insert(tag.name.clone());
+        rows.push(TagTreeRow {
+            name: tag.name.clone(),
+            depth: tag.name.matches('/').count(),
+            notes: Some(tag.notes),
+            color: tag.color.clone(),
+            icon: tag.icon.clone(),
+            has_children: false,
+        });
+    }
+
+    for index in 1..rows.len() {
+        if rows[index].depth > rows[index - 1].depth {
+            rows[index - 1].has_children = true;
+        }
+    }
+
+    rows.retain(|row| {
+        !row.name
+            .match_indices('/')
+            .any(|(index, _)| collapsed.contains(&row.name[..index]))
+    });
+    rows
+}
+
+fn build_tag_tree_row(tag: &TagTreeRow, collapsed: bool) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    let content = GtkBox::new(Orientation::Horizontal, 8);
+    content.set_margin_top(6);
+    content.set_margin_bottom(6);
+    content.set_margin_start(8 + 20 * i32::try_from(tag.depth).unwrap_or(0));
+    content.set_margin_end(8);
+
+    let expander = Button::from_icon_name(if collapsed {
+        "pan-end-symbolic"
+    } else {
+        "pan-down-symbolic"
+    });
+    expander.add_css_class("flat");
+    if tag.has_children {
+        expander.set_tooltip_text(Some("Plier ou deplier les sous-tags"));
+        expander.set_action_name(Some("app.toggle_tag_branch"));
+        expander.set_action_target_value(Some(&tag.name.to_variant()));
+    } else {
+        // Keeps leaves aligned with their siblings.
+        expander.set_opacity(0.0);
+        expander.set_sensitive(false);
+    }
+
+    let leaf = tag.name.rsplit('/').next().unwrap_or_default();
+    let name = Label::new(Some(&match &tag.icon {
+        Some(icon) => format!("{} {}", icon, leaf),
+        None => leaf.to_string(),
+    }));
+    name.set_halign(Align::Start);
+    name.set_xalign(0.0);
+    name.set_tooltip_text(Some(&tag.name));
+    match tag.color.as_deref().and_then(tag_color_class) {
+        Some(class) => {
+            name.add_css_class("tag-chip");
+            name.add_css_class(&class);
+        }
+        None => name.add_css_class("section-title"),
+    }
+    let spacer = GtkBox::new(Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+
+    content.append(&expander);
+    content.append(&name);
+    content.append(&spacer);
+    if let Some(notes) = tag.notes {
+        let count = Label::new(Some(&format!("{} notes", notes)));
+        count.add_css_class("note-meta");
+        content.append(&count);
+    }
+    row.set_child(Some(&content));
+    row
+}
+
+// A checklist item on the Tasks page: its checkbox, its text and the note it
+// comes from, which opens on click.
+fn build_task_row(task: &db::NoteTask) -> (ListBoxRow, gtk::CheckButton) {
+    let row = ListBoxRow::new();
+    row.set_activatable(false);
+    let content = GtkBox::new(Orientation::Horizontal, 8);
+    content.set_margin_top(4);
+    content.set_margin_bottom(4);
+    content.set_margin_start(8);
+    content.set_margin_end(8);
+
+    let check = gtk::CheckButton::new();
+    check.set_active(task.done);
+    check.set_tooltip_text(Some("Cocher ou decocher la tache dans sa note"));
+
+    let text = Label::new(Some(&task.text));
+    text.set_halign(Align::Start);
+    text.set_xalign(0.0);
+    text.set_hexpand(true);
+    text.set_wrap(true);
+    if task.done {
+        text.add_css_class("note-meta");
+    }
+
+    let source = Button::with_label(&task.note_title);
+    source.add_css_class("flat");
+    source.set_tooltip_text(Some("Ouvrir la note de cette tache"));
+    source.set_action_name(Some("app.open_note"));
+    source.set_action_target_value(Some(&task.note_id.to_variant()));
+
+    content.append(&check);
+    content.append(&text);
+    content.append(&source);
+    row.set_child(Some(&content));
+    (row, check)
+}
+
+fn selected_tag_names(list_box: &ListBox, tags: &RefCell<Vec<TagTreeRow>>) -> Vec<String> {
+    let tags = tags.borrow();
+    list_box
+        .selected_rows()
+        .iter()
+        .filter_map(|row| usize::try_from(row.index()).ok())
+        .filter_map(|index| tags.get(index).map(|tag| tag.name.clone()))
+        .collect()
+}
+
+// How often saved search counts are taken again when nothing changed, for
+// relative dates to move with the clock.
+const SAVED_SEARCH_COUNT_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+fn build_saved_search_row(search: &db::SavedSearch, count: Option<i64>) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    let content = GtkBox::new(Orientation::Horizontal, 6);
+    content.set_margin_top(4);
+    content.set_margin_bottom(4);
+    content.set_margin_start(8);
+    content.set_margin_end(4);
+
+    let name = Label::new(Some(&search.name));
+    name.set_halign(Align::Start);
+    name.set_xalign(0.0);
+    name.set_hexpand(true);
+    name.set_ellipsize(pango::EllipsizeMode::End);
+    name.set_tooltip_text(Some(&search.query));
+
+    let count = Label::new(Some(
+        &count.map_or_else(|| "?".to_string(), |count| count.to_string()),
+    ));
+    count.add_css_class("note-meta");
+
+    let delete = Button::from_icon_name("edit-delete-symbolic");
+    delete.add_css_class("flat");
+    delete.set_tooltip_text(Some("Supprimer cette recherche"));
+    delete.set_action_name(Some("app.delete_saved_search"));
+    delete.set_action_target_value(Some(&search.id.to_variant()));
+
+    content.append(&name);
+    content.append(&count);
+    content.append(&delete);
+    row.set_child(Some(&content));
+    row
+}
+
+fn build_reminder_row(reminder: &db::Reminder) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    row.set_activatable(false);
+    let content = GtkBox::new(Orientation::Horizontal, 8);
+    content.set_margin_top(4);
+    content.set_margin_bottom(4);
+    content.set_margin_start(8);
+    content.set_margin_end(4);
+
+    let due = Label::new(Some(&format_local_time(reminder.due_at)));
+    due.add_css_class("note-meta");
+
+    let note = Button::with_label(&reminder.note_title);
+    note.add_css_class("flat");
+    note.set_hexpand(true);
+    note.set_halign(Align::Start);
+    note.set_tooltip_text(Some("Ouvrir la note de ce rappel"));
+    note.set_action_name(Some("app.open_note"));
+    note.set_action_target_value(Some(&reminder.note_id.to_variant()));
+
+    let delete = Button::from_icon_name("edit-delete-symbolic");
+    delete.add_css_class("flat");
+    delete.set_tooltip_text(Some("Supprimer ce rappel"));
+    delete.set_action_name(Some("app.delete_reminder"));
+    delete.set_action_target_value(Some(&reminder.id.to_variant()));
+
+    content.append(&due);
+    content.append(&note);
+    content.append(&delete);
+    row.set_child(Some(&content));
+    row
+}
+
+// How far the Snooze button of a reminder notification puts it off.
+const REMINDER_SNOOZE_SECS: i64 = 10 * 60;
+
+// Timeouts follow the monotonic clock, which stops during suspend. Waking up
+// at least this often keeps reminders that came due meanwhile from being late
+// by more than that.
+const REMINDER_MAX_WAIT_SECS: u32 = 15 * 60;
+
+fn local_utc_offset() -> i64 {
+    glib::DateTime::now_local()
+        .map(|now| now.utc_offset().as_seconds())
+        .unwrap_or(0)
+}
+
+// The due time typed in `entry` as unix seconds, or the reason it can't be
+// used.
+fn reminder_due(entry: &Entry) -> std::result::Result<i64, &'static str> {
+    let now = db::unix_seconds().map_err(|_| "Horloge systeme invalide")?;
+    match reminders::parse_due(&entry.text(), now, local_utc_offset()) {
+        Some(due) if due > now => Ok(due),
+        Some(_) => Err("Cette heure est deja passee"),
+        None => Err("Date non reconnue (ex: demain 9h, dans 2h)"),
+    }
+}
+
+struct ReminderScheduler {
+    window: ApplicationWindow,
+    worker: DbWorker,
+    timer: RefCell<Option<glib::SourceId>>,
+    // Reloads the upcoming reminders list.
+    on_change: Rc<dyn Fn()>,
+}
+
+// Fires the reminders that are due, then arms a single timeout for the next
+// one. Runs at startup, which catches reminders that came due while the app
+// was closed, and after every reminder change.
+fn schedule_reminders(scheduler: &Rc<ReminderScheduler>) {
+    let request = scheduler.worker.call(|conn| {
+        let now = db::unix_seconds()?;
+        let due = db::take_due_reminders(conn, now)?;
+        Ok((now, due, db::next_reminder_due(conn)?))
+    });
+
+    let scheduler = Rc::clone(scheduler);
+    glib::spawn_future_local(async move {
+        let Ok((now, due, next)) = request.await else {
+            return;
+        };
+        for reminder in due {
+            notify_reminder(&scheduler, reminder);
+        }
+        scheduler.on_change.as_ref()();
+
+        let timer = next.map(|next| {
+            let wait = u32::try_from((next - now).max(1))
+                .unwrap_or(u32::MAX)
+                .min(REMINDER_MAX_WAIT_SECS);
+            glib::timeout_add_seconds_local_once(wait, {
+                let scheduler = Rc::clone(&scheduler);
+                move || {
+                    // The source is gone once it has run.
+                    scheduler.timer.borrow_mut().take();
+                    schedule_reminders(&scheduler);
+                }
+            })
+        });
+        if let Some(previous) = scheduler.timer.replace(timer) {
+            previous.remove();
+        }
+    });
+}
+
+// Shows a reminder that opens its note when clicked, with a snooze button.
+// The notification is waited on off the main loop; the action chosen, if
+// any, is handled back on it.
+fn notify_reminder(scheduler: &Rc<ReminderScheduler>, reminder: db::Reminder) {
+    let mut notification = Notification::new();
+    notification
+        .summary("Memo-Tori")
+        .body(&reminder.note_title)
+        .action("default", "Ouvrir")
+        .action("snooze", "Reporter de 10 min");
+
+    let pressed = gio::spawn_blocking(move || {
+        let mut pressed = String::new();
+        if let Ok(handle) = notification.show() {
+            handle.wait_for_action(|action| pressed = action.to_string());
+        }
+        pressed
+    });
+
+    let scheduler = Rc::clone(scheduler);
+    glib::spawn_future_local(async move {
+        match pressed.await.as_deref() {
+            Ok("default") => {
+                scheduler.window.present();
+                let _ = WidgetExt::activate_action(
+                    &scheduler.window,
+                    "app.open_note",
+                    Some(&reminder.note_id.to_variant()),
+                );
+            }
+            Ok("snooze") => {
+                let request = scheduler.worker.call(move |conn| {
+                    let due = db::unix_seconds()? + REMINDER_SNOOZE_SECS;
+                    db::snooze_reminder(conn, reminder.id, due)
+                });
+                if request.await.is_ok() {
+                    schedule_reminders(&scheduler);
+                }
+            }
+            _ => {}
+        }
+    });
+}
+
+fn selected_note(selection: &gtk::SingleSelection) -> Option<db::NoteSummary> {
+    selection
+        .selected_item()
+        .and_downcast::<NoteObject>()?
+        .item()
+}
+
+fn selected_note_id(list_box: &ListBox, notes: &RefCell<Vec<db::NoteListItem>>) -> Option<String> {
+    let row = list_box.selected_row()?;
+    let index = usize::try_from(row.index()).ok()?;
+    notes.borrow().get(index).map(|note| note.id.clone())
 }
 
 fn parse_tags(input: &str) -> Vec<String> {
@@ -51,6 +432,8 @@ fn current_tag_fragment(input: &str) -> String {
         .to_lowercase()
 }
 
+// A completion ending in `/` is a parent tag: the caret stays after it so the
+// next level can be picked.
 fn apply_tag_completion(input: &str, completion: &str) -> String {
     let mut parts: Vec<String> = input
         .split(',')
@@ -69,11 +452,51 @@ fn apply_tag_completion(input: &str, completion: &str) -> String {
         .filter(|part| !part.is_empty())
         .collect::<Vec<_>>()
         .join(", ");
-    rebuilt.push_str(", ");
+    if !completion.ends_with('/') {
+        rebuilt.push_str(", ");
+    }
     rebuilt
 }
 
-fn attach_tag_autocomplete(entry: &Entry, conn: Rc<RefCell<Connection>>) {
+// The tag being typed in the value of the last `tag:`/`-tag:` operator of a
+// search, after any `|` separating alternatives.
+fn search_tag_fragment(input: &str) -> String {
+    let word = input.rsplit(char::is_whitespace).next().unwrap_or_default();
+    let word = word.strip_prefix('-').unwrap_or(word).to_lowercase();
+    match word.strip_prefix("tag:") {
+        Some(value) => value.rsplit('|').next().unwrap_or_default().to_string(),
+        None => String::new(),
+    }
+}
+
+fn apply_search_tag_completion(input: &str, completion: &str) -> String {
+    let cut = input
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| *ch == '|' || *ch == ':' || ch.is_whitespace())
+        .map(|(index, ch)| index + ch.len_utf8())
+        .unwrap_or(0);
+    let separator = if completion.ends_with('/') { "" } else { " " };
+    format!("{}{}{}", &input[..cut], completion, separator)
+}
+
+// A field holding a single tag, like the Tasks page filter.
+fn single_tag_fragment(input: &str) -> String {
+    input.trim().to_lowercase()
+}
+
+fn apply_single_tag_completion(_input: &str, completion: &str) -> String {
+    completion.to_string()
+}
+
+// `fragment` extracts the tag being typed and `complete` splices the chosen
+// suggestion back into the text.
+fn attach_tag_autocomplete<W: IsA<gtk::Editable> + IsA<gtk::Widget>>(
+    entry: &W,
+    worker: DbWorker,
+    fragment: fn(&str) -> String,
+    complete: fn(&str, &str) -> String,
+) {
     let popover = Popover::new();
     popover.set_has_arrow(false);
     popover.set_autohide(true);
@@ -96,183 +519,3403 @@ fn attach_tag_autocomplete(entry: &Entry, conn: Rc<RefCell<Connection>>) {
             };
 
             let completion = label.text().to_string();
-            let updated = apply_tag_completion(&entry.text(), &completion);
+            let updated = complete(&entry.text(), &completion);
             entry.set_text(&updated);
             entry.set_position(-1);
             popover.popdown();
         }
     });
 
+    let latest = LatestRequest::default();
+
     entry.connect_changed({
         let entry = entry.clone();
-        let conn = Rc::clone(&conn);
         let suggestions = suggestions.clone();
         let popover = popover.clone();
         move |_| {
-            let fragment = current_tag_fragment(&entry.text());
+            let fragment = fragment(&entry.text());
             if fragment.is_empty() {
+                latest.cancel();
                 popover.popdown();
                 return;
             }
 
-            let tags = db::list_tags_prefix(&conn.borrow(), &fragment, 8).unwrap_or_default();
-            let tags: Vec<String> = tags.into_iter().filter(|tag| tag != &fragment).collect();
+            let request = worker.call_latest(&latest, {
+                let fragment = fragment.clone();
+                move |conn| db::list_tags_prefix(conn, &fragment, 8)
+            });
 
-            if tags.is_empty() {
-                popover.popdown();
+            let suggestions = suggestions.clone();
+            let popover = popover.clone();
+            glib::spawn_future_local(async move {
+                let Some(result) = request.await else {
+                    return;
+                };
+
+                let tags: Vec<String> = result
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|tag| tag != &fragment)
+                    .collect();
+
+                if tags.is_empty() {
+                    popover.popdown();
+                    return;
+                }
+
+                clear_listbox(&suggestions);
+                for tag in tags {
+                    suggestions.append(&build_suggestion_row(&tag));
+                }
+
+                popover.popup();
+            });
+        }
+    });
+}
+
+fn build_suggestion_row(tag: &str) -> ListBoxRow {
+    let row = ListBoxRow::new();
+    let label = Label::new(Some(tag));
+    label.set_halign(Align::Start);
+    label.set_xalign(0.0);
+    label.set_margin_top(6);
+    label.set_margin_bottom(6);
+    label.set_margin_start(10);
+    label.set_margin_end(10);
+    row.set_child(Some(&label));
+    row
+}
+
+// Where the text being completed starts and what was typed so far, found by
+// `fragment` in the line before the cursor.
+fn fragment_before_cursor(
+    buffer: &gtk::TextBuffer,
+    fragment: fn(&str) -> Option<&str>,
+) -> Option<(gtk::TextIter, String)> {
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let mut line_start = cursor;
+    line_start.set_line_offset(0);
+
+    let before = buffer.text(&line_start, &cursor, false);
+    let fragment = fragment(&before)?;
+    let mut start = cursor;
+    start.backward_chars(i32::try_from(fragment.chars().count()).ok()?);
+    Some((start, fragment.to_string()))
+}
+
+fn suggest_hashtags(conn: &Connection, fragment: &str) -> Result<Vec<String>> {
+    let fragment = fragment.to_lowercase();
+    let mut tags = db::list_tags_prefix(conn, &fragment, 8)?;
+    tags.retain(|tag| *tag != fragment);
+    Ok(tags)
+}
+
+// A parent tag keeps the cursor after its `/` for the next level.
+fn complete_hashtag(tag: &str) -> String {
+    if tag.ends_with('/') {
+        tag.to_string()
+    } else {
+        format!("{} ", tag)
+    }
+}
+
+fn suggest_note_titles(conn: &Connection, fragment: &str) -> Result<Vec<String>> {
+    db::list_note_titles(conn, fragment, 8)
+}
+
+fn complete_note_link(title: &str) -> String {
+    format!("{}]]", title)
+}
+
+// Suggests completions while typing in `view`: `fragment` finds the text
+// being completed, `suggest` lists candidates for it and `complete` gives
+// the text that replaces it. The popover leaves the focus in the text:
+// Up/Down pick a suggestion, Tab or Enter insert it and Escape dismisses it.
+fn attach_text_autocomplete(
+    view: &TextView,
+    worker: DbWorker,
+    fragment: fn(&str) -> Option<&str>,
+    suggest: fn(&Connection, &str) -> Result<Vec<String>>,
+    complete: fn(&str) -> String,
+) {
+    let popover = Popover::new();
+    popover.set_has_arrow(false);
+    popover.set_autohide(false);
+    popover.set_position(gtk::PositionType::Bottom);
+    popover.set_parent(view);
+
+    let suggestions = ListBox::new();
+    suggestions.set_activate_on_single_click(true);
+    popover.set_child(Some(&suggestions));
+
+    suggestions.connect_row_activated({
+        let view = view.clone();
+        let popover = popover.clone();
+        move |_, row| {
+            let Some(label) = row.child().and_downcast::<Label>() else {
+                return;
+            };
+
+            let buffer = view.buffer();
+            let Some((mut start, _)) = fragment_before_cursor(&buffer, fragment) else {
                 return;
+            };
+            let mut cursor = buffer.iter_at_mark(&buffer.get_insert());
+            popover.popdown();
+
+            buffer.delete(&mut start, &mut cursor);
+            buffer.insert(&mut start, &complete(&label.text()));
+            view.grab_focus();
+        }
+    });
+
+    let keys = gtk::EventControllerKey::new();
+    keys.set_propagation_phase(gtk::PropagationPhase::Capture);
+    keys.connect_key_pressed({
+        let popover = popover.clone();
+        let suggestions = suggestions.clone();
+        move |_, key, _, _| {
+            if !popover.is_visible() {
+                return Propagation::Proceed;
             }
 
-            clear_listbox(&suggestions);
-            for tag in tags {
-                let row = ListBoxRow::new();
-                let label = Label::new(Some(&tag));
-                label.set_halign(Align::Start);
-                label.set_xalign(0.0);
-                label.set_margin_top(6);
-                label.set_margin_bottom(6);
-                label.set_margin_start(10);
-                label.set_margin_end(10);
-                row.set_child(Some(&label));
-                suggestions.append(&row);
+            let selected = suggestions.selected_row().map_or(-1, |row| row.index());
+            match key {
+                gdk::Key::Down | gdk::Key::Up => {
+                    let step = if key == gdk::Key::Down { 1 } else { -1 };
+                    if let Some(row) = suggestions.row_at_index((selected + step).max(0)) {
+                        suggestions.select_row(Some(&row));
+                    }
+                }
+                gdk::Key::Return | gdk::Key::KP_Enter | gdk::Key::Tab => {
+                    if let Some(row) = suggestions.row_at_index(selected.max(0)) {
+                        row.emit_activate();
+                    }
+                }
+                gdk::Key::Escape => popover.popdown(),
+                _ => return Propagation::Proceed,
             }
+            Propagation::Stop
+        }
+    });
+    view.add_controller(keys);
+
+    let latest = LatestRequest::default();
+
+    view.buffer().connect_changed({
+        let view = view.clone();
+        move |buffer| {
+            let Some((start, fragment)) = fragment_before_cursor(buffer, fragment) else {
+                latest.cancel();
+                popover.popdown();
+                return;
+            };
+
+            let request = worker.call_latest(&latest, move |conn| suggest(conn, &fragment));
+
+            let view = view.clone();
+            let suggestions = suggestions.clone();
+            let popover = popover.clone();
+            glib::spawn_future_local(async move {
+                let Some(result) = request.await else {
+                    return;
+                };
+
+                let candidates = result.unwrap_or_default();
+                if candidates.is_empty() {
+                    popover.popdown();
+                    return;
+                }
+
+                clear_listbox(&suggestions);
+                for candidate in &candidates {
+                    suggestions.append(&build_suggestion_row(candidate));
+                }
 
-            popover.popup();
+                let location = view.iter_location(&start);
+                let (x, y) = view.buffer_to_window_coords(
+                    gtk::TextWindowType::Widget,
+                    location.x(),
+                    location.y(),
+                );
+                popover.set_pointing_to(Some(&gdk::Rectangle::new(x, y, 1, location.height())));
+                popover.popup();
+            });
         }
     });
 }
 
-fn random_hint(hints: &[String]) -> String {
-    if hints.is_empty() {
-        return "L'idee que je viens d'avoir :".to_string();
-    }
+fn format_local_time(secs: i64) -> String {
+    glib::DateTime::from_unix_local(secs)
+        .and_then(|time| time.format("%Y-%m-%d %H:%M"))
+        .map(|text| text.to_string())
+        .unwrap_or_else(|_| secs.to_string())
+}
 
-    if hints.len() == 1 {
-        return hints[0].clone();
-    }
+thread_local! {
+    // Colors that have a `tag-color-*` class, and the provider holding them.
+    static TAG_COLOR_STYLES: RefCell<(Option<gtk::CssProvider>, BTreeSet<String>)> =
+        const { RefCell::new((None, BTreeSet::new())) };
+}
 
-    let seed = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .map(|d| d.as_nanos() as usize)
-        .unwrap_or(0);
+// The CSS class painting a chip in `color`, with a label color meeting the
+// WCAG AA contrast ratio. Classes are added to the display as colors show up.
+fn tag_color_class(color: &str) -> Option<String> {
+    let color = colors::normalize(color)?;
+    let class = format!("tag-color-{}", &color[1..]);
 
-    hints[seed % hints.len()].clone()
-}
+    TAG_COLOR_STYLES.with(|styles| {
+        let (provider, known) = &mut *styles.borrow_mut();
+        if !known.insert(color) {
+            return;
+        }
 
-fn icon_label_button(icon_name: &str, label_text: &str) -> Button {
-    let button = Button::new();
-    let content = GtkBox::new(Orientation::Horizontal, 6);
+        let provider = provider.get_or_insert_with(|| {
+            let provider = gtk::CssProvider::new();
+            if let Some(display) = gdk::Display::default() {
+                gtk::style_context_add_provider_for_display(
+                    &display,
+                    &provider,
+                    gtk::STYLE_PROVIDER_PRIORITY_APPLICATION + 1,
+                );
+            }
+            provider
+        });
 
-    let icon = gtk::Image::from_icon_name(icon_name);
-    icon.set_icon_size(gtk::IconSize::Normal);
-    let label = Label::new(Some(label_text));
+        let css: String = known
+            .iter()
+            .filter_map(|color| {
+                let text = colors::chip_text_color(color)?;
+                Some(format!(
+                    ".tag-color-{} {{ background: {}; color: {}; }}\n",
+                    &color[1..],
+                    color,
+                    text
+                ))
+            })
+            .collect();
+        provider.load_from_data(&css);
+    });
 
-    content.append(&icon);
-    content.append(&label);
-    button.set_child(Some(&content));
-    button
+    Some(class)
 }
 
-fn install_css() {
-    let provider = gtk::CssProvider::new();
-    provider.load_from_data(
-        "
-window {
-  background: linear-gradient(180deg, #f4f0e6 0%, #ece7dc 100%);
+fn tag_chip_text(tag: &db::NoteTag) -> String {
+    match &tag.icon {
+        Some(icon) => format!("{} {}", icon, tag.name),
+        None => tag.name.clone(),
+    }
 }
 
-* {
-  font-family: \"Inter\", \"Noto Sans\", \"DejaVu Sans\", sans-serif;
+// Clicking a chip narrows the search to its tag.
+fn build_tag_chip(tag: &db::NoteTag, small: bool) -> Button {
+    let chip = Button::with_label(&tag_chip_text(tag));
+    chip.add_css_class("tag-chip");
+    if small {
+        chip.add_css_class("tag-chip-small");
+    }
+    if let Some(class) = tag.color.as_deref().and_then(tag_color_class) {
+        chip.add_css_class(&class);
+    }
+    chip.set_tooltip_text(Some("Filtrer les notes sur ce tag"));
+    chip.set_action_name(Some("app.filter_tag"));
+    chip.set_action_target_value(Some(&tag.name.to_variant()));
+    chip
 }
 
-.menu-bar {
-  background: #1f3a45;
-  color: #f8fafc;
-  padding: 6px;
-  border-radius: 8px;
+fn show_tag_chips(container: &GtkBox, tags: &[db::NoteTag]) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+
+    let caption = Label::new(Some(if tags.is_empty() { "Tags: -" } else { "Tags:" }));
+    caption.add_css_class("note-meta");
+    container.append(&caption);
+    for tag in tags {
+        container.append(&build_tag_chip(tag, false));
+    }
 }
 
-.switcher-wrap {
-  background: #d8dfdc;
-  padding: 6px;
-  border-radius: 10px;
+// The search operator selecting `tag`, quoted when the name has spaces.
+// Every tag filter (chips, the tag tree, the Tags page) goes through the
+// search text this way, so a saved search keeps it with the query.
+fn tag_filter_chunk(tag: &str) -> String {
+    if tag.contains(char::is_whitespace) {
+        format!("tag:\"{}\"", tag)
+    } else {
+        format!("tag:{}", tag)
+    }
 }
 
-.capture-panel {
-  background: #EDF5F3;
-  border: 1px solid #b69f70;
-  border-radius: 12px;
-  padding: 12px;
+// Row layout: [pin, title, date] / snippet / tag chips. `bind_note_row`
+// relies on this child order.
+fn build_note_row() -> GtkBox {
+    let container = GtkBox::new(Orientation::Vertical, 4);
+    container.set_margin_top(8);
+    container.set_margin_bottom(8);
+    container.set_margin_start(8);
+    container.set_margin_end(8);
+
+    let header = GtkBox::new(Orientation::Horizontal, 6);
+
+    let pin = gtk::Image::from_icon_name("view-pin-symbolic");
+    pin.set_tooltip_text(Some("Note epinglee"));
+    pin.update_property(&[gtk::accessible::Property::Label("Pinned")]);
+    header.append(&pin);
+
+    let title = Label::new(None);
+    title.set_halign(Align::Start);
+    title.set_xalign(0.0);
+    title.set_hexpand(true);
+    title.set_ellipsize(pango::EllipsizeMode::End);
+    title.add_css_class("section-title");
+    header.append(&title);
+
+    let date = Label::new(None);
+    date.add_css_class("note-meta");
+    header.append(&date);
+
+    let snippet = Label::new(None);
+    snippet.set_halign(Align::Start);
+    snippet.set_xalign(0.0);
+    snippet.set_ellipsize(pango::EllipsizeMode::End);
+    snippet.add_css_class("note-snippet");
+
+    let tags = GtkBox::new(Orientation::Horizontal, 4);
+
+    container.append(&header);
+    container.append(&snippet);
+    container.append(&tags);
+    container
 }
 
-.library-panel {
-  background: #EDF5F3;
-  border: 1px solid #b69f70;
-  border-radius: 12px;
-  padding: 12px;
+fn bind_note_row(container: &GtkBox, note: Option<&db::NoteSummary>) {
+    let Some(header) = container.first_child() else {
+        return;
+    };
+    let Some(snippet) = header.next_sibling().and_downcast::<Label>() else {
+        return;
+    };
+    let Some(tags) = snippet.next_sibling().and_downcast::<GtkBox>() else {
+        return;
+    };
+    let Some(pin) = header.first_child() else {
+        return;
+    };
+    let Some(title) = pin.next_sibling().and_downcast::<Label>() else {
+        return;
+    };
+    let Some(date) = title.next_sibling().and_downcast::<Label>() else {
+        return;
+    };
+
+    while let Some(chip) = tags.first_child() {
+        tags.remove(&chip);
+    }
+
+    // Rows whose page is still loading get an empty placeholder.
+    let Some(note) = note else {
+        pin.set_visible(false);
+        title.set_text("…");
+        date.set_text("");
+        snippet.set_visible(false);
+        tags.set_visible(false);
+        return;
+    };
+
+    pin.set_visible(note.pinned);
+    title.set_text(&note.title);
+    date.set_text(&format_local_time(note.updated_at));
+    date.set_tooltip_text(Some(&format!(
+        "Cree le {}",
+        format_local_time(note.created_at)
+    )));
+    let attributes = pango::AttrList::new();
+    for range in &note.snippet_matches {
+        let mut weight = pango::AttrInt::new_weight(pango::Weight::Bold);
+        weight.set_start_index(range.start as u32);
+        weight.set_end_index(range.end as u32);
+        attributes.insert(weight);
+
+        let mut background = pango::AttrColor::new_background(0xfdfd, 0xe6e6, 0x8a8a);
+        background.set_start_index(range.start as u32);
+        background.set_end_index(range.end as u32);
+        attributes.insert(background);
+    }
+    snippet.set_text(&note.snippet);
+    snippet.set_attributes(Some(&attributes));
+    snippet.set_visible(!note.snippet.is_empty());
+
+    for tag in &note.tags {
+        tags.append(&build_tag_chip(tag, true));
+    }
+    tags.set_visible(!note.tags.is_empty());
 }
 
-.reader {
-  background: #ffffff;
-  color: #172127;
+// The note shown in the reader: its Markdown source, which the preview is
+// drawn from, and the notes its `[[links]]` lead to, by target.
+struct ReaderNote {
+    content: String,
+    links: HashMap<String, String>,
 }
 
-.note-editor {
-  font-family: \"JetBrains Mono\", \"Fira Code\", \"DejaVu Sans Mono\", monospace;
+// Shows the Markdown source of `note` for editing.
+fn show_note_source(view: &TextView, note: &ReaderNote, matches: &[Range<usize>]) {
+    view.add_css_class("note-editor");
+    view.set_editable(true);
+    view.set_cursor_visible(true);
+    view.buffer().set_text(&note.content);
+    mark_note_links(view, &note.content, &note.links);
+    highlight_matches(view, &note.content, matches);
 }
 
-.section-title {
-  color: #172127;
-  font-weight: 700;
+// Shows `note` rendered as Markdown. The preview is read-only: the buffer
+// holds the rendered text, not the source.
+fn show_note_preview(view: &TextView, note: &ReaderNote) {
+    view.remove_css_class("note-editor");
+    view.set_editable(false);
+    view.set_cursor_visible(false);
+
+    let rendered = markdown::render(&note.content);
+    let buffer = view.buffer();
+    buffer.set_text(&rendered.text);
+
+    let table = buffer.tag_table();
+    for span in &rendered.spans {
+        let Some(name) = markdown_tag(&table, &span.style, &note.links) else {
+            continue;
+        };
+        let (Some(before), Some(styled)) = (
+            rendered.text.get(..span.range.start),
+            rendered.text.get(span.range.clone()),
+        ) else {
+            continue;
+        };
+        let start = buffer.iter_at_offset(before.chars().count() as i32);
+        let end = buffer.iter_at_offset(start.offset() + styled.chars().count() as i32);
+        buffer.apply_tag_by_name(&name, &start, &end);
+    }
+}
+
+// The name of the text tag drawing `style`, added to `table` on first use.
+// Links get a tag per destination so a click can tell where they lead;
+// `[[links]]` to no live note are left plain.
+fn markdown_tag(
+    table: &gtk::TextTagTable,
+    style: &Style,
+    links: &HashMap<String, String>,
+) -> Option<String> {
+    let name = match style {
+        Style::Heading(level) => format!("md-heading-{}", level),
+        Style::Bold => "md-bold".to_string(),
+        Style::Italic => "md-italic".to_string(),
+        Style::Strikethrough => "md-strikethrough".to_string(),
+        Style::Code => "md-code".to_string(),
+        Style::CodeBlock => "md-code-block".to_string(),
+        Style::Quote => "md-quote".to_string(),
+        Style::ListMarker => "md-marker".to_string(),
+        Style::Checkbox { line, .. } => format!("task:{}", line),
+        Style::Done => "md-done".to_string(),
+        Style::Link(url) => format!("web-link:{}", url),
+        Style::NoteLink(target) => format!("note-link:{}", links.get(target)?),
+    };
+    if table.lookup(&name).is_some() {
+        return Some(name);
+    }
+
+    let tag = gtk::TextTag::builder().name(name.as_str());
+    let tag = match style {
+        Style::Heading(level) => tag
+            .weight(700)
+            .scale(match level {
+                1 => 1.6,
+                2 => 1.4,
+                3 => 1.2,
+                _ => 1.1,
+            })
+            .pixels_above_lines(6),
+        Style::Bold => tag.weight(700),
+        Style::Italic => tag.style(pango::Style::Italic),
+        Style::Strikethrough => tag.strikethrough(true),
+        Style::Code => tag.family("monospace").background("#eef2f5"),
+        Style::CodeBlock => tag
+            .family("monospace")
+            .paragraph_background("#eef2f5")
+            .left_margin(12),
+        Style::Quote => tag
+            .foreground("#52606d")
+            .style(pango::Style::Italic)
+            .left_margin(16),
+        Style::ListMarker | Style::Checkbox { .. } => tag.foreground("#0b6ea8").weight(700),
+        Style::Done => tag.foreground("#7b8794").strikethrough(true),
+        Style::Link(_) | Style::NoteLink(_) => tag
+            .foreground("#0b6ea8")
+            .underline(pango::Underline::Single),
+    };
+    table.add(&tag.build());
+    Some(name)
 }
 
-.status-label {
-  color: #172127;
-  font-weight: 600;
+// Underlines the `[[links]]` in `content`, the text of `view`, that lead to
+// a note in `links`. Each link gets the tag named after its target so a click
+// can tell where it leads, and edits to the text move the tags along with it.
+fn mark_note_links(view: &TextView, content: &str, links: &HashMap<String, String>) {
+    let buffer = view.buffer();
+    let table = buffer.tag_table();
+
+    for (range, target) in wikilinks::find(content) {
+        let Some(name) = markdown_tag(&table, &Style::NoteLink(target), links) else {
+            continue;
+        };
+        let (Some(before), Some(linked)) = (content.get(..range.start), content.get(range)) else {
+            continue;
+        };
+
+        let start = buffer.iter_at_offset(before.chars().count() as i32);
+        let end = buffer.iter_at_offset(start.offset() + linked.chars().count() as i32);
+        buffer.apply_tag_by_name(&name, &start, &end);
+    }
+}
+
+// The note a `[[link]]` at `iter` leads to.
+fn note_link_at(iter: &gtk::TextIter) -> Option<String> {
+    iter.tags()
+        .iter()
+        .find_map(|tag| tag.name()?.strip_prefix("note-link:").map(str::to_string))
+}
+
+// The line of the checklist item whose box is at `iter`, in preview mode.
+fn task_line_at(iter: &gtk::TextIter) -> Option<usize> {
+    iter.tags()
+        .iter()
+        .find_map(|tag| tag.name()?.strip_prefix("task:")?.parse().ok())
+}
+
+// The address a Markdown link at `iter` leads to, in preview mode.
+fn web_link_at(iter: &gtk::TextIter) -> Option<String> {
+    iter.tags()
+        .iter()
+        .find_map(|tag| tag.name()?.strip_prefix("web-link:").map(str::to_string))
+}
+
+fn show_backlinks(container: &GtkBox, notes: &[db::LinkedNote]) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    container.set_visible(!notes.is_empty());
+    if notes.is_empty() {
+        return;
+    }
+
+    let title = Label::new(Some("Linked from"));
+    title.set_halign(Align::Start);
+    title.add_css_class("section-title");
+    container.append(&title);
+
+    for note in notes {
+        let link = Button::with_label(&note.title);
+        link.add_css_class("flat");
+        link.set_halign(Align::Start);
+        link.set_tooltip_text(Some("Ouvrir cette note"));
+        link.set_action_name(Some("app.open_note"));
+        link.set_action_target_value(Some(&note.id.to_variant()));
+        container.append(&link);
+    }
+}
+
+/// Tags every search match in the reader and selects the first one.
+/// `matches` are byte ranges into `content`, which must be the buffer text.
+fn highlight_matches(view: &TextView, content: &str, matches: &[Range<usize>]) {
+    let buffer = view.buffer();
+    let table = buffer.tag_table();
+
+    if table.lookup("search-match").is_none() {
+        table.add(
+            &gtk::TextTag::builder()
+                .name("search-match")
+                .background("#fde68a")
+                .build(),
+        );
+    }
+    if table.lookup("search-current").is_none() {
+        table.add(
+            &gtk::TextTag::builder()
+                .name("search-current")
+                .background("#f59e0b")
+                .build(),
+        );
+    }
+
+    let mut first = None;
+    for range in matches {
+        let (Some(before), Some(matched)) =
+            (content.get(..range.start), content.get(range.clone()))
+        else {
+            continue;
+        };
+        let start = buffer.iter_at_offset(before.chars().count() as i32);
+        let end = buffer.iter_at_offset(start.offset() + matched.chars().count() as i32);
+        buffer.apply_tag_by_name("search-match", &start, &end);
+        first.get_or_insert((start, end));
+    }
+
+    if let Some((start, end)) = first {
+        select_match(view, &start, &end);
+    }
+}
+
+fn select_match(view: &TextView, start: &gtk::TextIter, end: &gtk::TextIter) {
+    let buffer = view.buffer();
+    buffer.remove_tag_by_name("search-current", &buffer.start_iter(), &buffer.end_iter());
+    buffer.apply_tag_by_name("search-current", start, end);
+    buffer.select_range(start, end);
+    view.scroll_to_mark(&buffer.get_insert(), 0.1, false, 0.0, 0.0);
+}
+
+/// Selects the next (or previous) search match after the cursor, wrapping
+/// around at either end of the note.
+fn goto_match(view: &TextView, forward: bool) {
+    let buffer = view.buffer();
+    let Some(tag) = buffer.tag_table().lookup("search-match") else {
+        return;
+    };
+
+    let find = |mut iter: gtk::TextIter| -> Option<gtk::TextIter> {
+        loop {
+            let moved = if forward {
+                iter.forward_to_tag_toggle(Some(&tag))
+            } else {
+                iter.backward_to_tag_toggle(Some(&tag))
+            };
+            if !moved {
+                return None;
+            }
+            if iter.starts_tag(Some(&tag)) {
+                return Some(iter);
+            }
+        }
+    };
+
+    let cursor = buffer.iter_at_mark(&buffer.get_insert());
+    let start = find(cursor).or_else(|| {
+        if forward {
+            // A match at the very start has no toggle before it to move to.
+            let start = buffer.start_iter();
+            if start.starts_tag(Some(&tag)) {
+                Some(start)
+            } else {
+                find(start)
+            }
+        } else {
+            find(buffer.end_iter())
+        }
+    });
+    let Some(start) = start else {
+        return;
+    };
+
+    let mut end = start;
+    end.forward_to_tag_toggle(Some(&tag));
+    select_match(view, &start, &end);
+}
+
+fn render_diff(view: &TextView, old: &str, new: &str) {
+    let buffer = view.buffer();
+    let table = buffer.tag_table();
+
+    if table.lookup("diff-added").is_none() {
+        table.add(
+            &gtk::TextTag::builder()
+                .name("diff-added")
+                .paragraph_background("#dcf2e3")
+                .foreground("#14532d")
+                .build(),
+        );
+        table.add(
+            &gtk::TextTag::builder()
+                .name("diff-removed")
+                .paragraph_background("#f9dede")
+                .foreground("#7f1d1d")
+                .strikethrough(true)
+                .build(),
+        );
+    }
+
+    buffer.set_text("");
+    let diff = TextDiff::from_lines(old, new);
+
+    for change in diff.iter_all_changes() {
+        let (prefix, tag) = match change.tag() {
+            ChangeTag::Equal => ("  ", None),
+            ChangeTag::Delete => ("- ", Some("diff-removed")),
+            ChangeTag::Insert => ("+ ", Some("diff-added")),
+        };
+
+        let mut line = format!("{}{}", prefix, change.value());
+        if !line.ends_with('\n') {
+            line.push('\n');
+        }
+
+        let mut end = buffer.end_iter();
+        match tag {
+            Some(tag) => buffer.insert_with_tags_by_name(&mut end, &line, &[tag]),
+            None => buffer.insert(&mut end, &line),
+        }
+    }
+
+    if old == new {
+        buffer.set_text("(no differences)");
+    }
+}
+
+fn random_hint(hints: &[String]) -> String {
+    if hints.is_empty() {
+        return "L'idee que je viens d'avoir :".to_string();
+    }
+
+    if hints.len() == 1 {
+        return hints[0].clone();
+    }
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as usize)
+        .unwrap_or(0);
+
+    hints[seed % hints.len()].clone()
+}
+
+fn icon_label_button(icon_name: &str, label_text: &str) -> Button {
+    let button = Button::new();
+    let content = GtkBox::new(Orientation::Horizontal, 6);
+
+    let icon = gtk::Image::from_icon_name(icon_name);
+    icon.set_icon_size(gtk::IconSize::Normal);
+    let label = Label::new(Some(label_text));
+
+    content.append(&icon);
+    content.append(&label);
+    button.set_child(Some(&content));
+    button
+}
+
+fn install_css() {
+    let provider = gtk::CssProvider::new();
+    provider.load_from_data(
+        "
+window {
+  background: linear-gradient(180deg, #f4f0e6 0%, #ece7dc 100%);
+}
+
+* {
+  font-family: \"Inter\", \"Noto Sans\", \"DejaVu Sans\", sans-serif;
+}
+
+.menu-bar {
+  background: #1f3a45;
+  color: #f8fafc;
+  padding: 6px;
+  border-radius: 8px;
+}
+
+.switcher-wrap {
+  background: #d8dfdc;
+  padding: 6px;
+  border-radius: 10px;
+}
+
+.capture-panel {
+  background: #EDF5F3;
+  border: 1px solid #b69f70;
+  border-radius: 12px;
+  padding: 12px;
+}
+
+.quick-capture .capture-panel {
+  border-radius: 0;
+}
+
+.library-panel {
+  background: #EDF5F3;
+  border: 1px solid #b69f70;
+  border-radius: 12px;
+  padding: 12px;
+}
+
+.reader {
+  background: #ffffff;
+  color: #172127;
+}
+
+.note-editor {
+  font-family: \"JetBrains Mono\", \"Fira Code\", \"DejaVu Sans Mono\", monospace;
+}
+
+.section-title {
+  color: #172127;
+  font-weight: 700;
+}
+
+.status-label {
+  color: #172127;
+  font-weight: 600;
+}
+
+.placeholder-hint {
+  color: #496067;
+  font-style: italic;
+}
+
+.tag-chip {
+  color: #1f3a45;
+  background: #d9ecef;
+  border-radius: 8px;
+  padding: 4px 8px;
+}
+
+.tag-chip-small {
+  font-size: 0.85em;
+  padding: 1px 6px;
+}
+
+.note-meta {
+  color: #496067;
+  font-size: 0.85em;
+}
+
+.note-snippet {
+  color: #33474e;
+}
+
+.capture-panel textview,
+.library-panel entry,
+.library-panel list,
+.library-panel listview,
+.library-panel textview {
+  background: #ffffff;
+  color: #172127;
+  border-radius: 8px;
+}
+
+button:focus-visible,
+entry:focus-visible,
+textview:focus-visible,
+list:focus-visible {
+  outline: 3px solid #0b6ea8;
+  outline-offset: 2px;
+}
+",
+    );
+
+    if let Some(display) = gdk::Display::default() {
+        gtk::style_context_add_provider_for_display(
+            &display,
+            &provider,
+            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+        );
+    }
+}
+
+// The small capture window of agent.md section 5: a text field and tags, no
+// menus. Enter saves, Shift+Enter starts a new line and Escape closes it.
+// Only one is open at a time.
+fn open_quick_capture(
+    app: &Application,
+    worker: &DbWorker,
+    hints: &[String],
+    hashtag_mode: hashtags::HashtagMode,
+    open_window: &Rc<RefCell<Option<gtk::Window>>>,
+) {
+    if let Some(window) = open_window.borrow().as_ref() {
+        window.present();
+        return;
+    }
+
+    let window = gtk::Window::builder()
+        .application(app)
+        .title("Quick capture")
+        .default_width(480)
+        .resizable(false)
+        .decorated(false)
+        .build();
+    window.add_css_class("quick-capture");
+
+    let content = GtkBox::new(Orientation::Vertical, 8);
+    content.add_css_class("capture-panel");
+
+    let text_view = TextView::new();
+    text_view.add_css_class("note-editor");
+    text_view.set_wrap_mode(WrapMode::WordChar);
+    text_view.set_tooltip_text(Some(
+        "Enter pour sauvegarder, Shift+Enter pour nouvelle ligne, Echap pour fermer.",
+    ));
+    attach_text_autocomplete(
+        &text_view,
+        worker.clone(),
+        hashtags::fragment_at_end,
+        suggest_hashtags,
+        complete_hashtag,
+    );
+    attach_text_autocomplete(
+        &text_view,
+        worker.clone(),
+        wikilinks::fragment_at_end,
+        suggest_note_titles,
+        complete_note_link,
+    );
+
+    let text_scrolled = ScrolledWindow::new();
+    text_scrolled.set_min_content_height(120);
+    text_scrolled.set_child(Some(&text_view));
+
+    let text_overlay = gtk::Overlay::new();
+    text_overlay.set_child(Some(&text_scrolled));
+    let hint = Label::new(Some(&random_hint(hints)));
+    hint.add_css_class("placeholder-hint");
+    hint.set_halign(Align::Start);
+    hint.set_valign(Align::Start);
+    hint.set_margin_top(10);
+    hint.set_margin_start(10);
+    hint.set_can_target(false);
+    text_overlay.add_overlay(&hint);
+    text_view.buffer().connect_changed({
+        let hint = hint.clone();
+        move |buffer| hint.set_visible(buffer.char_count() == 0)
+    });
+
+    let tags = Entry::new();
+    tags.set_placeholder_text(Some("Tags (ex: perso, urgent)"));
+    attach_tag_autocomplete(
+        &tags,
+        worker.clone(),
+        current_tag_fragment,
+        apply_tag_completion,
+    );
+
+    let status = Label::new(None);
+    status.add_css_class("status-label");
+    status.set_halign(Align::Start);
+    status.set_visible(false);
+
+    content.append(&text_overlay);
+    content.append(&tags);
+    content.append(&status);
+    window.set_child(Some(&content));
+
+    let on_save: Rc<dyn Fn()> = Rc::new({
+        let window = window.clone();
+        let text_view = text_view.clone();
+        let tags = tags.clone();
+        let status = status.clone();
+        let worker = worker.clone();
+        move || {
+            let buffer = text_view.buffer();
+            let content = buffer
+                .text(&buffer.start_iter(), &buffer.end_iter(), true)
+                .trim()
+                .to_string();
+            if content.is_empty() {
+                return;
+            }
+
+            let tags = parse_tags(&tags.text());
+            let request =
+                worker.call(move |conn| db::insert_note(conn, &content, &tags, hashtag_mode));
+
+            let window = window.clone();
+            let status = status.clone();
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(_) => {
+                        let _ = Notification::new()
+                            .summary("Memo-Tori")
+                            .body("Note saved")
+                            .show();
+                        if let Some(app) = window.application() {
+                            if app.lookup_action("reload_notes").is_some() {
+                                ActionGroupExt::activate_action(&app, "reload_notes", None);
+                            }
+                        }
+                        window.close();
+                    }
+                    Err(err) => {
+                        status.set_text(&format!("Save failed: {}", err));
+                        status.set_visible(true);
+                    }
+                }
+            });
+        }
+    });
+
+    let text_keys = gtk::EventControllerKey::new();
+    text_keys.connect_key_pressed({
+        let on_save = Rc::clone(&on_save);
+        move |_, key, _, state| {
+            if key == gdk::Key::Return && !state.contains(gdk::ModifierType::SHIFT_MASK) {
+                on_save.as_ref()();
+                return Propagation::Stop;
+            }
+            Propagation::Proceed
+        }
+    });
+    text_view.add_controller(text_keys);
+
+    tags.connect_activate({
+        let on_save = Rc::clone(&on_save);
+        move |_| on_save.as_ref()()
+    });
+
+    let window_keys = gtk::EventControllerKey::new();
+    window_keys.connect_key_pressed({
+        let window = window.clone();
+        move |_, key, _, _| {
+            if key == gdk::Key::Escape {
+                window.close();
+                return Propagation::Stop;
+            }
+            Propagation::Proceed
+        }
+    });
+    window.add_controller(window_keys);
+
+    window.connect_close_request({
+        let open_window = Rc::clone(open_window);
+        move |_| {
+            open_window.borrow_mut().take();
+            Propagation::Proceed
+        }
+    });
+
+    *open_window.borrow_mut() = Some(window.clone());
+    window.present();
+    text_view.grab_focus();
+}
+
+// A page of the main window's stack, or a part of one, and the callback
+// reloading what it lists.
+struct Page {
+    root: GtkBox,
+    refresh: Rc<dyn Fn()>,
+}
+
+// The Notes page also hands out its list and search field, which the tag
+// tree and the export menu work with. `refresh` only reloads the list;
+// `notes_changed` also recounts the saved searches, after notes were added,
+// edited or deleted.
+struct NotesPage {
+    root: GtkBox,
+    refresh: Rc<dyn Fn()>,
+    notes_changed: Rc<dyn Fn()>,
+    model: NoteListModel,
+    selection: gtk::SingleSelection,
+    search_entry: SearchEntry,
+    search_mode: gtk::DropDown,
+    reader: TextView,
+    // A note opened from a link, to select once the list is reloaded.
+    opening_note: Rc<RefCell<Option<String>>>,
+}
+
+struct NoteList {
+    root: ScrolledWindow,
+    model: NoteListModel,
+    selection: gtk::SingleSelection,
+}
+
+struct NoteReader {
+    root: ScrolledWindow,
+    view: TextView,
+    note: Rc<RefCell<Option<ReaderNote>>>,
+}
+
+// The capture page has nothing to reload, so only its widgets are returned.
+fn build_capture_page(
+    worker: &DbWorker,
+    capture_hints: &Rc<Vec<String>>,
+    hashtag_mode: hashtags::HashtagMode,
+    reminder_scheduler: &Rc<ReminderScheduler>,
+    notes_changed: &Rc<dyn Fn()>,
+) -> GtkBox {
+    let capture_panel = GtkBox::new(Orientation::Vertical, 8);
+    capture_panel.add_css_class("capture-panel");
+
+    let capture_label = Label::new(Some("Capture d'idee rapide"));
+    capture_label.set_halign(Align::Start);
+    capture_label.add_css_class("section-title");
+
+    let capture_scrolled = ScrolledWindow::new();
+    capture_scrolled.set_vexpand(false);
+    capture_scrolled.set_min_content_height(220);
+
+    let text_view = TextView::new();
+    text_view.add_css_class("note-editor");
+    text_view.set_wrap_mode(WrapMode::WordChar);
+    text_view.set_vexpand(false);
+    text_view.grab_focus();
+    text_view.set_tooltip_text(Some(
+        "Saisir une idee. Enter pour sauvegarder, Shift+Enter pour nouvelle ligne.",
+    ));
+    capture_scrolled.set_child(Some(&text_view));
+
+    let capture_overlay = gtk::Overlay::new();
+    capture_overlay.set_child(Some(&capture_scrolled));
+
+    let placeholder_label = Label::new(Some(&random_hint(capture_hints.as_ref())));
+    placeholder_label.add_css_class("placeholder-hint");
+    placeholder_label.set_halign(Align::Start);
+    placeholder_label.set_valign(Align::Start);
+    placeholder_label.set_margin_top(10);
+    placeholder_label.set_margin_start(10);
+    placeholder_label.set_xalign(0.0);
+    placeholder_label.set_can_target(false);
+    capture_overlay.add_overlay(&placeholder_label);
+
+    attach_text_autocomplete(
+        &text_view,
+        worker.clone(),
+        hashtags::fragment_at_end,
+        suggest_hashtags,
+        complete_hashtag,
+    );
+    attach_text_autocomplete(
+        &text_view,
+        worker.clone(),
+        wikilinks::fragment_at_end,
+        suggest_note_titles,
+        complete_note_link,
+    );
+
+    let capture_tags = Entry::new();
+    capture_tags.set_placeholder_text(Some("Tags capture (ex: perso, urgent)"));
+    capture_tags.set_tooltip_text(Some("Liste de tags separes par des virgules"));
+    attach_tag_autocomplete(
+        &capture_tags,
+        worker.clone(),
+        current_tag_fragment,
+        apply_tag_completion,
+    );
+
+    let actions = GtkBox::new(Orientation::Horizontal, 8);
+    actions.set_halign(Align::End);
+
+    let capture_remind = Entry::new();
+    capture_remind.set_placeholder_text(Some("Rappel (ex: demain 9h, dans 2h)"));
+    capture_remind.set_tooltip_text(Some("Quand rappeler cette note, vide pour aucun rappel"));
+
+    let save_btn = icon_label_button("document-save-symbolic", "Save");
+    let cancel_btn = icon_label_button("edit-clear-symbolic", "Cancel");
+    save_btn.set_tooltip_text(Some("Sauvegarder la note"));
+    cancel_btn.set_tooltip_text(Some("Effacer la saisie"));
+
+    actions.append(&capture_remind);
+    actions.append(&cancel_btn);
+    actions.append(&save_btn);
+
+    capture_panel.append(&capture_label);
+    capture_panel.append(&capture_overlay);
+    capture_panel.append(&capture_tags);
+    capture_panel.append(&actions);
+
+    let on_save = {
+        let text_view = text_view.clone();
+        let capture_tags = capture_tags.clone();
+        let capture_remind = capture_remind.clone();
+        let worker = worker.clone();
+        let reminder_scheduler = Rc::clone(reminder_scheduler);
+        let notes_changed = Rc::clone(notes_changed);
+        move || {
+            let buffer = text_view.buffer();
+            let start = buffer.start_iter();
+            let end = buffer.end_iter();
+            let content = buffer.text(&start, &end, true);
+            let trimmed = content.trim().to_string();
+
+            if trimmed.is_empty() {
+                return;
+            }
+
+            let due = if capture_remind.text().trim().is_empty() {
+                None
+            } else {
+                match reminder_due(&capture_remind) {
+                    Ok(due) => Some(due),
+                    Err(reason) => {
+                        capture_remind.add_css_class("error");
+                        capture_remind.set_tooltip_text(Some(reason));
+                        return;
+                    }
+                }
+            };
+
+            let tags = parse_tags(&capture_tags.text());
+            let request = worker.call(move |conn| {
+                let id = db::insert_note(conn, &trimmed, &tags, hashtag_mode)?;
+                if let Some(due) = due {
+                    db::add_reminder(conn, &id, due)?;
+                }
+                Ok(())
+            });
+
+            let capture_tags = capture_tags.clone();
+            let capture_remind = capture_remind.clone();
+            let reminder_scheduler = Rc::clone(&reminder_scheduler);
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    buffer.set_text("");
+                    capture_tags.set_text("");
+                    capture_remind.set_text("");
+                    if due.is_some() {
+                        schedule_reminders(&reminder_scheduler);
+                    }
+                    let _ = Notification::new()
+                        .summary("Memo-Tori")
+                        .body("Note saved")
+                        .show();
+                    notes_changed.as_ref()();
+                }
+            });
+        }
+    };
+
+    text_view.buffer().connect_changed({
+        let text_view = text_view.clone();
+        let placeholder_label = placeholder_label.clone();
+        let capture_hints = Rc::clone(capture_hints);
+        let was_empty = Rc::new(RefCell::new(true));
+
+        move |_| {
+            let buffer = text_view.buffer();
+            let start = buffer.start_iter();
+            let end = buffer.end_iter();
+            let is_empty = buffer.text(&start, &end, true).trim().is_empty();
+
+            if is_empty {
+                if !*was_empty.borrow() {
+                    placeholder_label.set_text(&random_hint(capture_hints.as_ref()));
+                }
+                placeholder_label.set_visible(true);
+            } else {
+                placeholder_label.set_visible(false);
+            }
+
+            *was_empty.borrow_mut() = is_empty;
+        }
+    });
+
+    save_btn.connect_clicked({
+        let on_save = on_save.clone();
+        move |_| on_save()
+    });
+
+    capture_remind.connect_changed(|entry| {
+        entry.remove_css_class("error");
+        entry.set_tooltip_text(Some("Quand rappeler cette note, vide pour aucun rappel"));
+    });
+
+    cancel_btn.connect_clicked({
+        let text_view = text_view.clone();
+        move |_| {
+            text_view.buffer().set_text("");
+        }
+    });
+
+    let key_controller = gtk::EventControllerKey::new();
+    key_controller.connect_key_pressed({
+        let on_save = on_save.clone();
+        let text_view = text_view.clone();
+        move |_, key, _, state| {
+            if key == gdk::Key::Return && !state.contains(gdk::ModifierType::SHIFT_MASK) {
+                on_save();
+                return Propagation::Stop;
+            }
+
+            if key == gdk::Key::Escape {
+                text_view.buffer().set_text("");
+                return Propagation::Stop;
+            }
+
+            Propagation::Proceed
+        }
+    });
+    text_view.add_controller(key_controller);
+
+    capture_panel
+}
+
+fn build_notes_page(
+    app: &Application,
+    worker: &DbWorker,
+    stack: &Stack,
+    reminder_scheduler: &Rc<ReminderScheduler>,
+    hashtag_mode: hashtags::HashtagMode,
+    revision_retention: db::RevisionRetention,
+) -> NotesPage {
+    let library_panel = GtkBox::new(Orientation::Vertical, 8);
+    library_panel.add_css_class("library-panel");
+
+    let search_row = GtkBox::new(Orientation::Horizontal, 8);
+    let search_entry = SearchEntry::new();
+    search_entry.set_hexpand(true);
+    search_entry.set_placeholder_text(Some("Search notes (tag:, is:pinned, updated:<7d...)"));
+    search_entry.set_tooltip_text(Some(
+        "Recherche plein texte, combinable avec des operateurs :\n\
+         tag:projet  tag:a|b  -tag:archive\n\
+         created:>2026-01-01  updated:<7d\n\
+         is:pinned  is:deleted  has:link",
+    ));
+    attach_tag_autocomplete(
+        &search_entry,
+        worker.clone(),
+        search_tag_fragment,
+        apply_search_tag_completion,
+    );
+    // Items follow `search_mode_at`.
+    let search_mode = gtk::DropDown::from_strings(&["Words", "FTS", "Fuzzy"]);
+    search_mode.set_tooltip_text(Some(
+        "Words : mots exacts, le dernier en prefixe\n\
+         FTS : syntaxe FTS5 brute (AND, OR, NOT, NEAR, \"phrase\", prefixe*)\n\
+         Fuzzy : fragments de mots et fautes de frappe toleres",
+    ));
+    let status_label = Label::new(Some("0 notes"));
+    status_label.set_halign(Align::End);
+    status_label.add_css_class("status-label");
+    let save_search_btn = Button::from_icon_name("starred-symbolic");
+    save_search_btn.set_tooltip_text(Some("Enregistrer la recherche courante"));
+    save_search_btn.set_action_name(Some("app.save_search"));
+    search_row.append(&search_entry);
+    search_row.append(&search_mode);
+    search_row.append(&save_search_btn);
+    search_row.append(&status_label);
+
+    let note_list = build_note_list(worker);
+    let notes_model = note_list.model.clone();
+    let note_selection = note_list.selection.clone();
+
+    let edit_tags_row = GtkBox::new(Orientation::Horizontal, 8);
+    let selected_tags_entry = Entry::new();
+    selected_tags_entry.set_hexpand(true);
+    selected_tags_entry.set_placeholder_text(Some("Tags de la note selectionnee"));
+    attach_tag_autocomplete(
+        &selected_tags_entry,
+        worker.clone(),
+        current_tag_fragment,
+        apply_tag_completion,
+    );
+    let apply_tags_btn = icon_label_button("emblem-ok-symbolic", "Apply tags");
+    apply_tags_btn.set_tooltip_text(Some("Appliquer les tags a la note selectionnee"));
+    let save_note_btn = icon_label_button("document-save-symbolic", "Save note");
+    save_note_btn.set_tooltip_text(Some("Sauvegarder les modifications de la note"));
+    edit_tags_row.append(&selected_tags_entry);
+    edit_tags_row.append(&apply_tags_btn);
+    let delete_note_btn = icon_label_button("user-trash-symbolic", "Delete");
+    delete_note_btn.set_tooltip_text(Some("Mettre la note selectionnee a la corbeille (Suppr)"));
+    delete_note_btn.set_action_name(Some("app.delete_note"));
+    let history_btn = gtk::ToggleButton::new();
+    history_btn.set_child(Some(&{
+        let content = GtkBox::new(Orientation::Horizontal, 6);
+        content.append(&gtk::Image::from_icon_name("document-open-recent-symbolic"));
+        content.append(&Label::new(Some("History")));
+        content
+    }));
+    history_btn.set_tooltip_text(Some("Afficher les versions precedentes de la note"));
+    let preview_btn = gtk::ToggleButton::new();
+    preview_btn.set_child(Some(&{
+        let content = GtkBox::new(Orientation::Horizontal, 6);
+        content.append(&gtk::Image::from_icon_name("view-reveal-symbolic"));
+        content.append(&Label::new(Some("Preview")));
+        content
+    }));
+    preview_btn.set_tooltip_text(Some("Basculer entre l'edition et l'apercu Markdown"));
+    let remind_btn = build_remind_button(worker, &note_selection, reminder_scheduler);
+    edit_tags_row.append(&preview_btn);
+    edit_tags_row.append(&remind_btn);
+    edit_tags_row.append(&save_note_btn);
+    edit_tags_row.append(&history_btn);
+    edit_tags_row.append(&delete_note_btn);
+
+    let selected_tags_chips = GtkBox::new(Orientation::Horizontal, 4);
+    selected_tags_chips.set_halign(Align::Start);
+    show_tag_chips(&selected_tags_chips, &[]);
+
+    let paned = Paned::new(Orientation::Horizontal);
+    paned.set_wide_handle(true);
+    paned.set_resize_start_child(true);
+    paned.set_shrink_start_child(false);
+
+    let note_reader = build_note_reader(worker, &note_selection, &preview_btn, hashtag_mode);
+    let reader = note_reader.view.clone();
+    let reader_note = Rc::clone(&note_reader.note);
+
+    let backlinks_box = GtkBox::new(Orientation::Vertical, 2);
+    backlinks_box.set_margin_top(6);
+    backlinks_box.set_visible(false);
+
+    let opening_note = Rc::new(RefCell::new(None::<String>));
+
+    let refresh_notes: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let opening_note = Rc::clone(&opening_note);
+        let search_entry = search_entry.clone();
+        let search_mode = search_mode.clone();
+        let notes_model = notes_model.clone();
+        let note_selection = note_selection.clone();
+        let reader = reader.clone();
+        let status_label = status_label.clone();
+        let selected_tags_chips = selected_tags_chips.clone();
+        let selected_tags_entry = selected_tags_entry.clone();
+
+        Rc::new(move || {
+            let mode = search_mode_at(search_mode.selected());
+            let now = db::unix_seconds().unwrap_or_default();
+            let search = query::parse(&search_entry.text(), mode, now);
+            let selected_id = selected_note(&note_selection).map(|note| note.id);
+
+            let worker = worker.clone();
+            let opening_note = Rc::clone(&opening_note);
+            let notes_model = notes_model.clone();
+            let note_selection = note_selection.clone();
+            let reader = reader.clone();
+            let status_label = status_label.clone();
+            let selected_tags_chips = selected_tags_chips.clone();
+            let selected_tags_entry = selected_tags_entry.clone();
+            glib::spawn_future_local(async move {
+                let Some(result) = notes_model.reload(search).await else {
+                    return;
+                };
+
+                match result {
+                    Ok(total) => {
+                        let listed = notes_model.filter().mode;
+                        if listed == QueryMode::Fuzzy && mode != QueryMode::Fuzzy {
+                            status_label.set_text(&format!("{} notes (fuzzy)", total));
+                        } else {
+                            status_label.set_text(&format!("{} notes", total));
+                        }
+
+                        if total > 0 {
+                            // Keep the same note selected when it is
+                            // still listed, asking where it is when its
+                            // page is not loaded; start at the first row
+                            // when nothing was selected.
+                            let target = opening_note.borrow_mut().take().or(selected_id);
+                            match target {
+                                None => note_selection.set_selected(0),
+                                Some(note_id) => match notes_model.position_of(&note_id) {
+                                    Some(position) => note_selection.set_selected(position),
+                                    None => {
+                                        let query = notes_model.filter();
+                                        let request = worker.call(move |conn| {
+                                            db::note_position(conn, &query, &note_id)
+                                        });
+                                        let note_selection = note_selection.clone();
+                                        glib::spawn_future_local(async move {
+                                            if let Ok(Some(position)) = request.await {
+                                                note_selection.set_selected(position);
+                                            }
+                                        });
+                                    }
+                                },
+                            }
+                        } else {
+                            note_selection.set_selected(gtk::INVALID_LIST_POSITION);
+                            reader.buffer().set_text("No notes yet.");
+                            show_tag_chips(&selected_tags_chips, &[]);
+                            selected_tags_entry.set_text("");
+                        }
+                    }
+                    Err(err) => {
+                        status_label.set_text("Search error");
+                        reader
+                            .buffer()
+                            .set_text(&format!("Search failed:\n{}", err));
+                    }
+                }
+            });
+        })
+    };
+
+    let saved_searches = build_saved_searches(
+        app,
+        worker,
+        stack,
+        &search_entry,
+        &search_mode,
+        &save_search_btn,
+        &refresh_notes,
+    );
+
+    // Reloads after notes were added, edited or deleted: the list and the
+    // saved search counts.
+    let notes_changed: Rc<dyn Fn()> = {
+        let refresh_notes = Rc::clone(&refresh_notes);
+        let refresh_saved_searches = Rc::clone(&saved_searches.refresh);
+        Rc::new(move || {
+            refresh_notes.as_ref()();
+            refresh_saved_searches.as_ref()();
+        })
+    };
+
+    notes_model.connect_stale({
+        let refresh_notes = Rc::clone(&refresh_notes);
+        move |_| refresh_notes.as_ref()()
+    });
+
+    // Keystrokes in the search fields are coalesced so only the final
+    // query of a burst of typing reaches the database.
+    let schedule_refresh_notes: Rc<dyn Fn()> = {
+        let refresh_notes = Rc::clone(&refresh_notes);
+        let pending = Rc::new(RefCell::new(None::<glib::SourceId>));
+        Rc::new(move || {
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+
+            let source = glib::timeout_add_local_once(Duration::from_millis(150), {
+                let refresh_notes = Rc::clone(&refresh_notes);
+                let pending = Rc::clone(&pending);
+                move || {
+                    pending.borrow_mut().take();
+                    refresh_notes.as_ref()();
+                }
+            });
+            *pending.borrow_mut() = Some(source);
+        })
+    };
+
+    let selection_request = LatestRequest::default();
+
+    note_selection.connect_selected_item_notify({
+        let worker = worker.clone();
+        let notes_model = notes_model.clone();
+        let reader = reader.clone();
+        let selected_tags_chips = selected_tags_chips.clone();
+        let selected_tags_entry = selected_tags_entry.clone();
+        let backlinks_box = backlinks_box.clone();
+        let preview_btn = preview_btn.clone();
+        let reader_note = Rc::clone(&reader_note);
+        move |selection| {
+            let Some(note_id) = selected_note(selection).map(|note| note.id) else {
+                *reader_note.borrow_mut() = None;
+                reader.buffer().set_text("No note selected.");
+                show_tag_chips(&selected_tags_chips, &[]);
+                selected_tags_entry.set_text("");
+                show_backlinks(&backlinks_box, &[]);
+                return;
+            };
+
+            let query = notes_model.filter();
+            let request = worker.call_latest(&selection_request, move |conn| {
+                Ok((
+                    db::get_note_content_any(conn, &note_id),
+                    db::get_note_tag_styles(conn, &note_id),
+                    db::note_matches(conn, &note_id, &query).unwrap_or_default(),
+                    db::note_link_targets(conn, &note_id).unwrap_or_default(),
+                    db::list_backlinks(conn, &note_id).unwrap_or_default(),
+                ))
+            });
+
+            let reader = reader.clone();
+            let selected_tags_chips = selected_tags_chips.clone();
+            let selected_tags_entry = selected_tags_entry.clone();
+            let backlinks_box = backlinks_box.clone();
+            let preview_btn = preview_btn.clone();
+            let reader_note = Rc::clone(&reader_note);
+            glib::spawn_future_local(async move {
+                let Some(Ok((content, tags, matches, links, backlinks))) = request.await else {
+                    return;
+                };
+
+                show_backlinks(&backlinks_box, &backlinks);
+                *reader_note.borrow_mut() = None;
+                match content {
+                    Ok(Some(content)) => {
+                        let note = ReaderNote { content, links };
+                        if preview_btn.is_active() {
+                            show_note_preview(&reader, &note);
+                        } else {
+                            show_note_source(&reader, &note, &matches);
+                        }
+                        *reader_note.borrow_mut() = Some(note);
+                    }
+                    Ok(None) => reader.buffer().set_text("Note not found."),
+                    Err(err) => reader
+                        .buffer()
+                        .set_text(&format!("Failed to load note:\n{}", err)),
+                }
+
+                match tags {
+                    Ok(tags) => {
+                        show_tag_chips(&selected_tags_chips, &tags);
+                        let names: Vec<&str> = tags.iter().map(|tag| tag.name.as_str()).collect();
+                        selected_tags_entry.set_text(&names.join(", "));
+                    }
+                    Err(_) => {
+                        show_tag_chips(&selected_tags_chips, &[]);
+                        selected_tags_entry.set_text("");
+                    }
+                }
+            });
+        }
+    });
+
+    search_entry.connect_search_changed({
+        let schedule_refresh_notes = Rc::clone(&schedule_refresh_notes);
+        move |_| schedule_refresh_notes.as_ref()()
+    });
+
+    search_mode.connect_selected_notify({
+        let refresh_notes = Rc::clone(&refresh_notes);
+        move |_| refresh_notes.as_ref()()
+    });
+
+    apply_tags_btn.connect_clicked({
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let selected_tags_entry = selected_tags_entry.clone();
+        let selected_tags_chips = selected_tags_chips.clone();
+        let notes_changed = Rc::clone(&notes_changed);
+        move |_| {
+            let Some(note_id) = selected_note(&note_selection).map(|note| note.id) else {
+                return;
+            };
+
+            let tags = parse_tags(&selected_tags_entry.text());
+            let request = worker.call(move |conn| {
+                db::replace_note_tags(conn, &note_id, &tags)?;
+                db::get_note_tag_styles(conn, &note_id)
+            });
+
+            let selected_tags_chips = selected_tags_chips.clone();
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                if let Ok(tags) = request.await {
+                    show_tag_chips(&selected_tags_chips, &tags);
+                    notes_changed.as_ref()();
+                }
+            });
+        }
+    });
+
+    save_note_btn.connect_clicked({
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let reader = reader.clone();
+        let preview_btn = preview_btn.clone();
+        let reader_note = Rc::clone(&reader_note);
+        let notes_changed = Rc::clone(&notes_changed);
+        move |_| {
+            let Some(note_id) = selected_note(&note_selection).map(|note| note.id) else {
+                return;
+            };
+
+            // The preview buffer holds rendered text; save the source.
+            let content = if preview_btn.is_active() {
+                let Some(note) = reader_note
+                    .borrow()
+                    .as_ref()
+                    .map(|note| note.content.clone())
+                else {
+                    return;
+                };
+                note
+            } else {
+                let buffer = reader.buffer();
+                let start = buffer.start_iter();
+                let end = buffer.end_iter();
+                buffer.text(&start, &end, true).to_string()
+            };
+
+            let request = worker.call(move |conn| {
+                db::update_note_content(conn, &note_id, content.trim(), hashtag_mode)?;
+                db::prune_revisions(conn, &note_id, revision_retention)
+            });
+
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    let _ = Notification::new()
+                        .summary("Memo-Tori")
+                        .body("Note updated")
+                        .show();
+                    notes_changed.as_ref()();
+                }
+            });
+        }
+    });
+
+    let history_revealer = build_history_panel(
+        worker,
+        &note_selection,
+        &history_btn,
+        revision_retention,
+        &notes_changed,
+    );
+
+    let reader_column = GtkBox::new(Orientation::Vertical, 0);
+    reader_column.append(&note_reader.root);
+    reader_column.append(&backlinks_box);
+    reader_column.append(&history_revealer);
+
+    paned.set_start_child(Some(&note_list.root));
+    paned.set_end_child(Some(&reader_column));
+    paned.set_position(320);
+
+    paned.set_hexpand(true);
+    let library_body = GtkBox::new(Orientation::Horizontal, 8);
+    library_body.append(&saved_searches.root);
+    library_body.append(&paned);
+
+    library_panel.append(&search_row);
+    library_panel.append(&edit_tags_row);
+    library_panel.append(&selected_tags_chips);
+    library_panel.append(&library_body);
+
+    NotesPage {
+        root: library_panel,
+        refresh: refresh_notes,
+        notes_changed,
+        model: notes_model,
+        selection: note_selection,
+        search_entry,
+        search_mode,
+        reader,
+        opening_note,
+    }
+}
+
+// The list's context menu and Delete key go through the app actions, which
+// act on the selected note.
+fn build_note_list(worker: &DbWorker) -> NoteList {
+    let notes_model = NoteListModel::new(worker.clone());
+    let note_selection = gtk::SingleSelection::new(Some(notes_model.clone()));
+    note_selection.set_autoselect(false);
+    note_selection.set_can_unselect(true);
+
+    let list_view = gtk::ListView::new(
+        Some(note_selection.clone()),
+        None::<gtk::SignalListItemFactory>,
+    );
+
+    let note_menu = gio::Menu::new();
+    note_menu.append(Some("Pin / Unpin"), Some("app.toggle_pin"));
+    note_menu.append(Some("Move to trash"), Some("app.delete_note"));
+
+    let note_context_menu = gtk::PopoverMenu::from_model(Some(&note_menu));
+    note_context_menu.set_has_arrow(false);
+    note_context_menu.set_parent(&list_view);
+
+    let note_factory = gtk::SignalListItemFactory::new();
+    note_factory.connect_setup({
+        let list_view = list_view.clone();
+        let note_selection = note_selection.clone();
+        let note_context_menu = note_context_menu.clone();
+        move |_, list_item| {
+            let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+                return;
+            };
+
+            let container = build_note_row();
+
+            let row_click = gtk::GestureClick::new();
+            row_click.set_button(gdk::BUTTON_SECONDARY);
+            row_click.connect_pressed({
+                let list_item = list_item.downgrade();
+                let container = container.clone();
+                let list_view = list_view.clone();
+                let note_selection = note_selection.clone();
+                let note_context_menu = note_context_menu.clone();
+                move |_, _, x, y| {
+                    let Some(list_item) = list_item.upgrade() else {
+                        return;
+                    };
+                    let position = list_item.position();
+                    if position == gtk::INVALID_LIST_POSITION {
+                        return;
+                    }
+
+                    note_selection.set_selected(position);
+                    let point = gtk::graphene::Point::new(x as f32, y as f32);
+                    let Some(point) = container.compute_point(&list_view, &point) else {
+                        return;
+                    };
+                    note_context_menu.set_pointing_to(Some(&gdk::Rectangle::new(
+                        point.x() as i32,
+                        point.y() as i32,
+                        1,
+                        1,
+                    )));
+                    note_context_menu.popup();
+                }
+            });
+            container.add_controller(row_click);
+
+            list_item.set_child(Some(&container));
+        }
+    });
+    note_factory.connect_bind(|_, list_item| {
+        let Some(list_item) = list_item.downcast_ref::<gtk::ListItem>() else {
+            return;
+        };
+        let Some(container) = list_item.child().and_downcast::<GtkBox>() else {
+            return;
+        };
+
+        let note = list_item
+            .item()
+            .and_downcast::<NoteObject>()
+            .and_then(|object| object.item());
+        bind_note_row(&container, note.as_ref());
+    });
+    list_view.set_factory(Some(&note_factory));
+
+    let list_scrolled = ScrolledWindow::new();
+    list_scrolled.set_hexpand(true);
+    list_scrolled.set_vexpand(true);
+    list_scrolled.set_min_content_width(280);
+    list_scrolled.set_child(Some(&list_view));
+
+    let list_key_controller = gtk::EventControllerKey::new();
+    list_key_controller.connect_key_pressed({
+        let list_view = list_view.clone();
+        move |_, key, _, _| {
+            if key == gdk::Key::Delete || key == gdk::Key::KP_Delete {
+                let _ = list_view.activate_action("app.delete_note", None);
+                return Propagation::Stop;
+            }
+
+            Propagation::Proceed
+        }
+    });
+    list_view.add_controller(list_key_controller);
+
+    NoteList {
+        root: list_scrolled,
+        model: notes_model,
+        selection: note_selection,
+    }
+}
+
+fn build_remind_button(
+    worker: &DbWorker,
+    note_selection: &gtk::SingleSelection,
+    reminder_scheduler: &Rc<ReminderScheduler>,
+) -> Button {
+    let remind_entry = Entry::new();
+    remind_entry.set_placeholder_text(Some("demain 9h, dans 2h, lundi 14:30"));
+    let remind_status = Label::new(None);
+    remind_status.add_css_class("note-meta");
+    remind_status.set_halign(Align::Start);
+    let remind_content = GtkBox::new(Orientation::Vertical, 6);
+    remind_content.append(&remind_entry);
+    remind_content.append(&remind_status);
+    let remind_btn = icon_label_button("alarm-symbolic", "Remind");
+    let remind_popover = Popover::new();
+    remind_popover.set_parent(&remind_btn);
+    remind_popover.set_child(Some(&remind_content));
+    remind_btn.connect_clicked({
+        let remind_popover = remind_popover.clone();
+        let remind_entry = remind_entry.clone();
+        move |_| {
+            remind_popover.popup();
+            remind_entry.grab_focus();
+        }
+    });
+    remind_btn.set_tooltip_text(Some("Programmer un rappel pour la note selectionnee"));
+
+    remind_entry.connect_activate({
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let remind_popover = remind_popover.clone();
+        let remind_status = remind_status.clone();
+        let reminder_scheduler = Rc::clone(reminder_scheduler);
+        move |entry| {
+            let Some(note_id) = selected_note(&note_selection).map(|note| note.id) else {
+                remind_status.set_text("Aucune note selectionnee");
+                return;
+            };
+            let due = match reminder_due(entry) {
+                Ok(due) => due,
+                Err(reason) => {
+                    remind_status.set_text(reason);
+                    return;
+                }
+            };
+
+            let request = worker.call(move |conn| db::add_reminder(conn, &note_id, due));
+
+            let entry = entry.clone();
+            let remind_popover = remind_popover.clone();
+            let remind_status = remind_status.clone();
+            let reminder_scheduler = Rc::clone(&reminder_scheduler);
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(_) => {
+                        entry.set_text("");
+                        remind_status.set_text("");
+                        remind_popover.popdown();
+                        let _ = Notification::new()
+                            .summary("Memo-Tori")
+                            .body(&format!("Reminder set for {}", format_local_time(due)))
+                            .show();
+                        schedule_reminders(&reminder_scheduler);
+                    }
+                    Err(err) => remind_status.set_text(&err.to_string()),
+                }
+            });
+        }
+    });
+
+    remind_btn
+}
+
+// The reader of the Notes page: the selected note as editable Markdown, or
+// rendered while `preview_btn` is active. Clicks follow links and tick
+// checklist items.
+fn build_note_reader(
+    worker: &DbWorker,
+    note_selection: &gtk::SingleSelection,
+    preview_btn: &gtk::ToggleButton,
+    hashtag_mode: hashtags::HashtagMode,
+) -> NoteReader {
+    let reader = TextView::new();
+    reader.add_css_class("reader");
+    reader.add_css_class("note-editor");
+    reader.set_editable(true);
+    reader.set_cursor_visible(true);
+    reader.set_wrap_mode(WrapMode::WordChar);
+
+    let reader_scrolled = ScrolledWindow::new();
+    reader_scrolled.set_hexpand(true);
+    reader_scrolled.set_vexpand(true);
+    reader_scrolled.set_min_content_width(420);
+    reader_scrolled.set_child(Some(&reader));
+
+    attach_text_autocomplete(
+        &reader,
+        worker.clone(),
+        wikilinks::fragment_at_end,
+        suggest_note_titles,
+        complete_note_link,
+    );
+
+    // A click on a `[[link]]` opens its note, and one on a Markdown link
+    // in the preview opens its address, unless the click ends a selection.
+    let link_click = gtk::GestureClick::new();
+    link_click.connect_released({
+        let reader = reader.clone();
+        move |_, n_press, x, y| {
+            if n_press != 1 || reader.buffer().has_selection() {
+                return;
+            }
+            let (x, y) =
+                reader.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+            let Some(iter) = reader.iter_at_location(x, y) else {
+                return;
+            };
+            if let Some(target_id) = note_link_at(&iter) {
+                let _ = reader.activate_action("app.open_note", Some(&target_id.to_variant()));
+            } else if let Some(url) = web_link_at(&iter) {
+                let _ = gio::AppInfo::launch_default_for_uri(&url, None::<&gio::AppLaunchContext>);
+            }
+        }
+    });
+    reader.add_controller(link_click);
+
+    let link_hover = gtk::EventControllerMotion::new();
+    link_hover.connect_motion({
+        let reader = reader.clone();
+        move |_, x, y| {
+            let (x, y) =
+                reader.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+            let on_link = reader.iter_at_location(x, y).is_some_and(|iter| {
+                note_link_at(&iter).or(web_link_at(&iter)).is_some()
+                    || task_line_at(&iter).is_some()
+            });
+            reader.set_cursor_from_name(Some(if on_link { "pointer" } else { "text" }));
+        }
+    });
+    reader.add_controller(link_hover);
+
+    let reader_note: Rc<RefCell<Option<ReaderNote>>> = Rc::new(RefCell::new(None));
+
+    // Switching to the preview renders the text as edited so far; switching
+    // back shows that text again, so unsaved edits survive the round trip.
+    preview_btn.connect_toggled({
+        let reader = reader.clone();
+        let reader_note = Rc::clone(&reader_note);
+        move |preview_btn| {
+            let mut reader_note = reader_note.borrow_mut();
+            let Some(note) = reader_note.as_mut() else {
+                return;
+            };
+            if preview_btn.is_active() {
+                let buffer = reader.buffer();
+                note.content = buffer
+                    .text(&buffer.start_iter(), &buffer.end_iter(), true)
+                    .to_string();
+                show_note_preview(&reader, note);
+            } else {
+                show_note_source(&reader, note, &[]);
+            }
+        }
+    });
+
+    // A click on a checkbox in the preview checks or unchecks its item
+    // and saves the note.
+    let task_click = gtk::GestureClick::new();
+    task_click.connect_released({
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let reader = reader.clone();
+        let reader_note = Rc::clone(&reader_note);
+        move |_, n_press, x, y| {
+            if n_press != 1 || reader.buffer().has_selection() {
+                return;
+            }
+            let (x, y) =
+                reader.window_to_buffer_coords(gtk::TextWindowType::Widget, x as i32, y as i32);
+            let Some(line) = reader
+                .iter_at_location(x, y)
+                .as_ref()
+                .and_then(task_line_at)
+            else {
+                return;
+            };
+            let Some(note_id) = selected_note(&note_selection).map(|note| note.id) else {
+                return;
+            };
+
+            let content = {
+                let mut reader_note = reader_note.borrow_mut();
+                let Some(note) = reader_note.as_mut() else {
+                    return;
+                };
+                let Some(task) = markdown::tasks(&note.content)
+                    .into_iter()
+                    .find(|task| task.line == line)
+                else {
+                    return;
+                };
+                let Some(content) = markdown::set_task_done(&note.content, line, !task.done) else {
+                    return;
+                };
+                note.content = content.clone();
+                show_note_preview(&reader, note);
+                content
+            };
+
+            let request = worker
+                .call(move |conn| db::update_note_content(conn, &note_id, &content, hashtag_mode));
+
+            let reader = reader.clone();
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    let _ = reader.activate_action("app.reload_notes", None);
+                }
+            });
+        }
+    });
+    reader.add_controller(task_click);
+
+    NoteReader {
+        root: reader_scrolled,
+        view: reader,
+        note: reader_note,
+    }
+}
+
+// The panel under the reader, shown while `history_btn` is active: a diff
+// between two versions of the selected note, and a button restoring the
+// older one.
+fn build_history_panel(
+    worker: &DbWorker,
+    note_selection: &gtk::SingleSelection,
+    history_btn: &gtk::ToggleButton,
+    revision_retention: db::RevisionRetention,
+    notes_changed: &Rc<dyn Fn()>,
+) -> gtk::Revealer {
+    let history_panel = GtkBox::new(Orientation::Vertical, 6);
+    history_panel.set_margin_top(8);
+
+    let history_controls = GtkBox::new(Orientation::Horizontal, 8);
+    let history_from = gtk::DropDown::from_strings(&[]);
+    history_from.set_tooltip_text(Some("Version de depart"));
+    let history_to = gtk::DropDown::from_strings(&[]);
+    history_to.set_tooltip_text(Some("Version comparee"));
+    let history_arrow = Label::new(Some("→"));
+    let restore_revision_btn = icon_label_button("edit-undo-symbolic", "Restore");
+    restore_revision_btn.set_tooltip_text(Some("Restaurer la version de depart"));
+    restore_revision_btn.set_hexpand(true);
+    restore_revision_btn.set_halign(Align::End);
+    history_controls.append(&history_from);
+    history_controls.append(&history_arrow);
+    history_controls.append(&history_to);
+    history_controls.append(&restore_revision_btn);
+
+    let diff_view = TextView::new();
+    diff_view.add_css_class("reader");
+    diff_view.add_css_class("note-editor");
+    diff_view.set_editable(false);
+    diff_view.set_cursor_visible(false);
+    diff_view.set_wrap_mode(WrapMode::WordChar);
+
+    let diff_scrolled = ScrolledWindow::new();
+    diff_scrolled.set_hexpand(true);
+    diff_scrolled.set_min_content_height(200);
+    diff_scrolled.set_child(Some(&diff_view));
+
+    history_panel.append(&history_controls);
+    history_panel.append(&diff_scrolled);
+
+    let history_revealer = gtk::Revealer::new();
+    history_revealer.set_transition_type(gtk::RevealerTransitionType::SlideUp);
+    history_revealer.set_child(Some(&history_panel));
+    history_btn
+        .bind_property("active", &history_revealer, "reveal-child")
+        .sync_create()
+        .build();
+
+    // Index 0 is always the current content; saved revisions follow,
+    // newest first.
+    let history_state = Rc::new(RefCell::new(Vec::<(Option<i64>, String)>::new()));
+
+    let render_history: Rc<dyn Fn()> = {
+        let history_state = Rc::clone(&history_state);
+        let history_from = history_from.clone();
+        let history_to = history_to.clone();
+        let diff_view = diff_view.clone();
+        let restore_revision_btn = restore_revision_btn.clone();
+        Rc::new(move || {
+            let versions = history_state.borrow();
+            let from = versions.get(history_from.selected() as usize);
+            let to = versions.get(history_to.selected() as usize);
+
+            restore_revision_btn.set_sensitive(from.is_some_and(|(id, _)| id.is_some()));
+
+            match (from, to) {
+                (Some((_, old)), Some((_, new))) => render_diff(&diff_view, old, new),
+                _ => diff_view.buffer().set_text("No previous versions."),
+            }
+        })
+    };
+
+    let history_request = LatestRequest::default();
+
+    let refresh_history: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let history_btn = history_btn.clone();
+        let history_state = Rc::clone(&history_state);
+        let history_from = history_from.clone();
+        let history_to = history_to.clone();
+        let render_history = Rc::clone(&render_history);
+        Rc::new(move || {
+            if !history_btn.is_active() {
+                return;
+            }
+
+            let note_id = selected_note(&note_selection).map(|note| note.id);
+            let request = worker.call_latest(&history_request, move |conn| {
+                let Some(note_id) = note_id else {
+                    return Ok((None, Vec::new()));
+                };
+                Ok((
+                    db::get_note_content_any(conn, &note_id)?,
+                    db::list_revisions(conn, &note_id)?,
+                ))
+            });
+
+            let history_state = Rc::clone(&history_state);
+            let history_from = history_from.clone();
+            let history_to = history_to.clone();
+            let render_history = Rc::clone(&render_history);
+            glib::spawn_future_local(async move {
+                let Some(result) = request.await else {
+                    return;
+                };
+                let (current, revisions) = result.unwrap_or_default();
+
+                let mut versions = Vec::new();
+                let mut labels = Vec::new();
+
+                if let Some(content) = current {
+                    versions.push((None, content));
+                    labels.push("Current".to_string());
+                }
+                for revision in revisions {
+                    labels.push(format_local_time(revision.created_at));
+                    versions.push((Some(revision.id), revision.content));
+                }
+
+                *history_state.borrow_mut() = versions;
+
+                let labels: Vec<&str> = labels.iter().map(String::as_str).collect();
+                history_from.set_model(Some(&gtk::StringList::new(&labels)));
+                history_to.set_model(Some(&gtk::StringList::new(&labels)));
+                history_from.set_selected(if labels.len() > 1 { 1 } else { 0 });
+                history_to.set_selected(0);
+                render_history.as_ref()();
+            });
+        })
+    };
+
+    for dropdown in [&history_from, &history_to] {
+        dropdown.connect_selected_notify({
+            let render_history = Rc::clone(&render_history);
+            move |_| render_history.as_ref()()
+        });
+    }
+
+    history_btn.connect_toggled({
+        let refresh_history = Rc::clone(&refresh_history);
+        move |_| refresh_history.as_ref()()
+    });
+
+    note_selection.connect_selected_item_notify({
+        let refresh_history = Rc::clone(&refresh_history);
+        move |_| refresh_history.as_ref()()
+    });
+
+    restore_revision_btn.connect_clicked({
+        let worker = worker.clone();
+        let history_state = Rc::clone(&history_state);
+        let history_from = history_from.clone();
+        let notes_changed = Rc::clone(notes_changed);
+        move |_| {
+            let revision_id = history_state
+                .borrow()
+                .get(history_from.selected() as usize)
+                .and_then(|(id, _)| *id);
+
+            let Some(revision_id) = revision_id else {
+                return;
+            };
+
+            let request = worker.call(move |conn| {
+                let note_id = db::restore_revision(conn, revision_id)?;
+                db::prune_revisions(conn, &note_id, revision_retention)
+            });
+
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    let _ = Notification::new()
+                        .summary("Memo-Tori")
+                        .body("Version restored")
+                        .show();
+                    notes_changed.as_ref()();
+                }
+            });
+        }
+    });
+
+    history_revealer
+}
+
+// The saved searches sidebar, and the popover of `save_search_btn` naming a
+// new one. A click on a saved search puts it back in the search field.
+fn build_saved_searches(
+    app: &Application,
+    worker: &DbWorker,
+    stack: &Stack,
+    search_entry: &SearchEntry,
+    search_mode: &gtk::DropDown,
+    save_search_btn: &Button,
+    refresh_notes: &Rc<dyn Fn()>,
+) -> Page {
+    let save_search_popover = Popover::new();
+    save_search_popover.set_parent(save_search_btn);
+    let save_search_box = GtkBox::new(Orientation::Horizontal, 6);
+    let save_search_name = Entry::new();
+    save_search_name.set_placeholder_text(Some("Nom de la recherche"));
+    let save_search_confirm = Button::with_label("Save");
+    save_search_box.append(&save_search_name);
+    save_search_box.append(&save_search_confirm);
+    save_search_popover.set_child(Some(&save_search_box));
+
+    let saved_sidebar = GtkBox::new(Orientation::Vertical, 6);
+    saved_sidebar.set_size_request(180, -1);
+    let saved_title = Label::new(Some("Saved searches"));
+    saved_title.set_halign(Align::Start);
+    saved_title.add_css_class("section-title");
+    let saved_list = ListBox::new();
+    saved_list.set_selection_mode(gtk::SelectionMode::None);
+    saved_list.set_activate_on_single_click(true);
+    saved_list.set_placeholder(Some(&{
+        let hint = Label::new(Some("Aucune recherche enregistree"));
+        hint.add_css_class("placeholder-hint");
+        hint.set_wrap(true);
+        hint.set_margin_top(8);
+        hint.set_margin_bottom(8);
+        hint
+    }));
+    let saved_scrolled = ScrolledWindow::new();
+    saved_scrolled.set_vexpand(true);
+    saved_scrolled.set_hscrollbar_policy(gtk::PolicyType::Never);
+    saved_scrolled.set_child(Some(&saved_list));
+    saved_sidebar.append(&saved_title);
+    saved_sidebar.append(&saved_scrolled);
+
+    let saved_state = Rc::new(RefCell::new(Vec::<db::SavedSearch>::new()));
+    let saved_request = LatestRequest::default();
+
+    // Counts are taken again when searches are saved or deleted and after
+    // notes change, not on every list refresh, so they never hold up the
+    // search being typed.
+    let refresh_saved_searches: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let saved_list = saved_list.clone();
+        let saved_state = Rc::clone(&saved_state);
+
+        Rc::new(move || {
+            let request = worker.call_latest(&saved_request, |conn| {
+                let now = db::unix_seconds()?;
+                let searches = db::list_saved_searches(conn)?;
+                Ok(searches
+                    .into_iter()
+                    .map(|search| {
+                        let parsed = query::parse(&search.query, search.mode, now);
+                        let count = db::count_listed_notes(conn, &parsed).ok();
+                        (search, count)
+                    })
+                    .collect::<Vec<_>>())
+            });
+
+            let saved_list = saved_list.clone();
+            let saved_state = Rc::clone(&saved_state);
+            glib::spawn_future_local(async move {
+                let Some(Ok(searches)) = request.await else {
+                    return;
+                };
+
+                clear_listbox(&saved_list);
+                for (search, count) in &searches {
+                    saved_list.append(&build_saved_search_row(search, *count));
+                }
+                *saved_state.borrow_mut() =
+                    searches.into_iter().map(|(search, _)| search).collect();
+            });
+        })
+    };
+
+    // Relative dates such as `updated:<7d` move with the clock, so the
+    // counts are also taken again every few minutes.
+    glib::timeout_add_local(SAVED_SEARCH_COUNT_INTERVAL, {
+        let refresh_saved_searches = Rc::clone(&refresh_saved_searches);
+        move || {
+            refresh_saved_searches.as_ref()();
+            glib::ControlFlow::Continue
+        }
+    });
+
+    saved_list.connect_row_activated({
+        let saved_state = Rc::clone(&saved_state);
+        let search_entry = search_entry.clone();
+        let search_mode = search_mode.clone();
+        let refresh_notes = Rc::clone(refresh_notes);
+        move |_, row| {
+            let Some(search) = usize::try_from(row.index())
+                .ok()
+                .and_then(|index| saved_state.borrow().get(index).cloned())
+            else {
+                return;
+            };
+
+            search_mode.set_selected(search_mode_position(search.mode));
+            search_entry.set_text(&search.query);
+            search_entry.set_position(-1);
+            refresh_notes.as_ref()();
+        }
+    });
+
+    let on_save_search = {
+        let worker = worker.clone();
+        let search_entry = search_entry.clone();
+        let search_mode = search_mode.clone();
+        let save_search_name = save_search_name.clone();
+        let save_search_popover = save_search_popover.clone();
+        let refresh_saved_searches = Rc::clone(&refresh_saved_searches);
+        move || {
+            let name = save_search_name.text().trim().to_string();
+            if name.is_empty() {
+                return;
+            }
+
+            let query = search_entry.text().to_string();
+            let mode = search_mode_at(search_mode.selected());
+            let request = worker.call(move |conn| db::save_search(conn, &name, &query, mode));
+
+            save_search_popover.popdown();
+            let refresh_saved_searches = Rc::clone(&refresh_saved_searches);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    refresh_saved_searches.as_ref()();
+                }
+            });
+        }
+    };
+
+    save_search_confirm.connect_clicked({
+        let on_save_search = on_save_search.clone();
+        move |_| on_save_search()
+    });
+
+    save_search_name.connect_activate({
+        let on_save_search = on_save_search.clone();
+        move |_| on_save_search()
+    });
+
+    let action_save_search = gio::SimpleAction::new("save_search", None);
+    action_save_search.connect_activate({
+        let stack = stack.clone();
+        let search_entry = search_entry.clone();
+        let save_search_name = save_search_name.clone();
+        let save_search_popover = save_search_popover.clone();
+        move |_, _| {
+            stack.set_visible_child_name("notes");
+            save_search_name.set_text(search_entry.text().trim());
+            save_search_popover.popup();
+            save_search_name.grab_focus();
+        }
+    });
+    app.add_action(&action_save_search);
+    app.set_accels_for_action("app.save_search", &["<Primary>d"]);
+
+    let action_delete_saved_search =
+        gio::SimpleAction::new("delete_saved_search", Some(VariantTy::INT64));
+    action_delete_saved_search.connect_activate({
+        let worker = worker.clone();
+        let refresh_saved_searches = Rc::clone(&refresh_saved_searches);
+        move |_, parameter| {
+            let Some(id) = parameter.and_then(|p| p.get::<i64>()) else {
+                return;
+            };
+
+            let request = worker.call(move |conn| db::delete_saved_search(conn, id));
+
+            let refresh_saved_searches = Rc::clone(&refresh_saved_searches);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    refresh_saved_searches.as_ref()();
+                }
+            });
+        }
+    });
+    app.add_action(&action_delete_saved_search);
+
+    Page {
+        root: saved_sidebar,
+        refresh: refresh_saved_searches,
+    }
+}
+
+// Actions on the Notes page, also reached from its menus, links and the
+// other pages.
+fn install_notes_actions(
+    app: &Application,
+    worker: &DbWorker,
+    stack: &Stack,
+    notes: &NotesPage,
+    refresh_trash: &Rc<dyn Fn()>,
+) {
+    let NotesPage {
+        refresh: refresh_notes,
+        notes_changed,
+        model: notes_model,
+        selection: note_selection,
+        search_entry,
+        search_mode,
+        reader,
+        opening_note,
+        ..
+    } = notes;
+
+    let on_delete = {
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let notes_changed = Rc::clone(notes_changed);
+        let refresh_trash = Rc::clone(refresh_trash);
+        move || {
+            let Some(note_id) = selected_note(&note_selection).map(|note| note.id) else {
+                return;
+            };
+
+            let request = worker.call(move |conn| db::soft_delete_note(conn, &note_id));
+
+            let notes_changed = Rc::clone(&notes_changed);
+            let refresh_trash = Rc::clone(&refresh_trash);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    notes_changed.as_ref()();
+                    refresh_trash.as_ref()();
+                }
+            });
+        }
+    };
+
+    let action_delete_note = gio::SimpleAction::new("delete_note", None);
+    action_delete_note.connect_activate({
+        let on_delete = on_delete.clone();
+        move |_, _| on_delete()
+    });
+    app.add_action(&action_delete_note);
+
+    let action_toggle_pin = gio::SimpleAction::new("toggle_pin", None);
+    action_toggle_pin.connect_activate({
+        let worker = worker.clone();
+        let note_selection = note_selection.clone();
+        let stack = stack.clone();
+        let notes_changed = Rc::clone(notes_changed);
+        move |_, _| {
+            if stack.visible_child_name().as_deref() != Some("notes") {
+                return;
+            }
+
+            let pinned = selected_note(&note_selection).map(|note| (note.id, note.pinned));
+
+            let Some((note_id, pinned)) = pinned else {
+                return;
+            };
+
+            let request = worker.call(move |conn| db::set_pinned(conn, &note_id, !pinned));
+
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    notes_changed.as_ref()();
+                }
+            });
+        }
+    });
+    app.add_action(&action_toggle_pin);
+    app.set_accels_for_action("app.toggle_pin", &["<Primary>p"]);
+
+    let action_filter_tag = gio::SimpleAction::new("filter_tag", Some(VariantTy::STRING));
+    action_filter_tag.connect_activate({
+        let stack = stack.clone();
+        let search_entry = search_entry.clone();
+        let refresh_notes = Rc::clone(refresh_notes);
+        move |_, parameter| {
+            let Some(name) = parameter.and_then(|p| p.get::<String>()) else {
+                return;
+            };
+
+            let chunk = tag_filter_chunk(&name);
+            let text = search_entry.text();
+            if !text.split_whitespace().any(|part| part == chunk) {
+                let text = text.trim_end();
+                if text.is_empty() {
+                    search_entry.set_text(&format!("{} ", chunk));
+                } else {
+                    search_entry.set_text(&format!("{} {} ", text, chunk));
+                }
+            }
+            search_entry.set_position(-1);
+            stack.set_visible_child_name("notes");
+            refresh_notes.as_ref()();
+        }
+    });
+    app.add_action(&action_filter_tag);
+
+    let action_open_note = gio::SimpleAction::new("open_note", Some(VariantTy::STRING));
+    action_open_note.connect_activate({
+        let stack = stack.clone();
+        let search_entry = search_entry.clone();
+        let search_mode = search_mode.clone();
+        let notes_model = notes_model.clone();
+        let note_selection = note_selection.clone();
+        let opening_note = Rc::clone(opening_note);
+        let refresh_notes = Rc::clone(refresh_notes);
+        move |_, parameter| {
+            let Some(note_id) = parameter.and_then(|p| p.get::<String>()) else {
+                return;
+            };
+
+            stack.set_visible_child_name("notes");
+            if let Some(position) = notes_model.position_of(&note_id) {
+                note_selection.set_selected(position);
+                return;
+            }
+
+            // Not among the loaded rows: list every note and select it
+            // there.
+            *opening_note.borrow_mut() = Some(note_id);
+            search_entry.set_text("");
+            search_mode.set_selected(search_mode_position(QueryMode::Simple));
+            refresh_notes.as_ref()();
+        }
+    });
+    app.add_action(&action_open_note);
+
+    // Lets the quick-capture window and the trash show the notes they add
+    // here.
+    let action_reload_notes = gio::SimpleAction::new("reload_notes", None);
+    action_reload_notes.connect_activate({
+        let notes_changed = Rc::clone(notes_changed);
+        move |_, _| notes_changed.as_ref()()
+    });
+    app.add_action(&action_reload_notes);
+
+    for (name, forward) in [("next_match", true), ("previous_match", false)] {
+        let action = gio::SimpleAction::new(name, None);
+        action.connect_activate({
+            let reader = reader.clone();
+            let stack = stack.clone();
+            move |_, _| {
+                if stack.visible_child_name().as_deref() == Some("notes") {
+                    goto_match(&reader, forward);
+                }
+            }
+        });
+        app.add_action(&action);
+    }
+    app.set_accels_for_action("app.next_match", &["F3"]);
+    app.set_accels_for_action("app.previous_match", &["<Shift>F3"]);
+}
+
+fn build_trash_page(worker: &DbWorker, window: &ApplicationWindow) -> Page {
+    let trash_panel = GtkBox::new(Orientation::Vertical, 8);
+    trash_panel.add_css_class("library-panel");
+
+    let trash_header = GtkBox::new(Orientation::Horizontal, 8);
+    let trash_title = Label::new(Some("Corbeille"));
+    trash_title.set_halign(Align::Start);
+    trash_title.set_hexpand(true);
+    trash_title.add_css_class("section-title");
+    let trash_status_label = Label::new(Some("0 notes"));
+    trash_status_label.add_css_class("status-label");
+    let restore_btn = icon_label_button("edit-undo-symbolic", "Restore");
+    restore_btn.set_tooltip_text(Some("Restaurer la note selectionnee"));
+    let purge_btn = icon_label_button("edit-delete-symbolic", "Delete forever");
+    purge_btn.set_tooltip_text(Some("Supprimer definitivement la note selectionnee"));
+    let empty_trash_btn = icon_label_button("user-trash-full-symbolic", "Empty trash");
+    empty_trash_btn.set_tooltip_text(Some("Supprimer definitivement toutes les notes"));
+    trash_header.append(&trash_title);
+    trash_header.append(&trash_status_label);
+    trash_header.append(&restore_btn);
+    trash_header.append(&purge_btn);
+    trash_header.append(&empty_trash_btn);
+
+    let trash_paned = Paned::new(Orientation::Horizontal);
+    trash_paned.set_wide_handle(true);
+    trash_paned.set_resize_start_child(true);
+    trash_paned.set_shrink_start_child(false);
+
+    let trash_list = ListBox::new();
+    trash_list.set_selection_mode(gtk::SelectionMode::Single);
+
+    let trash_list_scrolled = ScrolledWindow::new();
+    trash_list_scrolled.set_hexpand(true);
+    trash_list_scrolled.set_vexpand(true);
+    trash_list_scrolled.set_min_content_width(280);
+    trash_list_scrolled.set_child(Some(&trash_list));
+
+    let trash_reader = TextView::new();
+    trash_reader.add_css_class("reader");
+    trash_reader.add_css_class("note-editor");
+    trash_reader.set_editable(false);
+    trash_reader.set_cursor_visible(false);
+    trash_reader.set_wrap_mode(WrapMode::WordChar);
+
+    let trash_reader_scrolled = ScrolledWindow::new();
+    trash_reader_scrolled.set_hexpand(true);
+    trash_reader_scrolled.set_vexpand(true);
+    trash_reader_scrolled.set_min_content_width(420);
+    trash_reader_scrolled.set_child(Some(&trash_reader));
+
+    trash_paned.set_start_child(Some(&trash_list_scrolled));
+    trash_paned.set_end_child(Some(&trash_reader_scrolled));
+    trash_paned.set_position(320);
+
+    trash_panel.append(&trash_header);
+    trash_panel.append(&trash_paned);
+
+    let trash_state = Rc::new(RefCell::new(Vec::<db::NoteListItem>::new()));
+
+    let refresh_trash: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let trash_list = trash_list.clone();
+        let trash_reader = trash_reader.clone();
+        let trash_status_label = trash_status_label.clone();
+        let trash_state = Rc::clone(&trash_state);
+
+        Rc::new(move || {
+            let request = worker.call(|conn| db::list_deleted_notes(conn, 200));
+
+            let trash_list = trash_list.clone();
+            let trash_reader = trash_reader.clone();
+            let trash_status_label = trash_status_label.clone();
+            let trash_state = Rc::clone(&trash_state);
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(notes) => {
+                        clear_listbox(&trash_list);
+                        *trash_state.borrow_mut() = notes.clone();
+
+                        for item in &notes {
+                            let row = ListBoxRow::new();
+                            let title = Label::new(Some(&db::note_title(&item.preview)));
+                            title.set_halign(Align::Start);
+                            title.set_xalign(0.0);
+                            title.set_margin_top(8);
+                            title.set_margin_bottom(8);
+                            title.set_margin_start(8);
+                            title.set_margin_end(8);
+                            title.add_css_class("section-title");
+                            row.set_child(Some(&title));
+                            trash_list.append(&row);
+                        }
+
+                        trash_status_label.set_text(&format!("{} notes", notes.len()));
+
+                        if let Some(row) = trash_list.row_at_index(0) {
+                            trash_list.select_row(Some(&row));
+                        } else {
+                            trash_reader.buffer().set_text("Trash is empty.");
+                        }
+                    }
+                    Err(err) => {
+                        trash_status_label.set_text("Trash error");
+                        trash_reader
+                            .buffer()
+                            .set_text(&format!("Failed to load trash:\n{}", err));
+                    }
+                }
+            });
+        })
+    };
+
+    trash_list.connect_row_selected({
+        let trash_reader = trash_reader.clone();
+        let trash_state = Rc::clone(&trash_state);
+        move |_, row| {
+            let preview = row
+                .and_then(|row| usize::try_from(row.index()).ok())
+                .and_then(|index| trash_state.borrow().get(index).cloned());
+
+            match preview {
+                Some(note) => trash_reader.buffer().set_text(&note.preview),
+                None => trash_reader.buffer().set_text("No note selected."),
+            }
+        }
+    });
+
+    restore_btn.connect_clicked({
+        let worker = worker.clone();
+        let trash_state = Rc::clone(&trash_state);
+        let trash_list = trash_list.clone();
+        let refresh_trash = Rc::clone(&refresh_trash);
+        move |_| {
+            let Some(note_id) = selected_note_id(&trash_list, &trash_state) else {
+                return;
+            };
+
+            let request = worker.call(move |conn| db::restore_note(conn, &note_id));
+
+            let trash_list = trash_list.clone();
+            let refresh_trash = Rc::clone(&refresh_trash);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    refresh_trash.as_ref()();
+                    let _ = trash_list.activate_action("app.reload_notes", None);
+                }
+            });
+        }
+    });
+
+    purge_btn.connect_clicked({
+        let worker = worker.clone();
+        let trash_state = Rc::clone(&trash_state);
+        let trash_list = trash_list.clone();
+        let refresh_trash = Rc::clone(&refresh_trash);
+        move |_| {
+            let Some(note_id) = selected_note_id(&trash_list, &trash_state) else {
+                return;
+            };
+
+            let request = worker.call(move |conn| db::purge_note(conn, &note_id));
+
+            let refresh_trash = Rc::clone(&refresh_trash);
+            glib::spawn_future_local(async move {
+                if request.await.is_ok() {
+                    refresh_trash.as_ref()();
+                }
+            });
+        }
+    });
+
+    // Purging cannot be undone, so the whole trash only goes once the
+    // number of notes in it has been confirmed.
+    empty_trash_btn.connect_clicked({
+        let worker = worker.clone();
+        let window = window.clone();
+        let refresh_trash = Rc::clone(&refresh_trash);
+        move |_| {
+            let request = worker.call(|conn| {
+                let trash = query::parse("is:deleted", QueryMode::Simple, 0);
+                db::count_notes(conn, &trash)
+            });
+
+            let worker = worker.clone();
+            let window = window.clone();
+            let refresh_trash = Rc::clone(&refresh_trash);
+            glib::spawn_future_local(async move {
+                let Ok(count) = request.await else {
+                    return;
+                };
+                if count == 0 {
+                    return;
+                }
+
+                let noun = if count == 1 { "note" } else { "notes" };
+                let dialog = gtk::MessageDialog::new(
+                    Some(&window),
+                    gtk::DialogFlags::MODAL | gtk::DialogFlags::DESTROY_WITH_PARENT,
+                    gtk::MessageType::Warning,
+                    gtk::ButtonsType::None,
+                    format!("Permanently delete {} {} from the trash?", count, noun),
+                );
+                dialog.set_secondary_text(Some(
+                    "Their history, links and reminders are deleted with them. \
+                     This cannot be undone.",
+                ));
+                dialog.add_button("Cancel", gtk::ResponseType::Cancel);
+                let confirm = dialog.add_button(
+                    &format!("Delete {} {}", count, noun),
+                    gtk::ResponseType::Accept,
+                );
+                confirm.add_css_class("destructive-action");
+                dialog.set_default_response(gtk::ResponseType::Cancel);
+
+                dialog.connect_response(move |dialog, response| {
+                    dialog.close();
+                    if response != gtk::ResponseType::Accept {
+                        return;
+                    }
+
+                    let request = worker.call(db::empty_trash);
+                    let refresh_trash = Rc::clone(&refresh_trash);
+                    glib::spawn_future_local(async move {
+                        if request.await.is_ok() {
+                            refresh_trash.as_ref()();
+                        }
+                    });
+                });
+                dialog.present();
+            });
+        }
+    });
+
+    Page {
+        root: trash_panel,
+        refresh: refresh_trash,
+    }
+}
+
+fn build_tags_page(app: &Application, worker: &DbWorker, stack: &Stack, notes: &NotesPage) -> Page {
+    let NotesPage {
+        search_entry,
+        search_mode,
+        refresh: refresh_notes,
+        notes_changed,
+        ..
+    } = notes;
+
+    let tags_panel = GtkBox::new(Orientation::Vertical, 8);
+    tags_panel.add_css_class("library-panel");
+
+    let tags_header = GtkBox::new(Orientation::Horizontal, 8);
+    let tags_title = Label::new(Some("Tags"));
+    tags_title.set_halign(Align::Start);
+    tags_title.set_hexpand(true);
+    tags_title.add_css_class("section-title");
+    let tags_status_label = Label::new(Some("0 tags"));
+    tags_status_label.add_css_class("status-label");
+    tags_header.append(&tags_title);
+    tags_header.append(&tags_status_label);
+
+    let tags_actions = GtkBox::new(Orientation::Horizontal, 8);
+    let tag_name_entry = Entry::new();
+    tag_name_entry.set_hexpand(true);
+    tag_name_entry.set_placeholder_text(Some("Nouveau nom ou tag cible"));
+    let rename_tag_btn = icon_label_button("document-edit-symbolic", "Rename");
+    rename_tag_btn.set_tooltip_text(Some("Renommer le tag selectionne"));
+    let merge_tags_btn = icon_label_button("insert-link-symbolic", "Merge");
+    merge_tags_btn.set_tooltip_text(Some(
+        "Fusionner les tags selectionnes (Ctrl+clic) dans le tag saisi",
+    ));
+    let delete_tag_btn = icon_label_button("edit-delete-symbolic", "Delete");
+    delete_tag_btn.set_tooltip_text(Some("Retirer les tags selectionnes de toutes les notes"));
+    let tag_color_entry = Entry::new();
+    tag_color_entry.set_width_chars(9);
+    tag_color_entry.set_placeholder_text(Some("#rrggbb"));
+    tag_color_entry.set_tooltip_text(Some("Couleur du tag, vide pour aucune"));
+    let tag_icon_entry = Entry::new();
+    tag_icon_entry.set_width_chars(4);
+    tag_icon_entry.set_placeholder_text(Some("Emoji"));
+    tag_icon_entry.set_tooltip_text(Some("Emoji affiche devant le tag, vide pour aucun"));
+    let style_tags_btn = icon_label_button("applications-graphics-symbolic", "Style");
+    style_tags_btn.set_tooltip_text(Some(
+        "Appliquer la couleur et l'emoji aux tags selectionnes",
+    ));
+    let prune_tags_btn = icon_label_button("edit-clear-all-symbolic", "Prune unused");
+    prune_tags_btn.set_tooltip_text(Some("Supprimer les tags qui ne sont plus utilises"));
+    tags_actions.append(&tag_name_entry);
+    tags_actions.append(&rename_tag_btn);
+    tags_actions.append(&merge_tags_btn);
+    tags_actions.append(&delete_tag_btn);
+    tags_actions.append(&tag_color_entry);
+    tags_actions.append(&tag_icon_entry);
+    tags_actions.append(&style_tags_btn);
+    tags_actions.append(&prune_tags_btn);
+
+    let tags_list = ListBox::new();
+    tags_list.set_selection_mode(gtk::SelectionMode::Multiple);
+    tags_list.set_tooltip_text(Some("Double-clic : afficher les notes de ce tag"));
+
+    let tags_scrolled = ScrolledWindow::new();
+    tags_scrolled.set_vexpand(true);
+    tags_scrolled.set_child(Some(&tags_list));
+
+    tags_panel.append(&tags_header);
+    tags_panel.append(&tags_actions);
+    tags_panel.append(&tags_scrolled);
+
+    // Every tag as loaded, the collapsed branches, and the rows currently
+    // shown in the tree, which selections map back to.
+    let tag_usage = Rc::new(RefCell::new(Vec::<db::TagUsage>::new()));
+    let collapsed_tags = Rc::new(RefCell::new(HashSet::<String>::new()));
+    let tags_state = Rc::new(RefCell::new(Vec::<TagTreeRow>::new()));
+
+    let render_tags: Rc<dyn Fn()> = {
+        let tags_list = tags_list.clone();
+        let tag_usage = Rc::clone(&tag_usage);
+        let collapsed_tags = Rc::clone(&collapsed_tags);
+        let tags_state = Rc::clone(&tags_state);
+
+        Rc::new(move || {
+            let collapsed = collapsed_tags.borrow();
+            let rows = tag_tree(&tag_usage.borrow(), &collapsed);
+            clear_listbox(&tags_list);
+            for row in &rows {
+                tags_list.append(&build_tag_tree_row(row, collapsed.contains(&row.name)));
+            }
+            *tags_state.borrow_mut() = rows;
+        })
+    };
+
+    let refresh_tags: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let tags_status_label = tags_status_label.clone();
+        let tag_usage = Rc::clone(&tag_usage);
+        let render_tags = Rc::clone(&render_tags);
+
+        Rc::new(move || {
+            let request = worker.call(|conn| db::list_tag_usage(conn));
+
+            let tags_status_label = tags_status_label.clone();
+            let tag_usage = Rc::clone(&tag_usage);
+            let render_tags = Rc::clone(&render_tags);
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(tags) => {
+                        tags_status_label.set_text(&format!("{} tags", tags.len()));
+                        *tag_usage.borrow_mut() = tags;
+                        render_tags.as_ref()();
+                    }
+                    Err(err) => {
+                        tags_status_label.set_text(&format!("Tags error: {}", err));
+                    }
+                }
+            });
+        })
+    };
+
+    // Runs a tag operation, then reloads everything that shows tags.
+    let run_tag_change: Rc<dyn Fn(TagChange)> = {
+        let worker = worker.clone();
+        let tags_status_label = tags_status_label.clone();
+        let refresh_tags = Rc::clone(&refresh_tags);
+        let notes_changed = Rc::clone(notes_changed);
+
+        Rc::new(move |change| {
+            let request = worker.call(change);
+
+            let tags_status_label = tags_status_label.clone();
+            let refresh_tags = Rc::clone(&refresh_tags);
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(()) => {
+                        refresh_tags.as_ref()();
+                        notes_changed.as_ref()();
+                    }
+                    Err(err) => tags_status_label.set_text(&err.to_string()),
+                }
+            });
+        })
+    };
+
+    let action_toggle_tag_branch =
+        gio::SimpleAction::new("toggle_tag_branch", Some(VariantTy::STRING));
+    action_toggle_tag_branch.connect_activate({
+        let collapsed_tags = Rc::clone(&collapsed_tags);
+        let render_tags = Rc::clone(&render_tags);
+        move |_, parameter| {
+            let Some(name) = parameter.and_then(|p| p.get::<String>()) else {
+                return;
+            };
+
+            {
+                let mut collapsed = collapsed_tags.borrow_mut();
+                if !collapsed.remove(&name) {
+                    collapsed.insert(name);
+                }
+            }
+            render_tags.as_ref()();
+        }
+    });
+    app.add_action(&action_toggle_tag_branch);
+
+    tags_list.connect_selected_rows_changed({
+        let tags_state = Rc::clone(&tags_state);
+        let tag_name_entry = tag_name_entry.clone();
+        let tag_color_entry = tag_color_entry.clone();
+        let tag_icon_entry = tag_icon_entry.clone();
+        move |list| {
+            let rows = list.selected_rows();
+            let [row] = rows.as_slice() else {
+                return;
+            };
+            let tags = tags_state.borrow();
+            let Some(tag) = usize::try_from(row.index())
+                .ok()
+                .and_then(|index| tags.get(index))
+            else {
+                return;
+            };
+
+            tag_name_entry.set_text(&tag.name);
+            tag_color_entry.set_text(tag.color.as_deref().unwrap_or_default());
+            tag_icon_entry.set_text(tag.icon.as_deref().unwrap_or_default());
+        }
+    });
+
+    tags_list.connect_row_activated({
+        let tags_state = Rc::clone(&tags_state);
+        let stack = stack.clone();
+        let search_entry = search_entry.clone();
+        let search_mode = search_mode.clone();
+        let refresh_notes = Rc::clone(refresh_notes);
+        move |_, row| {
+            let Some(name) = usize::try_from(row.index())
+                .ok()
+                .and_then(|index| tags_state.borrow().get(index).map(|tag| tag.name.clone()))
+            else {
+                return;
+            };
+
+            search_mode.set_selected(search_mode_position(QueryMode::Simple));
+            search_entry.set_text(&format!("{} ", tag_filter_chunk(&name)));
+            stack.set_visible_child_name("notes");
+            refresh_notes.as_ref()();
+        }
+    });
+
+    rename_tag_btn.connect_clicked({
+        let tags_list = tags_list.clone();
+        let tags_state = Rc::clone(&tags_state);
+        let tag_name_entry = tag_name_entry.clone();
+        let run_tag_change = Rc::clone(&run_tag_change);
+        move |_| {
+            // Renaming applies to exactly one selected tag.
+            let names = selected_tag_names(&tags_list, &tags_state);
+            let [name] = names.as_slice() else {
+                return;
+            };
+            let name = name.clone();
+            let new_name = tag_name_entry.text().to_string();
+
+            run_tag_change(Box::new(move |conn| db::rename_tag(conn, &name, &new_name)));
+        }
+    });
+
+    merge_tags_btn.connect_clicked({
+        let tags_list = tags_list.clone();
+        let tags_state = Rc::clone(&tags_state);
+        let tag_name_entry = tag_name_entry.clone();
+        let run_tag_change = Rc::clone(&run_tag_change);
+        move |_| {
+            let sources = selected_tag_names(&tags_list, &tags_state);
+            let target = tag_name_entry.text().to_string();
+            if sources.is_empty() || target.trim().is_empty() {
+                return;
+            }
+
+            run_tag_change(Box::new(move |conn| {
+                db::merge_tags(conn, &sources, &target)
+            }));
+        }
+    });
+
+    delete_tag_btn.connect_clicked({
+        let tags_list = tags_list.clone();
+        let tags_state = Rc::clone(&tags_state);
+        let run_tag_change = Rc::clone(&run_tag_change);
+        move |_| {
+            let names = selected_tag_names(&tags_list, &tags_state);
+            if names.is_empty() {
+                return;
+            }
+
+            run_tag_change(Box::new(move |conn| {
+                for name in &names {
+                    db::delete_tag(conn, name)?;
+                }
+                Ok(())
+            }));
+        }
+    });
+
+    style_tags_btn.connect_clicked({
+        let tags_list = tags_list.clone();
+        let tags_state = Rc::clone(&tags_state);
+        let tag_color_entry = tag_color_entry.clone();
+        let tag_icon_entry = tag_icon_entry.clone();
+        let run_tag_change = Rc::clone(&run_tag_change);
+        move |_| {
+            let names = selected_tag_names(&tags_list, &tags_state);
+            if names.is_empty() {
+                return;
+            }
+            let color = tag_color_entry.text().to_string();
+            let icon = tag_icon_entry.text().to_string();
+
+            run_tag_change(Box::new(move |conn| {
+                for name in &names {
+                    db::set_tag_style(conn, name, Some(&color), Some(&icon))?;
+                }
+                Ok(())
+            }));
+        }
+    });
+
+    prune_tags_btn.connect_clicked({
+        let run_tag_change = Rc::clone(&run_tag_change);
+        move |_| run_tag_change(Box::new(|conn| db::prune_orphan_tags(conn).map(|_| ())))
+    });
+
+    Page {
+        root: tags_panel,
+        refresh: refresh_tags,
+    }
 }
 
-.placeholder-hint {
-  color: #496067;
-  font-style: italic;
-}
+fn build_tasks_page(
+    worker: &DbWorker,
+    hashtag_mode: hashtags::HashtagMode,
+    notes_changed: &Rc<dyn Fn()>,
+) -> Page {
+    let tasks_panel = GtkBox::new(Orientation::Vertical, 8);
+    tasks_panel.add_css_class("library-panel");
+
+    let tasks_header = GtkBox::new(Orientation::Horizontal, 8);
+    let tasks_title = Label::new(Some("Tasks"));
+    tasks_title.set_halign(Align::Start);
+    tasks_title.set_hexpand(true);
+    tasks_title.add_css_class("section-title");
+    let tasks_status_label = Label::new(Some("0 tasks"));
+    tasks_status_label.add_css_class("status-label");
+    tasks_header.append(&tasks_title);
+    tasks_header.append(&tasks_status_label);
+
+    let tasks_actions = GtkBox::new(Orientation::Horizontal, 8);
+    let task_tag_entry = Entry::new();
+    task_tag_entry.set_hexpand(true);
+    task_tag_entry.set_placeholder_text(Some("Filtrer par tag"));
+    attach_tag_autocomplete(
+        &task_tag_entry,
+        worker.clone(),
+        single_tag_fragment,
+        apply_single_tag_completion,
+    );
+    let show_done_tasks = gtk::CheckButton::with_label("Show done");
+    show_done_tasks.set_tooltip_text(Some("Afficher aussi les taches cochees"));
+    tasks_actions.append(&task_tag_entry);
+    tasks_actions.append(&show_done_tasks);
+
+    let tasks_list = ListBox::new();
+    tasks_list.set_selection_mode(gtk::SelectionMode::None);
+
+    let tasks_scrolled = ScrolledWindow::new();
+    tasks_scrolled.set_vexpand(true);
+    tasks_scrolled.set_child(Some(&tasks_list));
+
+    tasks_panel.append(&tasks_header);
+    tasks_panel.append(&tasks_actions);
+    tasks_panel.append(&tasks_scrolled);
+
+    let tasks_request = LatestRequest::default();
+    // Set to `refresh_tasks` once built, so a checkbox toggled in the
+    // list can reload it.
+    let reload_tasks: Rc<OnceCell<Rc<dyn Fn()>>> = Rc::default();
+
+    let refresh_tasks: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let reload_tasks = Rc::clone(&reload_tasks);
+        let tasks_list = tasks_list.clone();
+        let tasks_status_label = tasks_status_label.clone();
+        let task_tag_entry = task_tag_entry.clone();
+        let show_done_tasks = show_done_tasks.clone();
+        let notes_changed = Rc::clone(notes_changed);
+
+        Rc::new(move || {
+            let tag = task_tag_entry.text().trim().to_string();
+            let include_done = show_done_tasks.is_active();
+            let request = worker.call_latest(&tasks_request, move |conn| {
+                db::list_tasks(conn, Some(tag.as_str()), include_done)
+            });
+
+            let worker = worker.clone();
+            let reload_tasks = Rc::clone(&reload_tasks);
+            let tasks_list = tasks_list.clone();
+            let tasks_status_label = tasks_status_label.clone();
+            let notes_changed = Rc::clone(&notes_changed);
+            glib::spawn_future_local(async move {
+                let Some(result) = request.await else {
+                    return;
+                };
+                let tasks = match result {
+                    Ok(tasks) => tasks,
+                    Err(err) => {
+                        tasks_status_label.set_text(&format!("Tasks error: {}", err));
+                        return;
+                    }
+                };
 
-.tag-chip {
-  color: #1f3a45;
-  background: #d9ecef;
-  border-radius: 8px;
-  padding: 4px 8px;
-}
+                let open = tasks.iter().filter(|task| !task.done).count();
+                tasks_status_label.set_text(&format!("{} open tasks", open));
+                clear_listbox(&tasks_list);
+                for task in tasks {
+                    let (row, check) = build_task_row(&task);
+                    check.connect_toggled({
+                        let worker = worker.clone();
+                        let reload_tasks = Rc::clone(&reload_tasks);
+                        let tasks_status_label = tasks_status_label.clone();
+                        let notes_changed = Rc::clone(&notes_changed);
+                        move |check| {
+                            let note_id = task.note_id.clone();
+                            let line = task.line;
+                            let text = task.text.clone();
+                            let done = check.is_active();
+                            let request = worker.call(move |conn| {
+                                db::set_task_done(conn, &note_id, line, &text, done, hashtag_mode)
+                            });
+
+                            let reload_tasks = Rc::clone(&reload_tasks);
+                            let tasks_status_label = tasks_status_label.clone();
+                            let notes_changed = Rc::clone(&notes_changed);
+                            glib::spawn_future_local(async move {
+                                if let Err(err) = request.await {
+                                    tasks_status_label.set_text(&err.to_string());
+                                    return;
+                                }
+                                notes_changed.as_ref()();
+                                if let Some(reload) = reload_tasks.get() {
+                                    reload.as_ref()();
+                                }
+                            });
+                        }
+                    });
+                    tasks_list.append(&row);
+                }
+            });
+        })
+    };
 
-.capture-panel textview,
-.library-panel entry,
-.library-panel list,
-.library-panel textview {
-  background: #ffffff;
-  color: #172127;
-  border-radius: 8px;
-}
+    let _ = reload_tasks.set(Rc::clone(&refresh_tasks));
 
-button:focus-visible,
-entry:focus-visible,
-textview:focus-visible,
-list:focus-visible {
-  outline: 3px solid #0b6ea8;
-  outline-offset: 2px;
+    task_tag_entry.connect_changed({
+        let refresh_tasks = Rc::clone(&refresh_tasks);
+        move |_| refresh_tasks.as_ref()()
+    });
+
+    show_done_tasks.connect_toggled({
+        let refresh_tasks = Rc::clone(&refresh_tasks);
+        move |_| refresh_tasks.as_ref()()
+    });
+
+    Page {
+        root: tasks_panel,
+        refresh: refresh_tasks,
+    }
 }
-",
-    );
 
-    if let Some(display) = gdk::Display::default() {
-        gtk::style_context_add_provider_for_display(
-            &display,
-            &provider,
-            gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
-        );
+fn build_reminders_page(worker: &DbWorker) -> Page {
+    let reminders_panel = GtkBox::new(Orientation::Vertical, 8);
+    reminders_panel.add_css_class("library-panel");
+
+    let reminders_header = GtkBox::new(Orientation::Horizontal, 8);
+    let reminders_title = Label::new(Some("Upcoming reminders"));
+    reminders_title.set_halign(Align::Start);
+    reminders_title.set_hexpand(true);
+    reminders_title.add_css_class("section-title");
+    let reminders_status_label = Label::new(Some("0 reminders"));
+    reminders_status_label.add_css_class("status-label");
+    reminders_header.append(&reminders_title);
+    reminders_header.append(&reminders_status_label);
+
+    let reminders_list = ListBox::new();
+    reminders_list.set_selection_mode(gtk::SelectionMode::None);
+
+    let reminders_scrolled = ScrolledWindow::new();
+    reminders_scrolled.set_vexpand(true);
+    reminders_scrolled.set_child(Some(&reminders_list));
+
+    reminders_panel.append(&reminders_header);
+    reminders_panel.append(&reminders_scrolled);
+
+    let refresh_reminders: Rc<dyn Fn()> = {
+        let worker = worker.clone();
+        let reminders_list = reminders_list.clone();
+        let reminders_status_label = reminders_status_label.clone();
+
+        Rc::new(move || {
+            let request = worker.call(|conn| db::list_upcoming_reminders(conn));
+
+            let reminders_list = reminders_list.clone();
+            let reminders_status_label = reminders_status_label.clone();
+            glib::spawn_future_local(async move {
+                match request.await {
+                    Ok(reminders) => {
+                        reminders_status_label.set_text(&format!("{} reminders", reminders.len()));
+                        clear_listbox(&reminders_list);
+                        for reminder in &reminders {
+                            reminders_list.append(&build_reminder_row(reminder));
+                        }
+                    }
+                    Err(err) => {
+                        reminders_status_label.set_text(&format!("Reminders error: {}", err));
+                    }
+                }
+            });
+        })
+    };
+
+    Page {
+        root: reminders_panel,
+        refresh: refresh_reminders,
     }
 }
 
 pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
     let quit_on_close = config.quit_on_close;
+    let hashtag_mode = config.hashtags;
+    let revision_retention = db::RevisionRetention {
+        keep_last: config.revisions_keep_last,
+        keep_days: config.revisions_keep_days,
+    };
     let capture_hints = if config.capture_hints.is_empty() {
         vec!["L'idee que je viens d'avoir :".to_string()]
     } else {
@@ -283,12 +3926,80 @@ pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
         .application_id("io.github.memo_tori.gtk")
         .build();
 
-    let conn = Rc::new(RefCell::new(connection));
+    let worker = DbWorker::spawn(connection)?;
     let capture_hints = Rc::new(capture_hints);
 
+    app.add_main_option(
+        "capture",
+        glib::Char::from(b'c'),
+        glib::OptionFlags::NONE,
+        glib::OptionArg::None,
+        "Open the quick-capture window, in the running instance if there is one",
+        None,
+    );
+
+    // Set by `--capture` when no instance is running: the first activation
+    // then opens only the quick-capture window.
+    let capture_only = Rc::new(Cell::new(false));
+
+    app.connect_handle_local_options({
+        let capture_only = Rc::clone(&capture_only);
+        move |app, options| {
+            if !options.contains("capture") {
+                return -1;
+            }
+            if let Err(err) = app.register(gio::Cancellable::NONE) {
+                eprintln!("failed to register the application: {}", err);
+                return 1;
+            }
+            if app.is_remote() {
+                ActionGroupExt::activate_action(app, "quick-capture", None);
+                return 0;
+            }
+            capture_only.set(true);
+            -1
+        }
+    });
+
+    app.connect_startup({
+        let worker = worker.clone();
+        let capture_hints = Rc::clone(&capture_hints);
+        let quick_capture_window = Rc::new(RefCell::new(None));
+        move |app| {
+            gtk::Window::set_default_icon_name("memo-tori");
+            install_css();
+
+            let action_quick_capture = gio::SimpleAction::new("quick-capture", None);
+            action_quick_capture.connect_activate({
+                let app = app.clone();
+                let worker = worker.clone();
+                let capture_hints = Rc::clone(&capture_hints);
+                let quick_capture_window = Rc::clone(&quick_capture_window);
+                move |_, _| {
+                    open_quick_capture(
+                        &app,
+                        &worker,
+                        &capture_hints,
+                        hashtag_mode,
+                        &quick_capture_window,
+                    )
+                }
+            });
+            app.add_action(&action_quick_capture);
+        }
+    });
+
+    let main_window: Rc<RefCell<Option<ApplicationWindow>>> = Rc::new(RefCell::new(None));
+
     app.connect_activate(move |app| {
-        gtk::Window::set_default_icon_name("memo-tori");
-        install_css();
+        if capture_only.replace(false) {
+            ActionGroupExt::activate_action(app, "quick-capture", None);
+            return;
+        }
+        if let Some(window) = main_window.borrow().as_ref() {
+            window.present();
+            return;
+        }
 
         let window = ApplicationWindow::builder()
             .application(app)
@@ -326,6 +4037,55 @@ pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
         item_notes.set_attribute_value("icon", Some(&"view-list-symbolic".to_variant()));
         app_menu.append_item(&item_notes);
 
+        let item_trash = gio::MenuItem::new(Some("Trash"), Some("app.show_trash"));
+        item_trash.set_attribute_value("icon", Some(&"user-trash-symbolic".to_variant()));
+        app_menu.append_item(&item_trash);
+
+        let item_tags = gio::MenuItem::new(Some("Tags"), Some("app.show_tags"));
+        item_tags.set_attribute_value("icon", Some(&"bookmark-new-symbolic".to_variant()));
+        app_menu.append_item(&item_tags);
+
+        let item_tasks = gio::MenuItem::new(Some("Tasks"), Some("app.show_tasks"));
+        item_tasks.set_attribute_value("icon", Some(&"checkbox-checked-symbolic".to_variant()));
+        app_menu.append_item(&item_tasks);
+
+        let item_reminders = gio::MenuItem::new(Some("Reminders"), Some("app.show_reminders"));
+        item_reminders.set_attribute_value("icon", Some(&"alarm-symbolic".to_variant()));
+        app_menu.append_item(&item_reminders);
+
+        let export_menu = gio::Menu::new();
+        export_menu.append(
+            Some("All notes to folder…"),
+            Some("app.export::all-directory"),
+        );
+        export_menu.append(
+            Some("All notes to single file…"),
+            Some("app.export::all-single"),
+        );
+        export_menu.append(
+            Some("Search results to folder…"),
+            Some("app.export::results-directory"),
+        );
+        export_menu.append(
+            Some("Search results to single file…"),
+            Some("app.export::results-single"),
+        );
+        app_menu.append_submenu(Some("Export Markdown"), &export_menu);
+
+        let item_import = gio::MenuItem::new(Some("Import Markdown…"), Some("app.import"));
+        item_import.set_attribute_value("icon", Some(&"document-open-symbolic".to_variant()));
+        app_menu.append_item(&item_import);
+
+        let item_save_search =
+            gio::MenuItem::new(Some("Save current search…"), Some("app.save_search"));
+        item_save_search.set_attribute_value("icon", Some(&"starred-symbolic".to_variant()));
+        app_menu.append_item(&item_save_search);
+
+        let item_quick_capture =
+            gio::MenuItem::new(Some("Quick capture"), Some("app.quick-capture"));
+        item_quick_capture.set_attribute_value("icon", Some(&"document-new-symbolic".to_variant()));
+        app_menu.append_item(&item_quick_capture);
+
         let item_quit = gio::MenuItem::new(Some("Quitter"), Some("app.quit"));
         item_quit.set_attribute_value("icon", Some(&"application-exit-symbolic".to_variant()));
         app_menu.append_item(&item_quit);
@@ -350,6 +4110,34 @@ pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
         });
         app.add_action(&action_show_notes);
 
+        let action_show_trash = gio::SimpleAction::new("show_trash", None);
+        action_show_trash.connect_activate({
+            let stack = stack.clone();
+            move |_, _| stack.set_visible_child_name("trash")
+        });
+        app.add_action(&action_show_trash);
+
+        let action_show_tags = gio::SimpleAction::new("show_tags", None);
+        action_show_tags.connect_activate({
+            let stack = stack.clone();
+            move |_, _| stack.set_visible_child_name("tags")
+        });
+        app.add_action(&action_show_tags);
+
+        let action_show_tasks = gio::SimpleAction::new("show_tasks", None);
+        action_show_tasks.connect_activate({
+            let stack = stack.clone();
+            move |_, _| stack.set_visible_child_name("tasks")
+        });
+        app.add_action(&action_show_tasks);
+
+        let action_show_reminders = gio::SimpleAction::new("show_reminders", None);
+        action_show_reminders.connect_activate({
+            let stack = stack.clone();
+            move |_, _| stack.set_visible_child_name("reminders")
+        });
+        app.add_action(&action_show_reminders);
+
         let action_quit = gio::SimpleAction::new("quit", None);
         action_quit.connect_activate({
             let app = app.clone();
@@ -358,454 +4146,252 @@ pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
         app.add_action(&action_quit);
         app.set_accels_for_action("app.show_capture", &["<Primary>1"]);
         app.set_accels_for_action("app.show_notes", &["<Primary>2"]);
+        app.set_accels_for_action("app.show_trash", &["<Primary>3"]);
+        app.set_accels_for_action("app.show_tags", &["<Primary>4"]);
+        app.set_accels_for_action("app.show_tasks", &["<Primary>5"]);
+        app.set_accels_for_action("app.show_reminders", &["<Primary>6"]);
         app.set_accels_for_action("app.quit", &["<Primary>q"]);
 
-        let capture_panel = GtkBox::new(Orientation::Vertical, 8);
-        capture_panel.add_css_class("capture-panel");
-
-        let capture_label = Label::new(Some("Capture d'idee rapide"));
-        capture_label.set_halign(Align::Start);
-        capture_label.add_css_class("section-title");
-
-        let capture_scrolled = ScrolledWindow::new();
-        capture_scrolled.set_vexpand(false);
-        capture_scrolled.set_min_content_height(220);
-
-        let text_view = TextView::new();
-        text_view.add_css_class("note-editor");
-        text_view.set_wrap_mode(WrapMode::WordChar);
-        text_view.set_vexpand(false);
-        text_view.grab_focus();
-        text_view.set_tooltip_text(Some(
-            "Saisir une idee. Enter pour sauvegarder, Shift+Enter pour nouvelle ligne.",
-        ));
-        capture_scrolled.set_child(Some(&text_view));
-
-        let capture_overlay = gtk::Overlay::new();
-        capture_overlay.set_child(Some(&capture_scrolled));
-
-        let placeholder_label = Label::new(Some(&random_hint(capture_hints.as_ref())));
-        placeholder_label.add_css_class("placeholder-hint");
-        placeholder_label.set_halign(Align::Start);
-        placeholder_label.set_valign(Align::Start);
-        placeholder_label.set_margin_top(10);
-        placeholder_label.set_margin_start(10);
-        placeholder_label.set_xalign(0.0);
-        placeholder_label.set_can_target(false);
-        capture_overlay.add_overlay(&placeholder_label);
-
-        let capture_tags = Entry::new();
-        capture_tags.set_placeholder_text(Some("Tags capture (ex: perso, urgent)"));
-        capture_tags.set_tooltip_text(Some("Liste de tags separes par des virgules"));
-        attach_tag_autocomplete(&capture_tags, Rc::clone(&conn));
-
-        let actions = GtkBox::new(Orientation::Horizontal, 8);
-        actions.set_halign(Align::End);
-
-        let save_btn = icon_label_button("document-save-symbolic", "Save");
-        let cancel_btn = icon_label_button("edit-clear-symbolic", "Cancel");
-        save_btn.set_tooltip_text(Some("Sauvegarder la note"));
-        cancel_btn.set_tooltip_text(Some("Effacer la saisie"));
-
-        actions.append(&cancel_btn);
-        actions.append(&save_btn);
-
-        capture_panel.append(&capture_label);
-        capture_panel.append(&capture_overlay);
-        capture_panel.append(&capture_tags);
-        capture_panel.append(&actions);
-
-        let library_panel = GtkBox::new(Orientation::Vertical, 8);
-        library_panel.add_css_class("library-panel");
-
-        let search_row = GtkBox::new(Orientation::Horizontal, 8);
-        let search_entry = SearchEntry::new();
-        search_entry.set_hexpand(true);
-        search_entry.set_placeholder_text(Some("Search notes (FTS5)"));
-        search_entry.set_tooltip_text(Some("Recherche plein texte dans les notes"));
-        let filter_tags_entry = Entry::new();
-        filter_tags_entry.set_hexpand(true);
-        filter_tags_entry.set_placeholder_text(Some("Filtre tags (ex: projet, idee)"));
-        filter_tags_entry.set_tooltip_text(Some("Affiche les notes qui contiennent tous ces tags"));
-        attach_tag_autocomplete(&filter_tags_entry, Rc::clone(&conn));
-        let status_label = Label::new(Some("0 notes"));
-        status_label.set_halign(Align::End);
-        status_label.add_css_class("status-label");
-        search_row.append(&search_entry);
-        search_row.append(&filter_tags_entry);
-        search_row.append(&status_label);
-
-        let edit_tags_row = GtkBox::new(Orientation::Horizontal, 8);
-        let selected_tags_entry = Entry::new();
-        selected_tags_entry.set_hexpand(true);
-        selected_tags_entry.set_placeholder_text(Some("Tags de la note selectionnee"));
-        attach_tag_autocomplete(&selected_tags_entry, Rc::clone(&conn));
-        let apply_tags_btn = icon_label_button("emblem-ok-symbolic", "Apply tags");
-        apply_tags_btn.set_tooltip_text(Some("Appliquer les tags a la note selectionnee"));
-        let save_note_btn = icon_label_button("document-save-symbolic", "Save note");
-        save_note_btn.set_tooltip_text(Some("Sauvegarder les modifications de la note"));
-        edit_tags_row.append(&selected_tags_entry);
-        edit_tags_row.append(&apply_tags_btn);
-        edit_tags_row.append(&save_note_btn);
-
-        let selected_tags_label = Label::new(Some("Tags: -"));
-        selected_tags_label.set_halign(Align::Start);
-        selected_tags_label.add_css_class("tag-chip");
-
-        let paned = Paned::new(Orientation::Horizontal);
-        paned.set_wide_handle(true);
-        paned.set_resize_start_child(true);
-        paned.set_shrink_start_child(false);
-
-        let list_box = ListBox::new();
-        list_box.set_selection_mode(gtk::SelectionMode::Single);
-
-        let list_scrolled = ScrolledWindow::new();
-        list_scrolled.set_hexpand(true);
-        list_scrolled.set_vexpand(true);
-        list_scrolled.set_min_content_width(280);
-        list_scrolled.set_child(Some(&list_box));
-
-        let reader = TextView::new();
-        reader.add_css_class("reader");
-        reader.add_css_class("note-editor");
-        reader.set_editable(true);
-        reader.set_cursor_visible(true);
-        reader.set_wrap_mode(WrapMode::WordChar);
-
-        let reader_scrolled = ScrolledWindow::new();
-        reader_scrolled.set_hexpand(true);
-        reader_scrolled.set_vexpand(true);
-        reader_scrolled.set_min_content_width(420);
-        reader_scrolled.set_child(Some(&reader));
-
-        paned.set_start_child(Some(&list_scrolled));
-        paned.set_end_child(Some(&reader_scrolled));
-        paned.set_position(320);
-
-        library_panel.append(&search_row);
-        library_panel.append(&edit_tags_row);
-        library_panel.append(&selected_tags_label);
-        library_panel.append(&paned);
-
-        stack.add_titled(&capture_panel, Some("capture"), "Capture");
-        stack.add_titled(&library_panel, Some("notes"), "Notes");
-        stack.set_visible_child_name("capture");
-
-        root.append(&menu_bar);
-        root.append(&switcher_wrap);
-        root.append(&stack);
-        window.set_child(Some(&root));
-
-        let notes_state = Rc::new(RefCell::new(Vec::<db::NoteListItem>::new()));
-
-        let refresh_notes: Rc<dyn Fn()> = {
-            let conn = Rc::clone(&conn);
-            let search_entry = search_entry.clone();
-            let filter_tags_entry = filter_tags_entry.clone();
-            let list_box = list_box.clone();
-            let reader = reader.clone();
-            let status_label = status_label.clone();
-            let notes_state = Rc::clone(&notes_state);
-            let selected_tags_label = selected_tags_label.clone();
-            let selected_tags_entry = selected_tags_entry.clone();
-
-            Rc::new(move || {
-                let query = search_entry.text().to_string();
-                let filter_tags = parse_tags(&filter_tags_entry.text());
-
-                match db::search_notes(&conn.borrow(), &query, &filter_tags, 200) {
-                    Ok(notes) => {
-                        clear_listbox(&list_box);
-                        *notes_state.borrow_mut() = notes.clone();
-
-                        for item in &notes {
-                            let row = ListBoxRow::new();
-                            let container = GtkBox::new(Orientation::Vertical, 2);
-                            container.set_margin_top(8);
-                            container.set_margin_bottom(8);
-                            container.set_margin_start(8);
-                            container.set_margin_end(8);
-
-                            let title = Label::new(Some(&note_title(&item.preview)));
-                            title.set_halign(Align::Start);
-                            title.set_xalign(0.0);
-                            title.add_css_class("section-title");
-
-                            container.append(&title);
-                            row.set_child(Some(&container));
-                            list_box.append(&row);
-                        }
-
-                        status_label.set_text(&format!("{} notes", notes.len()));
-
-                        if let Some(row) = list_box.row_at_index(0) {
-                            list_box.select_row(Some(&row));
-                        } else {
-                            reader.buffer().set_text("No notes yet.");
-                            selected_tags_label.set_text("Tags: -");
-                            selected_tags_entry.set_text("");
-                        }
-                    }
-                    Err(err) => {
-                        status_label.set_text("Search error");
-                        reader
-                            .buffer()
-                            .set_text(&format!("Search failed:\n{}", err));
-                    }
-                }
-            })
-        };
-
-        let on_save = {
-            let text_view = text_view.clone();
-            let capture_tags = capture_tags.clone();
-            let conn = Rc::clone(&conn);
-            let refresh_notes = Rc::clone(&refresh_notes);
-            move || {
-                let buffer = text_view.buffer();
-                let start = buffer.start_iter();
-                let end = buffer.end_iter();
-                let content = buffer.text(&start, &end, true);
-                let trimmed = content.trim();
-
-                if trimmed.is_empty() {
-                    return;
-                }
-
-                let tags = parse_tags(&capture_tags.text());
-
-                if db::insert_note(&mut conn.borrow_mut(), trimmed, &tags).is_ok() {
-                    buffer.set_text("");
-                    capture_tags.set_text("");
-                    let _ = Notification::new()
-                        .summary("Memo-Tori")
-                        .body("Note saved")
-                        .show();
-                    refresh_notes.as_ref()();
-                }
-            }
-        };
-
-        text_view.buffer().connect_changed({
-            let text_view = text_view.clone();
-            let placeholder_label = placeholder_label.clone();
-            let capture_hints = Rc::clone(&capture_hints);
-            let was_empty = Rc::new(RefCell::new(true));
-
-            move |_| {
-                let buffer = text_view.buffer();
-                let start = buffer.start_iter();
-                let end = buffer.end_iter();
-                let is_empty = buffer.text(&start, &end, true).trim().is_empty();
-
-                if is_empty {
-                    if !*was_empty.borrow() {
-                        placeholder_label.set_text(&random_hint(capture_hints.as_ref()));
-                    }
-                    placeholder_label.set_visible(true);
-                } else {
-                    placeholder_label.set_visible(false);
-                }
-
-                *was_empty.borrow_mut() = is_empty;
-            }
-        });
-
-        save_btn.connect_clicked({
-            let on_save = on_save.clone();
-            move |_| on_save()
-        });
+        let reminders = build_reminders_page(&worker);
 
-        cancel_btn.connect_clicked({
-            let text_view = text_view.clone();
-            move |_| {
-                text_view.buffer().set_text("");
-            }
+        let reminder_scheduler = Rc::new(ReminderScheduler {
+            window: window.clone(),
+            worker: worker.clone(),
+            timer: RefCell::new(None),
+            on_change: Rc::clone(&reminders.refresh),
         });
 
-        list_box.connect_row_selected({
-            let conn = Rc::clone(&conn);
-            let reader = reader.clone();
-            let notes_state = Rc::clone(&notes_state);
-            let selected_tags_label = selected_tags_label.clone();
-            let selected_tags_entry = selected_tags_entry.clone();
-            move |_, row| {
-                let Some(row) = row else {
-                    reader.buffer().set_text("No note selected.");
-                    selected_tags_label.set_text("Tags: -");
-                    selected_tags_entry.set_text("");
-                    return;
-                };
-
-                let index = row.index();
-                if index < 0 {
-                    reader.buffer().set_text("No note selected.");
-                    selected_tags_label.set_text("Tags: -");
-                    selected_tags_entry.set_text("");
-                    return;
-                }
-
-                let note_id = notes_state
-                    .borrow()
-                    .get(index as usize)
-                    .map(|note| note.id.clone());
-
-                let Some(note_id) = note_id else {
-                    reader.buffer().set_text("No note selected.");
-                    selected_tags_label.set_text("Tags: -");
-                    selected_tags_entry.set_text("");
+        let action_delete_reminder =
+            gio::SimpleAction::new("delete_reminder", Some(VariantTy::INT64));
+        action_delete_reminder.connect_activate({
+            let worker = worker.clone();
+            let reminder_scheduler = Rc::clone(&reminder_scheduler);
+            move |_, parameter| {
+                let Some(id) = parameter.and_then(|p| p.get::<i64>()) else {
                     return;
                 };
 
-                match db::get_note_content(&conn.borrow(), &note_id) {
-                    Ok(Some(content)) => reader.buffer().set_text(&content),
-                    Ok(None) => reader.buffer().set_text("Note not found."),
-                    Err(err) => reader
-                        .buffer()
-                        .set_text(&format!("Failed to load note:\n{}", err)),
-                }
+                let request = worker.call(move |conn| db::delete_reminder(conn, id));
 
-                match db::get_note_tags(&conn.borrow(), &note_id) {
-                    Ok(tags) => {
-                        if tags.is_empty() {
-                            selected_tags_label.set_text("Tags: -");
-                            selected_tags_entry.set_text("");
-                        } else {
-                            let joined = tags.join(", ");
-                            selected_tags_label.set_text(&format!("Tags: {}", joined));
-                            selected_tags_entry.set_text(&joined);
-                        }
+                let reminder_scheduler = Rc::clone(&reminder_scheduler);
+                glib::spawn_future_local(async move {
+                    if request.await.is_ok() {
+                        schedule_reminders(&reminder_scheduler);
                     }
-                    Err(_) => {
-                        selected_tags_label.set_text("Tags: error");
-                    }
-                }
+                });
             }
         });
+        app.add_action(&action_delete_reminder);
+
+        // Also fires the reminders missed while the app was closed.
+        schedule_reminders(&reminder_scheduler);
+
+        let trash = build_trash_page(&worker, &window);
+        let notes = build_notes_page(
+            app,
+            &worker,
+            &stack,
+            &reminder_scheduler,
+            hashtag_mode,
+            revision_retention,
+        );
+        install_notes_actions(app, &worker, &stack, &notes, &trash.refresh);
+        let capture = build_capture_page(
+            &worker,
+            &capture_hints,
+            hashtag_mode,
+            &reminder_scheduler,
+            &notes.notes_changed,
+        );
+        let tags = build_tags_page(app, &worker, &stack, &notes);
+        let tasks = build_tasks_page(&worker, hashtag_mode, &notes.notes_changed);
+
+        stack.add_titled(&capture, Some("capture"), "Capture");
+        stack.add_titled(&notes.root, Some("notes"), "Notes");
+        stack.add_titled(&trash.root, Some("trash"), "Trash");
+        stack.add_titled(&tags.root, Some("tags"), "Tags");
+        stack.add_titled(&tasks.root, Some("tasks"), "Tasks");
+        stack.add_titled(&reminders.root, Some("reminders"), "Reminders");
+        stack.set_visible_child_name("capture");
 
-        search_entry.connect_search_changed({
-            let refresh_notes = Rc::clone(&refresh_notes);
-            move |_| refresh_notes.as_ref()()
-        });
+        root.append(&menu_bar);
+        root.append(&switcher_wrap);
+        root.append(&stack);
+        window.set_child(Some(&root));
 
-        filter_tags_entry.connect_changed({
-            let refresh_notes = Rc::clone(&refresh_notes);
-            move |_| refresh_notes.as_ref()()
-        });
+        let file_dialog = Rc::new(RefCell::new(None::<gtk::FileChooserNative>));
 
-        apply_tags_btn.connect_clicked({
-            let conn = Rc::clone(&conn);
-            let notes_state = Rc::clone(&notes_state);
-            let list_box = list_box.clone();
-            let selected_tags_entry = selected_tags_entry.clone();
-            let selected_tags_label = selected_tags_label.clone();
-            let refresh_notes = Rc::clone(&refresh_notes);
-            move |_| {
-                let Some(row) = list_box.selected_row() else {
+        let action_export = gio::SimpleAction::new("export", Some(VariantTy::STRING));
+        action_export.connect_activate({
+            let worker = worker.clone();
+            let notes_model = notes.model.clone();
+            let window = window.clone();
+            let file_dialog = Rc::clone(&file_dialog);
+            move |_, parameter| {
+                let Some(target) = parameter.and_then(|p| p.str().map(str::to_string)) else {
                     return;
                 };
 
-                let index = row.index();
-                if index < 0 {
-                    return;
-                }
-
-                let note_id = notes_state
-                    .borrow()
-                    .get(index as usize)
-                    .map(|n| n.id.clone());
-
-                let Some(note_id) = note_id else {
-                    return;
+                let (results_only, mode) = match target.as_str() {
+                    "all-directory" => (false, ExportMode::Directory),
+                    "all-single" => (false, ExportMode::SingleFile),
+                    "results-directory" => (true, ExportMode::Directory),
+                    "results-single" => (true, ExportMode::SingleFile),
+                    _ => return,
                 };
 
-                let tags = parse_tags(&selected_tags_entry.text());
-                if db::replace_note_tags(&mut conn.borrow_mut(), &note_id, &tags).is_ok() {
-                    if tags.is_empty() {
-                        selected_tags_label.set_text("Tags: -");
-                    } else {
-                        selected_tags_label.set_text(&format!("Tags: {}", tags.join(", ")));
-                    }
-                    refresh_notes.as_ref()();
-                }
-            }
-        });
+                let filter = results_only.then(|| notes_model.filter());
 
-        save_note_btn.connect_clicked({
-            let conn = Rc::clone(&conn);
-            let notes_state = Rc::clone(&notes_state);
-            let list_box = list_box.clone();
-            let reader = reader.clone();
-            let refresh_notes = Rc::clone(&refresh_notes);
-            move |_| {
-                let Some(row) = list_box.selected_row() else {
-                    return;
+                let chooser_action = match mode {
+                    ExportMode::Directory => gtk::FileChooserAction::SelectFolder,
+                    ExportMode::SingleFile => gtk::FileChooserAction::Save,
                 };
 
-                let index = row.index();
-                if index < 0 {
-                    return;
+                let chooser = gtk::FileChooserNative::new(
+                    Some("Export Markdown"),
+                    Some(&window),
+                    chooser_action,
+                    Some("Export"),
+                    Some("Cancel"),
+                );
+                if mode == ExportMode::SingleFile {
+                    chooser.set_current_name("memo-tori-export.md");
                 }
 
-                let note_id = notes_state
-                    .borrow()
-                    .get(index as usize)
-                    .map(|n| n.id.clone());
-
-                let Some(note_id) = note_id else {
-                    return;
-                };
+                chooser.connect_response({
+                    let worker = worker.clone();
+                    let file_dialog = Rc::clone(&file_dialog);
+                    move |chooser, response| {
+                        let path = chooser.file().and_then(|file| file.path());
+                        file_dialog.borrow_mut().take();
 
-                let buffer = reader.buffer();
-                let start = buffer.start_iter();
-                let end = buffer.end_iter();
-                let content = buffer.text(&start, &end, true).to_string();
+                        if response != gtk::ResponseType::Accept {
+                            return;
+                        }
+                        let Some(path) = path else {
+                            return;
+                        };
+
+                        let filter = filter.clone();
+                        let request = worker.call(move |conn| {
+                            let note_ids = match &filter {
+                                Some(search) => Some(
+                                    db::search_notes(conn, search, -1)?
+                                        .into_iter()
+                                        .map(|note| note.id)
+                                        .collect::<Vec<_>>(),
+                                ),
+                                None => None,
+                            };
+                            let records = db::get_note_records(conn, note_ids.as_deref())?;
+                            export::export_notes(&records, &path, mode)
+                        });
+
+                        glib::spawn_future_local(async move {
+                            let body = match request.await {
+                                Ok(count) => format!("{} notes exported", count),
+                                Err(err) => format!("Export failed: {}", err),
+                            };
+                            let _ = Notification::new().summary("Memo-Tori").body(&body).show();
+                        });
+                    }
+                });
 
-                if db::update_note_content(&mut conn.borrow_mut(), &note_id, content.trim()).is_ok()
-                {
-                    let _ = Notification::new()
-                        .summary("Memo-Tori")
-                        .body("Note updated")
-                        .show();
-                    refresh_notes.as_ref()();
-                }
+                chooser.show();
+                *file_dialog.borrow_mut() = Some(chooser);
             }
         });
+        app.add_action(&action_export);
+
+        let action_import = gio::SimpleAction::new("import", None);
+        action_import.connect_activate({
+            let worker = worker.clone();
+            let window = window.clone();
+            let file_dialog = Rc::clone(&file_dialog);
+            let notes_changed = Rc::clone(&notes.notes_changed);
+            move |_, _| {
+                let chooser = gtk::FileChooserNative::new(
+                    Some("Import Markdown folder"),
+                    Some(&window),
+                    gtk::FileChooserAction::SelectFolder,
+                    Some("Import"),
+                    Some("Cancel"),
+                );
+
+                chooser.connect_response({
+                    let worker = worker.clone();
+                    let file_dialog = Rc::clone(&file_dialog);
+                    let notes_changed = Rc::clone(&notes_changed);
+                    move |chooser, response| {
+                        let path = chooser.file().and_then(|file| file.path());
+                        file_dialog.borrow_mut().take();
+
+                        if response != gtk::ResponseType::Accept {
+                            return;
+                        }
+                        let Some(path) = path else {
+                            return;
+                        };
+
+                        let request = worker.call(move |conn| import::import_path(conn, &path));
+
+                        let notes_changed = Rc::clone(&notes_changed);
+                        glib::spawn_future_local(async move {
+                            let body = match request.await {
+                                Ok(report) => format!(
+                                    "{} notes imported ({} skipped, {} failed)",
+                                    report.imported,
+                                    report.skipped,
+                                    report.failed.len()
+                                ),
+                                Err(err) => format!("Import failed: {}", err),
+                            };
+                            let _ = Notification::new().summary("Memo-Tori").body(&body).show();
+                            notes_changed.as_ref()();
+                        });
+                    }
+                });
 
-        refresh_notes.as_ref()();
-
-        let key_controller = gtk::EventControllerKey::new();
-        key_controller.connect_key_pressed({
-            let on_save = on_save.clone();
-            let text_view = text_view.clone();
-            move |_, key, _, state| {
-                if key == gdk::Key::Return && !state.contains(gdk::ModifierType::SHIFT_MASK) {
-                    on_save();
-                    return Propagation::Stop;
-                }
-
-                if key == gdk::Key::Escape {
-                    text_view.buffer().set_text("");
-                    return Propagation::Stop;
-                }
-
-                Propagation::Proceed
+                chooser.show();
+                *file_dialog.borrow_mut() = Some(chooser);
             }
         });
-        text_view.add_controller(key_controller);
+        app.add_action(&action_import);
+
+        // Counts change with every note edit, so they are fetched again
+        // whenever the page is shown.
+        for (name, page) in [
+            ("tags", &tags),
+            ("tasks", &tasks),
+            ("reminders", &reminders),
+        ] {
+            stack.connect_visible_child_name_notify({
+                let refresh = Rc::clone(&page.refresh);
+                move |stack| {
+                    if stack.visible_child_name().as_deref() == Some(name) {
+                        refresh.as_ref()();
+                    }
+                }
+            });
+        }
+
+        notes.notes_changed.as_ref()();
+        trash.refresh.as_ref()();
 
         let nav_controller = gtk::EventControllerKey::new();
         nav_controller.connect_key_pressed({
             let stack = stack.clone();
             move |_, key, _, state| {
                 if state.contains(gdk::ModifierType::CONTROL_MASK) && key == gdk::Key::Tab {
-                    let current = stack.visible_child_name();
-                    if current.as_deref() == Some("capture") {
-                        stack.set_visible_child_name("notes");
-                    } else {
-                        stack.set_visible_child_name("capture");
-                    }
+                    let next = match stack.visible_child_name().as_deref() {
+                        Some("capture") => "notes",
+                        Some("notes") => "trash",
+                        Some("trash") => "tags",
+                        Some("tags") => "tasks",
+                        Some("tasks") => "reminders",
+                        _ => "capture",
+                    };
+                    stack.set_visible_child_name(next);
                     return Propagation::Stop;
                 }
 
@@ -814,15 +4400,20 @@ pub fn run(config: AppConfig, connection: Connection) -> Result<()> {
         });
         window.add_controller(nav_controller);
 
-        window.connect_close_request(move |win| {
-            if quit_on_close {
-                Propagation::Proceed
-            } else {
-                win.hide();
-                Propagation::Stop
+        window.connect_close_request({
+            let main_window = Rc::clone(&main_window);
+            move |win| {
+                if quit_on_close {
+                    main_window.borrow_mut().take();
+                    Propagation::Proceed
+                } else {
+                    win.hide();
+                    Propagation::Stop
+                }
             }
         });
 
+        *main_window.borrow_mut() = Some(window.clone());
         window.present();
     });
 
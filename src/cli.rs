@@ -0,0 +1,439 @@
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+use anyhow::{bail, Context, Result};
+use rusqlite::Connection;
+use serde::Serialize;
+
+use crate::db;
+use crate::export::{self, ExportMode};
+use crate::hashtags::HashtagMode;
+use crate::import;
+use crate::query::{self, QueryMode, SearchQuery};
+use crate::version;
+
+const USAGE: &str = "\
+Usage: memo-tori-gtk [COMMAND]
+
+Without a command, the GTK window is opened.
+
+Commands:
+  add <text|-> [--tag a,b]                    Save a note (- reads stdin)
+  search [query] [--tag a,b] [--limit N] [--json] [--fts]
+                                              Search notes (words plus tag:,
+                                              -tag:, created:, updated:,
+                                              is:pinned, is:deleted, has:link;
+                                              --fts reads raw FTS5 syntax)
+  show <id> [--json]                          Print a note
+  tags [--json]                               List every tag
+  delete <id>                                 Move a note to the trash
+  export <path> [query] [--tag a,b] [--single] [--fts]
+                                              Export notes as Markdown, one file
+                                              per note in <path> or, with
+                                              --single, all in the file <path>
+                                              (one-way: it imports as one note)
+  import <path>                               Import Markdown files (with optional
+                                              YAML/TOML front matter) from a
+                                              folder or a single file
+
+A `--` after a command ends its options: `add -- -5 degrees` saves
+\"-5 degrees\".
+
+Options:
+  --capture                                   Open the quick-capture window
+                                              (in the running instance, if any)
+  --version                                   Print the version
+  --help                                      Print this help";
+
+#[derive(Debug)]
+pub enum Command {
+    Help,
+    Version,
+    Add {
+        content: Option<String>,
+        tags: Vec<String>,
+    },
+    Search {
+        query: String,
+        tags: Vec<String>,
+        limit: i64,
+        json: bool,
+        advanced: bool,
+    },
+    Show {
+        id: String,
+        json: bool,
+    },
+    Tags {
+        json: bool,
+    },
+    Delete {
+        id: String,
+    },
+    Export {
+        target: PathBuf,
+        query: String,
+        tags: Vec<String>,
+        single: bool,
+        advanced: bool,
+    },
+    Import {
+        source: PathBuf,
+    },
+}
+
+#[derive(Serialize)]
+struct NoteOutput {
+    id: String,
+    pinned: bool,
+    tags: Vec<String>,
+    content: String,
+}
+
+// Query text is read like the search field, so any input is safe; `--fts`
+// keeps raw FTS5 syntax. `--tag` tags are all required.
+fn build_query(input: &str, tags: &[String], advanced: bool) -> Result<SearchQuery> {
+    let mode = if advanced {
+        QueryMode::Advanced
+    } else {
+        QueryMode::Simple
+    };
+    let mut search = query::parse(input, mode, db::unix_seconds()?);
+    search.require_tags(tags);
+    Ok(search)
+}
+
+fn split_tags(input: &str) -> Vec<String> {
+    input
+        .split(',')
+        .map(|part| part.trim().to_string())
+        .filter(|tag| !tag.is_empty())
+        .collect()
+}
+
+fn option_value(
+    name: &str,
+    inline: Option<&str>,
+    rest: &mut impl Iterator<Item = String>,
+) -> Result<String> {
+    match inline {
+        Some(value) => Ok(value.to_string()),
+        None => rest
+            .next()
+            .with_context(|| format!("missing value for {}", name)),
+    }
+}
+
+/// Returns `None` when the arguments do not name a subcommand, in which case
+/// the GTK application is started and receives them unchanged.
+pub fn parse(args: &[String]) -> Result<Option<Command>> {
+    let Some(command) = args.first() else {
+        return Ok(None);
+    };
+
+    match command.as_str() {
+        "--help" | "-h" | "help" => return Ok(Some(Command::Help)),
+        "--version" => return Ok(Some(Command::Version)),
+        "add" | "search" | "show" | "tags" | "delete" | "export" | "import" => {}
+        other if other.starts_with('-') => return Ok(None),
+        other => bail!("unknown command `{}`\n\n{}", other, USAGE),
+    }
+
+    let mut positional = Vec::new();
+    let mut tags = Vec::new();
+    let mut limit = 50;
+    let mut json = false;
+    let mut single = false;
+    let mut advanced = false;
+
+    let mut rest = args[1..].iter().cloned();
+    while let Some(arg) = rest.next() {
+        if arg == "--" {
+            positional.extend(rest.by_ref());
+            break;
+        }
+
+        let (name, inline) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => (name.to_string(), Some(value)),
+            _ => (arg.clone(), None),
+        };
+
+        match name.as_str() {
+            "--tag" | "--tags" | "-t" => {
+                tags.extend(split_tags(&option_value(&name, inline, &mut rest)?));
+            }
+            "--limit" | "-n" => {
+                limit = option_value(&name, inline, &mut rest)?
+                    .parse()
+                    .context("--limit expects a number")?;
+            }
+            "--json" => json = true,
+            "--single" => single = true,
+            "--fts" | "--advanced" => advanced = true,
+            "-" => positional.push(arg),
+            flag if flag.starts_with('-') => bail!("unknown option `{}`\n\n{}", flag, USAGE),
+            _ => positional.push(arg),
+        }
+    }
+
+    let parsed = match command.as_str() {
+        "add" => {
+            let content = match positional.as_slice() {
+                [] => bail!("add expects note text or `-` to read stdin"),
+                [dash] if dash == "-" => None,
+                words => Some(words.join(" ")),
+            };
+            Command::Add { content, tags }
+        }
+        "search" => Command::Search {
+            query: positional.join(" "),
+            tags,
+            limit,
+            json,
+            advanced,
+        },
+        "show" => match positional.as_slice() {
+            [id] => Command::Show {
+                id: id.clone(),
+                json,
+            },
+            _ => bail!("show expects exactly one note id"),
+        },
+        "tags" => Command::Tags { json },
+        "delete" => match positional.as_slice() {
+            [id] => Command::Delete { id: id.clone() },
+            _ => bail!("delete expects exactly one note id"),
+        },
+        "export" => match positional.split_first() {
+            Some((target, query)) => Command::Export {
+                target: PathBuf::from(target),
+                query: query.join(" "),
+                tags,
+                single,
+                advanced,
+            },
+            None => bail!("export expects a target path"),
+        },
+        "import" => match positional.as_slice() {
+            [source] => Command::Import {
+                source: PathBuf::from(source),
+            },
+            _ => bail!("import expects exactly one folder or file"),
+        },
+        _ => unreachable!(),
+    };
+
+    Ok(Some(parsed))
+}
+
+fn print_json<T: Serialize>(value: &T) -> Result<()> {
+    let raw = serde_json::to_string_pretty(value).context("failed to encode JSON output")?;
+    println!("{}", raw);
+    Ok(())
+}
+
+/// Answers `--help` and `--version`, which need no database. Returns whether
+/// `command` was one of them.
+pub fn run_without_database(command: &Command) -> bool {
+    match command {
+        Command::Help => println!("{}", USAGE),
+        Command::Version => println!("{}", version::VERSION),
+        _ => return false,
+    }
+    true
+}
+
+pub fn run(command: Command, conn: &mut Connection, hashtags: HashtagMode) -> Result<()> {
+    match command {
+        Command::Help | Command::Version => {
+            run_without_database(&command);
+        }
+        Command::Add { content, tags } => {
+            let content = match content {
+                Some(content) => content,
+                None => {
+                    let mut buffer = String::new();
+                    io::stdin()
+                        .read_to_string(&mut buffer)
+                        .context("failed to read note from stdin")?;
+                    buffer
+                }
+            };
+
+            let trimmed = content.trim();
+            if trimmed.is_empty() {
+                bail!("refusing to save an empty note");
+            }
+
+            let id = db::insert_note(conn, trimmed, &tags, hashtags)?;
+            println!("{}", id);
+        }
+        Command::Search {
+            query,
+            tags,
+            limit,
+            json,
+            advanced,
+        } => {
+            let notes = db::search_notes(conn, &build_query(&query, &tags, advanced)?, limit)?;
+
+            if json {
+                let mut output = Vec::with_capacity(notes.len());
+                for note in notes {
+                    output.push(NoteOutput {
+                        tags: db::get_note_tags(conn, &note.id)?,
+                        id: note.id,
+                        pinned: note.pinned,
+                        content: note.preview,
+                    });
+                }
+                print_json(&output)?;
+            } else {
+                for note in &notes {
+                    println!("{}\t{}", note.id, db::note_title(&note.preview));
+                }
+            }
+        }
+        Command::Show { id, json } => {
+            let Some(note) = db::get_note(conn, &id)? else {
+                bail!("no note with id {}", id);
+            };
+            let tags = db::get_note_tags(conn, &id)?;
+
+            if json {
+                print_json(&NoteOutput {
+                    id: note.id,
+                    pinned: note.pinned,
+                    tags,
+                    content: note.preview,
+                })?;
+            } else {
+                if !tags.is_empty() {
+                    println!("Tags: {}\n", tags.join(", "));
+                }
+                println!("{}", note.preview);
+            }
+        }
+        Command::Tags { json } => {
+            let tags = db::list_tags(conn)?;
+            if json {
+                print_json(&tags)?;
+            } else {
+                for tag in tags {
+                    println!("{}", tag);
+                }
+            }
+        }
+        Command::Delete { id } => {
+            if db::get_note(conn, &id)?.is_none() {
+                bail!("no note with id {}", id);
+            }
+            db::soft_delete_note(conn, &id)?;
+        }
+        Command::Export {
+            target,
+            query,
+            tags,
+            single,
+            advanced,
+        } => {
+            let records = if query.trim().is_empty() && tags.is_empty() {
+                db::get_note_records(conn, None)?
+            } else {
+                let ids = db::search_notes(conn, &build_query(&query, &tags, advanced)?, -1)?
+                    .into_iter()
+                    .map(|note| note.id)
+                    .collect::<Vec<_>>();
+                db::get_note_records(conn, Some(&ids))?
+            };
+
+            let mode = if single {
+                ExportMode::SingleFile
+            } else {
+                ExportMode::Directory
+            };
+            if single && target.exists() {
+                bail!("{} already exists", target.display());
+            }
+
+            let count = export::export_notes(&records, &target, mode)?;
+            eprintln!("Exported {} notes to {}", count, target.display());
+        }
+        Command::Import { source } => {
+            let report = import::import_path(conn, &source)?;
+            for (path, err) in &report.failed {
+                eprintln!("{}: {}", path.display(), err);
+            }
+            eprintln!(
+                "Imported {} of {} files ({} skipped, {} failed)",
+                report.imported,
+                report.scanned,
+                report.skipped,
+                report.failed.len()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn version_is_only_read_as_the_first_argument() {
+        assert!(matches!(
+            parse(&args(&["--version"])).unwrap(),
+            Some(Command::Version)
+        ));
+        let Some(Command::Add { content, .. }) =
+            parse(&args(&["add", "see --version flag"])).unwrap()
+        else {
+            panic!("expected an add command");
+        };
+        assert_eq!(content.as_deref(), Some("see --version flag"));
+    }
+
+    #[test]
+    fn double_dash_ends_the_options() {
+        let Some(Command::Add { content, tags }) = parse(&args(&[
+            "add", "--tag", "meteo", "--", "-5", "degrees", "--json",
+        ]))
+        .unwrap() else {
+            panic!("expected an add command");
+        };
+        assert_eq!(content.as_deref(), Some("-5 degrees --json"));
+        assert_eq!(tags, ["meteo"]);
+    }
+
+    #[test]
+    fn search_reads_stray_quotes_unless_fts_is_asked() {
+        let mut conn = db::open_and_init(std::path::Path::new(":memory:")).unwrap();
+        db::insert_note(&mut conn, "it\"s raining", &[], HashtagMode::Keep).unwrap();
+
+        let Some(Command::Search {
+            query,
+            tags,
+            advanced,
+            ..
+        }) = parse(&args(&["search", "it\"s"])).unwrap()
+        else {
+            panic!("expected a search command");
+        };
+        assert!(!advanced);
+        let notes = db::search_notes(&conn, &build_query(&query, &tags, advanced).unwrap(), 10);
+        assert_eq!(notes.unwrap().len(), 1);
+
+        let Some(Command::Search { advanced, .. }) =
+            parse(&args(&["search", "--fts", "rain*"])).unwrap()
+        else {
+            panic!("expected a search command");
+        };
+        assert!(advanced);
+    }
+}
@@ -0,0 +1,103 @@
+// Tag colors. Chips draw their label over the tag color, so the label color
+// is picked here to keep the pair readable.
+
+/// WCAG 2.1 AA contrast ratio for normal text.
+pub const MIN_CONTRAST: f64 = 4.5;
+
+// Label colors tried in order: the app's usual text color, then white, then
+// black. Any background reaches AA against white or black.
+const TEXT_COLORS: [&str; 3] = ["#172127", "#ffffff", "#000000"];
+
+fn parse_hex(color: &str) -> Option<[u8; 3]> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.chars().all(|ch| ch.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        3 => {
+            let mut rgb = [0; 3];
+            for (index, digit) in hex.chars().enumerate() {
+                rgb[index] = channel(&format!("{0}{0}", digit))?;
+            }
+            Some(rgb)
+        }
+        6 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ]),
+        _ => None,
+    }
+}
+
+/// `#rgb` or `#rrggbb` as lowercase `#rrggbb`, or `None` if it is neither.
+pub fn normalize(color: &str) -> Option<String> {
+    let [r, g, b] = parse_hex(color)?;
+    Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+}
+
+fn relative_luminance(rgb: [u8; 3]) -> f64 {
+    let linear = |channel: u8| {
+        let value = f64::from(channel) / 255.0;
+        if value <= 0.039_28 {
+            value / 12.92
+        } else {
+            ((value + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(rgb[0]) + 0.7152 * linear(rgb[1]) + 0.0722 * linear(rgb[2])
+}
+
+/// Contrast ratio between two colors, from 1 (identical) to 21.
+pub fn contrast_ratio(a: &str, b: &str) -> Option<f64> {
+    let a = relative_luminance(parse_hex(a)?);
+    let b = relative_luminance(parse_hex(b)?);
+    Some((a.max(b) + 0.05) / (a.min(b) + 0.05))
+}
+
+/// The label color for a chip drawn over `background`, or `None` if that is
+/// not a color.
+pub fn chip_text_color(background: &str) -> Option<&'static str> {
+    TEXT_COLORS
+        .into_iter()
+        .find(|text| contrast_ratio(background, text).is_some_and(|ratio| ratio >= MIN_CONTRAST))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_short_and_long_forms() {
+        assert_eq!(normalize("#F80"), Some("#ff8800".to_string()));
+        assert_eq!(normalize(" #3B82F6 "), Some("#3b82f6".to_string()));
+        assert_eq!(normalize("3b82f6"), None);
+        assert_eq!(normalize("#12345"), None);
+        assert_eq!(normalize("#gggggg"), None);
+    }
+
+    #[test]
+    fn contrast_matches_reference_values() {
+        let ratio = contrast_ratio("#000000", "#ffffff").unwrap();
+        assert!((ratio - 21.0).abs() < 1e-9);
+        assert!((contrast_ratio("#777777", "#ffffff").unwrap() - 4.48).abs() < 0.01);
+    }
+
+    #[test]
+    fn chip_labels_reach_aa_on_any_background() {
+        assert_eq!(chip_text_color("#fde68a"), Some("#172127"));
+        assert_eq!(chip_text_color("#1d4ed8"), Some("#ffffff"));
+        for shade in (0..=255u8).step_by(5) {
+            let background = format!("#{0:02x}{0:02x}{0:02x}", shade);
+            let text = chip_text_color(&background).unwrap();
+            assert!(contrast_ratio(&background, text).unwrap() >= MIN_CONTRAST);
+        }
+        for background in ["#ff0000", "#00ff00", "#0000ff", "#ff00ff", "#808000"] {
+            let text = chip_text_color(background).unwrap();
+            assert!(contrast_ratio(background, text).unwrap() >= MIN_CONTRAST);
+        }
+        assert_eq!(chip_text_color("red"), None);
+    }
+}
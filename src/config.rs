@@ -4,6 +4,9 @@ use std::path::Path;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 
+use crate::db::SearchTokenizer;
+use crate::hashtags::HashtagMode;
+
 pub fn default_capture_hints() -> Vec<String> {
     vec![
         "L'idee que je viens d'avoir :".to_string(),
@@ -21,6 +24,18 @@ pub struct AppConfig {
     pub text_scale: f32,
     #[serde(default = "default_capture_hints")]
     pub capture_hints: Vec<String>,
+    /// Notes left in the trash longer than this are purged at startup.
+    pub trash_auto_purge_days: Option<u32>,
+    /// Number of previous versions kept per note.
+    pub revisions_keep_last: Option<u32>,
+    /// Previous versions older than this are dropped.
+    pub revisions_keep_days: Option<u32>,
+    /// `unicode61` (whole words), `porter` (English-only stemming, no help
+    /// for French) or `trigram`. Changing it rebuilds the search index at the
+    /// next start.
+    pub search_tokenizer: SearchTokenizer,
+    /// Whether `#hashtags` stay in the note text once turned into tags.
+    pub hashtags: HashtagMode,
 }
 
 impl Default for AppConfig {
@@ -29,6 +44,11 @@ impl Default for AppConfig {
             quit_on_close: false,
             text_scale: 1.0,
             capture_hints: default_capture_hints(),
+            trash_auto_purge_days: None,
+            revisions_keep_last: Some(50),
+            revisions_keep_days: None,
+            search_tokenizer: SearchTokenizer::default(),
+            hashtags: HashtagMode::default(),
         }
     }
 }
@@ -0,0 +1,104 @@
+// Calendar conversions for the unix-second timestamps stored in the database.
+// Only UTC is handled; the algorithms are Howard Hinnant's civil date helpers.
+
+/// Days from 1970-01-01 to a calendar date.
+pub fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let mp = i64::from(if month > 2 { month - 3 } else { month + 9 });
+    let day_of_year = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Formats unix seconds as an RFC 3339 UTC timestamp, e.g. `2026-02-17T09:30:00Z`.
+pub fn format_rfc3339(secs: i64) -> String {
+    let days = secs.div_euclid(86_400);
+    let rem = secs.rem_euclid(86_400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        rem / 3600,
+        (rem % 3600) / 60,
+        rem % 60
+    )
+}
+
+fn take_number(input: &str, digits: usize) -> Option<(i64, &str)> {
+    if input.len() < digits || !input.as_bytes()[..digits].iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    Some((input[..digits].parse().ok()?, &input[digits..]))
+}
+
+/// Parses unix seconds, `YYYY-MM-DD`, or an RFC 3339 / ISO 8601 date-time
+/// (`YYYY-MM-DDTHH:MM[:SS[.frac]][Z|±HH:MM]`). Missing offsets are read as UTC.
+pub fn parse_timestamp(input: &str) -> Option<i64> {
+    let input = input.trim();
+    if !input.is_empty() && input.bytes().all(|b| b.is_ascii_digit()) {
+        return input.parse().ok();
+    }
+
+    let (year, rest) = take_number(input, 4)?;
+    let (month, rest) = take_number(rest.strip_prefix('-')?, 2)?;
+    let (day, rest) = take_number(rest.strip_prefix('-')?, 2)?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let date = days_from_civil(year, month as u32, day as u32) * 86_400;
+    let Some(rest) = rest.strip_prefix(['T', 't', ' ']) else {
+        return rest.is_empty().then_some(date);
+    };
+
+    let (hour, rest) = take_number(rest, 2)?;
+    let (minute, mut rest) = take_number(rest.strip_prefix(':')?, 2)?;
+    let mut second = 0;
+    if let Some(after) = rest.strip_prefix(':') {
+        (second, rest) = take_number(after, 2)?;
+        if let Some(fraction) = rest.strip_prefix('.') {
+            rest = fraction.trim_start_matches(|c: char| c.is_ascii_digit());
+        }
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    let offset = match rest {
+        "" | "Z" | "z" => 0,
+        _ => {
+            let sign = match rest.as_bytes()[0] {
+                b'+' => 1,
+                b'-' => -1,
+                _ => return None,
+            };
+            let (off_hour, after) = take_number(&rest[1..], 2)?;
+            let after = after.strip_prefix(':').unwrap_or(after);
+            let (off_minute, after) = take_number(after, 2)?;
+            if !after.is_empty() {
+                return None;
+            }
+            sign * (off_hour * 3600 + off_minute * 60)
+        }
+    };
+
+    Some(date + hour * 3600 + minute * 60 + second - offset)
+}
@@ -1,31 +1,395 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::ops::Range;
 use std::path::Path;
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use rusqlite::types::Value;
-use rusqlite::{params, params_from_iter, Connection};
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, Transaction};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
 use uuid::Uuid;
 
-const SCHEMA_SQL: &str = include_str!("../migrations/001_init.sql");
+use crate::colors;
+use crate::fuzzy;
+use crate::hashtags::{self, HashtagMode};
+use crate::markdown;
+use crate::migrations;
+use crate::query::{Filter, QueryMode, SearchQuery};
+use crate::wikilinks;
 
 #[derive(Debug, Clone)]
 pub struct NoteListItem {
     pub id: String,
     pub preview: String,
+    pub pinned: bool,
+}
+
+/// One row of the note list. Built from the stored title and excerpt, so the
+/// note content itself is never loaded.
+#[derive(Debug, Clone)]
+pub struct NoteSummary {
+    pub id: String,
+    pub title: String,
+    /// The FTS match in context when searching, otherwise the excerpt.
+    pub snippet: String,
+    /// Byte ranges of the matched terms within `snippet`.
+    pub snippet_matches: Vec<Range<usize>>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub pinned: bool,
+    pub tags: Vec<NoteTag>,
+}
+
+/// A tag as drawn on a chip, with its optional color and emoji.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NoteTag {
+    pub name: String,
+    /// `#rrggbb`.
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
+
+/// Sort key of the last row of a page; the next page starts right after it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NoteCursor {
+    pinned: bool,
+    score: f64,
+    updated_at: String,
+    id: String,
+}
+
+#[derive(Debug, Clone)]
+pub enum PageStart {
+    After(NoteCursor),
+    /// Fallback for pages whose predecessor has not been loaded yet.
+    Offset(i64),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct NotePage {
+    pub items: Vec<NoteSummary>,
+    pub next: Option<NoteCursor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteRecord {
+    pub id: String,
+    pub content: String,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct NoteRevision {
+    pub id: i64,
+    pub content: String,
+    pub created_at: i64,
+}
+
+/// A search kept in the sidebar: the search field text and its mode.
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    pub id: i64,
+    pub name: String,
+    pub query: String,
+    pub mode: QueryMode,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RevisionRetention {
+    pub keep_last: Option<u32>,
+    pub keep_days: Option<u32>,
+}
+
+/// How note text is split into searchable terms. Every option ignores case
+/// and accents.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchTokenizer {
+    /// Whole words.
+    #[default]
+    Unicode61,
+    /// Whole words reduced to their English stem (`running` finds `run`).
+    Porter,
+    /// Any run of three or more characters, including inside words.
+    Trigram,
+}
+
+impl SearchTokenizer {
+    fn fts5_option(self) -> &'static str {
+        match self {
+            Self::Unicode61 => "unicode61 remove_diacritics 2",
+            Self::Porter => "porter unicode61 remove_diacritics 2",
+            Self::Trigram => "trigram remove_diacritics 1",
+        }
+    }
 }
 
 pub fn open_and_init(db_path: &Path) -> Result<Connection> {
-    let conn = Connection::open(db_path).context("failed to open sqlite database")?;
-    conn.execute_batch(SCHEMA_SQL)
-        .context("failed to initialize sqlite schema")?;
+    let mut conn = Connection::open(db_path).context("failed to open sqlite database")?;
+    migrations::run(&mut conn, db_path).context("failed to migrate sqlite schema")?;
+    backfill_note_summaries(&mut conn)?;
+    backfill_content_hashes(&mut conn)?;
+    fold_tag_names(&mut conn)?;
+    backfill_note_links(&mut conn)?;
+    backfill_note_tasks(&mut conn)?;
     Ok(conn)
 }
 
+/// Rebuilds the full-text index when it was built with another tokenizer.
+pub fn set_search_tokenizer(conn: &mut Connection, tokenizer: SearchTokenizer) -> Result<()> {
+    let option = tokenizer.fts5_option();
+    let current: String = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'notes_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .context("failed to read full-text index definition")?;
+
+    if current.contains(&format!("tokenize = '{}'", option)) {
+        return Ok(());
+    }
+
+    let tx = conn
+        .transaction()
+        .context("failed to start full-text index rebuild")?;
+
+    tx.execute_batch(&format!(
+        "DROP TABLE notes_fts;
+         CREATE VIRTUAL TABLE notes_fts USING fts5(
+             note_id UNINDEXED,
+             content,
+             tokenize = '{}'
+         );
+         INSERT INTO notes_fts (note_id, content)
+         SELECT id, content FROM notes;",
+        option
+    ))
+    .context("failed to rebuild full-text index")?;
+
+    tx.commit()
+        .context("failed to commit full-text index rebuild")
+}
+
+// Tags created before names were folded are renamed, or merged into the
+// tag that already has the folded name.
+fn fold_tag_names(conn: &mut Connection) -> Result<()> {
+    let tags = {
+        let mut stmt = conn
+            .prepare("SELECT id, name FROM tags")
+            .context("failed to prepare tag folding")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query tags")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode tags")?
+    };
+
+    let unfolded: Vec<(i64, String)> = tags
+        .into_iter()
+        .filter_map(|(id, name)| {
+            let folded = fold_tag(&name);
+            (folded != name).then_some((id, folded))
+        })
+        .collect();
+
+    if unfolded.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn.transaction().context("failed to start tag folding")?;
+
+    for (id, folded) in unfolded {
+        match tag_id(&tx, &folded)? {
+            Some(target) => {
+                move_tag_links(&tx, id, target)?;
+                tx.execute("DELETE FROM tags WHERE id = ?1", params![id])
+                    .context("failed to remove merged tag")?;
+            }
+            None => {
+                tx.execute(
+                    "UPDATE tags SET name = ?2 WHERE id = ?1",
+                    params![id, folded],
+                )
+                .context("failed to rename tag")?;
+            }
+        }
+    }
+
+    tx.commit().context("failed to commit tag folding")
+}
+
+// Notes written before titles were stored get them computed once; the
+// partial index keeps this check free when nothing is missing.
+fn backfill_note_summaries(conn: &mut Connection) -> Result<()> {
+    let notes = {
+        let mut stmt = conn
+            .prepare("SELECT id, content FROM notes WHERE title IS NULL")
+            .context("failed to prepare note title backfill")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query notes without title")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode notes without title")?
+    };
+
+    if notes.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn
+        .transaction()
+        .context("failed to start note title backfill")?;
+
+    for (id, content) in notes {
+        tx.prepare_cached("UPDATE notes SET title = ?2, excerpt = ?3 WHERE id = ?1")
+            .and_then(|mut stmt| {
+                stmt.execute(params![id, note_title(&content), note_excerpt(&content)])
+            })
+            .context("failed to store note title")?;
+    }
+
+    tx.commit().context("failed to commit note title backfill")
+}
+
+// Notes written before content hashes were stored get them computed once.
+fn backfill_content_hashes(conn: &mut Connection) -> Result<()> {
+    let notes = {
+        let mut stmt = conn
+            .prepare("SELECT id, content FROM notes WHERE content_hash IS NULL")
+            .context("failed to prepare content hash backfill")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query notes without content hash")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode notes without content hash")?
+    };
+
+    if notes.is_empty() {
+        return Ok(());
+    }
+
+    let tx = conn
+        .transaction()
+        .context("failed to start content hash backfill")?;
+
+    for (id, content) in notes {
+        tx.prepare_cached("UPDATE notes SET content_hash = ?2 WHERE id = ?1")
+            .and_then(|mut stmt| stmt.execute(params![id, content_hash(&content)]))
+            .context("failed to store content hash")?;
+    }
+
+    tx.commit()
+        .context("failed to commit content hash backfill")
+}
+
+pub fn note_title(content: &str) -> String {
+    content
+        .lines()
+        .find(|line| !line.trim().is_empty())
+        .map(|line| line.trim().chars().take(60).collect::<String>())
+        .filter(|title| !title.is_empty())
+        .unwrap_or_else(|| "(empty note)".to_string())
+}
+
+/// The lines after the title joined into one line, cut to 140 characters.
+pub fn note_excerpt(content: &str) -> String {
+    let mut excerpt = String::new();
+
+    for line in content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .skip(1)
+    {
+        if !excerpt.is_empty() {
+            excerpt.push(' ');
+        }
+        excerpt.push_str(line);
+        if excerpt.chars().count() >= 140 {
+            break;
+        }
+    }
+
+    excerpt.chars().take(140).collect()
+}
+
+// Delimiters passed to snippet()/highlight(). Control characters never typed
+// into a note, so they can be stripped back out unambiguously.
+const MATCH_OPEN: char = '\u{2}';
+const MATCH_CLOSE: char = '\u{3}';
+
+/// Removes the match delimiters from FTS output, returning the plain text and
+/// the byte ranges that were delimited.
+fn split_matches(marked: &str) -> (String, Vec<Range<usize>>) {
+    let mut text = String::with_capacity(marked.len());
+    let mut matches = Vec::new();
+    let mut open = None;
+
+    for ch in marked.chars() {
+        match ch {
+            MATCH_OPEN => open = Some(text.len()),
+            MATCH_CLOSE => {
+                if let Some(start) = open.take() {
+                    matches.push(start..text.len());
+                }
+            }
+            _ => text.push(ch),
+        }
+    }
+
+    (text, matches)
+}
+
+/// Lowercases and strips accents, as the full-text index does: letters are
+/// decomposed and their combining marks dropped, so `é` becomes `e` while
+/// letters of their own such as `œ` are kept.
+pub fn fold_text(text: &str) -> String {
+    text.to_lowercase()
+        .nfd()
+        .filter(|ch| !is_combining_mark(*ch))
+        .collect()
+}
+
+/// Tag names are stored folded, so `idee` and `Idée` are one tag, with each
+/// `/`-separated level trimmed and empty levels dropped (` a//b/` is `a/b`).
+pub fn fold_tag(tag: &str) -> String {
+    fold_text(tag)
+        .split('/')
+        .map(str::trim)
+        .filter(|level| !level.is_empty())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+// SQL matching `column` against a tag and every tag below it. Descendants
+// are the names between `tag/` and `tag0` (`0` sorts right after `/`), a
+// range the UNIQUE index on the name can serve.
+fn push_tag_subtree(sql: &mut String, args: &mut Vec<Value>, column: &str, tag: &str) {
+    sql.push_str(&format!("({0} = ? OR ({0} > ? AND {0} < ?))", column));
+    args.push(Value::Text(tag.to_string()));
+    args.push(Value::Text(format!("{}/", tag)));
+    args.push(Value::Text(format!("{}0", tag)));
+}
+
 fn normalize_tags(tags: &[String]) -> Vec<String> {
     let mut normalized = Vec::new();
 
     for tag in tags {
-        let clean = tag.trim().to_lowercase();
+        let clean = fold_tag(tag);
         if clean.is_empty() {
             continue;
         }
@@ -37,107 +401,451 @@ fn normalize_tags(tags: &[String]) -> Vec<String> {
     normalized
 }
 
-fn now_unix_seconds() -> Result<String> {
+pub fn unix_seconds() -> Result<i64> {
     Ok(SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .context("system clock is before unix epoch")?
-        .as_secs()
-        .to_string())
+        .as_secs() as i64)
 }
 
-pub fn replace_note_tags(conn: &mut Connection, note_id: &str, tags: &[String]) -> Result<()> {
-    let normalized = normalize_tags(tags);
+/// Latest date a note can carry: the last second that fits in ten digits,
+/// in 2286.
+pub const MAX_TIMESTAMP: i64 = 9_999_999_999;
+
+// Note dates are stored as ten-digit text, so comparing them as text, as the
+// list order and page cursors do, matches comparing them as numbers.
+fn timestamp_text(secs: i64) -> String {
+    format!("{:010}", secs.clamp(0, MAX_TIMESTAMP))
+}
+
+fn now_unix_seconds() -> Result<String> {
+    Ok(timestamp_text(unix_seconds()?))
+}
+
+// Records the `[[links]]` of a note. A target the note already linked to keeps
+// the id it resolved to; a new one is looked up as a note id, then as the
+// title of the most recently updated live note. Unresolved targets are left
+// out and tried again on the next save.
+fn link_notes(tx: &Transaction<'_>, note_id: &str, content: &str) -> Result<()> {
+    let targets = wikilinks::targets(content);
+
+    let mut previous: HashMap<String, String> = HashMap::new();
+    {
+        let mut stmt = tx
+            .prepare_cached(
+                "SELECT l.target, l.target_id
+                 FROM note_links l
+                 JOIN notes n ON n.id = l.target_id
+                 WHERE l.source_id = ?1",
+            )
+            .context("failed to prepare note links query")?;
+        let rows = stmt
+            .query_map(params![note_id], |row| Ok((row.get(0)?, row.get(1)?)))
+            .context("failed to query note links")?;
+        for row in rows {
+            let (target, target_id) = row.context("failed to decode note link")?;
+            previous.insert(target, target_id);
+        }
+    }
+
+    tx.execute(
+        "DELETE FROM note_links WHERE source_id = ?1",
+        params![note_id],
+    )
+    .context("failed to clear note links")?;
+
+    for target in targets {
+        let target_id = match previous.remove(&target) {
+            Some(target_id) => Some(target_id),
+            None => tx
+                .prepare_cached(
+                    "SELECT id FROM notes
+                     WHERE id = ?1
+                        OR (deleted_at IS NULL AND title = ?1 COLLATE NOCASE)
+                     ORDER BY id = ?1 DESC, updated_at DESC
+                     LIMIT 1",
+                )
+                .and_then(|mut stmt| {
+                    stmt.query_row(params![target], |row| row.get::<_, String>(0))
+                        .optional()
+                })
+                .context("failed to resolve note link")?,
+        };
+
+        let Some(target_id) = target_id.filter(|target_id| target_id != note_id) else {
+            continue;
+        };
+
+        tx.prepare_cached(
+            "INSERT INTO note_links (source_id, target, target_id) VALUES (?1, ?2, ?3)",
+        )
+        .and_then(|mut stmt| stmt.execute(params![note_id, target, target_id]))
+        .context("failed to store note link")?;
+    }
+
+    Ok(())
+}
+
+// Parses the links of notes saved before `note_links` existed.
+fn backfill_note_links(conn: &mut Connection) -> Result<()> {
+    let notes = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT n.id, n.content
+                 FROM note_links_pending p
+                 JOIN notes n ON n.id = p.note_id",
+            )
+            .context("failed to prepare note links backfill")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query notes pending link parsing")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode notes pending link parsing")?
+    };
+
     let tx = conn
         .transaction()
-        .context("failed to start tags transaction")?;
+        .context("failed to start note links backfill")?;
+
+    for (id, content) in notes {
+        link_notes(&tx, &id, &content)?;
+    }
+    tx.execute("DELETE FROM note_links_pending", [])
+        .context("failed to clear pending note links")?;
 
+    tx.commit().context("failed to commit note links backfill")
+}
+
+// Records the checklist items of a note, replacing the previous ones.
+fn index_tasks(tx: &Transaction<'_>, note_id: &str, content: &str) -> Result<()> {
     tx.execute(
-        "DELETE FROM notes_tags WHERE note_id = ?1",
+        "DELETE FROM note_tasks WHERE note_id = ?1",
         params![note_id],
     )
-    .context("failed to clear existing note tags")?;
+    .context("failed to clear note tasks")?;
 
-    for tag in &normalized {
-        tx.execute(
-            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
-            params![tag],
+    for task in markdown::tasks(content) {
+        tx.prepare_cached(
+            "INSERT INTO note_tasks (note_id, line, text, done) VALUES (?1, ?2, ?3, ?4)",
         )
-        .context("failed to upsert tag")?;
+        .and_then(|mut stmt| stmt.execute(params![note_id, task.line, task.text, task.done]))
+        .context("failed to store note task")?;
+    }
+
+    Ok(())
+}
+
+// Parses the checklists of notes saved before `note_tasks` existed.
+fn backfill_note_tasks(conn: &mut Connection) -> Result<()> {
+    let notes = {
+        let mut stmt = conn
+            .prepare(
+                "SELECT n.id, n.content
+                 FROM note_tasks_pending p
+                 JOIN notes n ON n.id = p.note_id",
+            )
+            .context("failed to prepare note tasks backfill")?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+            })
+            .context("failed to query notes pending task parsing")?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode notes pending task parsing")?
+    };
+
+    let tx = conn
+        .transaction()
+        .context("failed to start note tasks backfill")?;
+
+    for (id, content) in notes {
+        index_tasks(&tx, &id, &content)?;
+    }
+    tx.execute("DELETE FROM note_tasks_pending", [])
+        .context("failed to clear pending note tasks")?;
+
+    tx.commit().context("failed to commit note tasks backfill")
+}
+
+fn link_tags(tx: &Transaction<'_>, note_id: &str, tags: &[String]) -> Result<()> {
+    for tag in &normalize_tags(tags) {
+        tx.prepare_cached("INSERT OR IGNORE INTO tags (name) VALUES (?1)")
+            .and_then(|mut stmt| stmt.execute(params![tag]))
+            .context("failed to upsert tag")?;
 
-        tx.execute(
-            "INSERT INTO notes_tags (note_id, tag_id)
+        tx.prepare_cached(
+            "INSERT OR IGNORE INTO notes_tags (note_id, tag_id)
              SELECT ?1, id FROM tags WHERE name = ?2",
-            params![note_id, tag],
         )
+        .and_then(|mut stmt| stmt.execute(params![note_id, tag]))
         .context("failed to link tag to note")?;
     }
 
+    Ok(())
+}
+
+pub fn replace_note_tags(conn: &mut Connection, note_id: &str, tags: &[String]) -> Result<()> {
+    let tx = conn
+        .transaction()
+        .context("failed to start tags transaction")?;
+
+    tx.execute(
+        "DELETE FROM notes_tags WHERE note_id = ?1",
+        params![note_id],
+    )
+    .context("failed to clear existing note tags")?;
+
+    link_tags(&tx, note_id, tags)?;
+
     tx.commit().context("failed to commit tags transaction")?;
     Ok(())
 }
 
-pub fn insert_note(conn: &mut Connection, content: &str, tags: &[String]) -> Result<()> {
+// The text to save and the tags written inline as `#hashtags`. A note made
+// only of hashtags keeps them rather than being saved empty.
+fn take_hashtags(content: &str, mode: HashtagMode) -> (String, Vec<String>) {
+    let tags = hashtags::extract(content);
+    let content = match mode {
+        HashtagMode::Strip if !tags.is_empty() => {
+            let stripped = hashtags::strip(content).trim().to_string();
+            if stripped.is_empty() {
+                content.to_string()
+            } else {
+                stripped
+            }
+        }
+        _ => content.to_string(),
+    };
+    (content, tags)
+}
+
+/// Saves a new note. Hashtags in `content` are added to `tags`.
+pub fn insert_note(
+    conn: &mut Connection,
+    content: &str,
+    tags: &[String],
+    hashtags: HashtagMode,
+) -> Result<String> {
     let id = Uuid::new_v4().to_string();
     let now = now_unix_seconds()?;
+    let (content, inline_tags) = take_hashtags(content, hashtags);
+    let content = content.as_str();
 
     let tx = conn
         .transaction()
         .context("failed to start note insertion transaction")?;
 
     tx.execute(
-        "INSERT INTO notes (id, content, created_at, updated_at, pinned) VALUES (?1, ?2, ?3, ?4, 0)",
-        params![id, content, now, now],
+        "INSERT INTO notes (id, content, title, excerpt, content_hash, created_at, updated_at, pinned)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, 0)",
+        params![
+            id,
+            content,
+            note_title(content),
+            note_excerpt(content),
+            content_hash(content),
+            now,
+            now
+        ],
     )
     .context("failed to insert note")?;
 
-    tx.execute(
-        "INSERT INTO notes_fts (note_id, content) VALUES (?1, ?2)",
-        params![id, content],
-    )
-    .context("failed to index note in FTS table")?;
+    index_note(&tx, &id, content)?;
+    link_tags(&tx, &id, tags)?;
+    link_tags(&tx, &id, &inline_tags)?;
+    link_notes(&tx, &id, content)?;
+    index_tasks(&tx, &id, content)?;
 
-    for tag in &normalize_tags(tags) {
-        tx.execute(
-            "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
-            params![tag],
-        )
-        .context("failed to upsert tag")?;
+    tx.commit()
+        .context("failed to commit note insertion transaction")?;
 
-        tx.execute(
-            "INSERT INTO notes_tags (note_id, tag_id)
-             SELECT ?1, id FROM tags WHERE name = ?2",
-            params![id, tag],
+    Ok(id)
+}
+
+/// Inserts notes with their original ids, timestamps and tags in a single
+/// transaction. Records whose id already exists, or whose content matches an
+/// existing note or an earlier record, are skipped so re-imports are
+/// idempotent. Returns the number of notes inserted.
+pub fn insert_note_records(conn: &mut Connection, records: &[NoteRecord]) -> Result<usize> {
+    let tx = conn
+        .transaction()
+        .context("failed to start bulk insertion transaction")?;
+
+    let mut inserted = Vec::new();
+    for record in records {
+        if note_exists(&tx, &record.id)? || content_exists(&tx, &record.content)? {
+            continue;
+        }
+
+        tx.prepare_cached(
+            "INSERT INTO notes
+                 (id, content, title, excerpt, content_hash, created_at, updated_at, pinned)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
         )
-        .context("failed to link tag to note")?;
+        .and_then(|mut stmt| {
+            stmt.execute(params![
+                record.id,
+                record.content,
+                note_title(&record.content),
+                note_excerpt(&record.content),
+                content_hash(&record.content),
+                timestamp_text(record.created_at),
+                timestamp_text(record.updated_at),
+                record.pinned
+            ])
+        })
+        .context("failed to insert note")?;
+
+        index_note(&tx, &record.id, &record.content)?;
+        link_tags(&tx, &record.id, &record.tags)?;
+        index_tasks(&tx, &record.id, &record.content)?;
+        inserted.push(record);
+    }
+
+    // Links are resolved once every record is in, so they can point at notes
+    // imported later in the batch. Skipped records keep the links they have.
+    for record in &inserted {
+        if record.content.contains("[[") {
+            link_notes(&tx, &record.id, &record.content)?;
+        }
     }
 
     tx.commit()
-        .context("failed to commit note insertion transaction")?;
+        .context("failed to commit bulk insertion transaction")?;
+
+    Ok(inserted.len())
+}
+
+// Notes are indexed twice: by word for search and by trigram for fuzzy
+// search candidates.
+fn index_note(tx: &Transaction<'_>, note_id: &str, content: &str) -> Result<()> {
+    tx.prepare_cached("INSERT INTO notes_fts (note_id, content) VALUES (?1, ?2)")
+        .and_then(|mut stmt| stmt.execute(params![note_id, content]))
+        .context("failed to index note in FTS table")?;
+
+    tx.prepare_cached("INSERT INTO notes_trigram (note_id, content) VALUES (?1, ?2)")
+        .and_then(|mut stmt| stmt.execute(params![note_id, content]))
+        .context("failed to index note in trigram table")?;
+
+    Ok(())
+}
+
+fn unindex_note(tx: &Transaction<'_>, note_id: &str) -> Result<()> {
+    tx.execute("DELETE FROM notes_fts WHERE note_id = ?1", params![note_id])
+        .context("failed to remove note from FTS table")?;
+
+    tx.execute(
+        "DELETE FROM notes_trigram WHERE note_id = ?1",
+        params![note_id],
+    )
+    .context("failed to remove note from trigram table")?;
 
     Ok(())
 }
 
-pub fn update_note_content(conn: &mut Connection, note_id: &str, content: &str) -> Result<()> {
+// Hex SHA-256 of the content without surrounding whitespace, so a note that
+// went through an export and back still matches.
+fn content_hash(content: &str) -> String {
+    Sha256::digest(content.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn note_exists(tx: &Transaction<'_>, note_id: &str) -> Result<bool> {
+    tx.prepare_cached("SELECT 1 FROM notes WHERE id = ?1")
+        .and_then(|mut stmt| stmt.exists(params![note_id]))
+        .context("failed to look up note id")
+}
+
+// The hash only narrows the lookup down; contents are compared in full so a
+// collision cannot drop a distinct note.
+fn content_exists(tx: &Transaction<'_>, content: &str) -> Result<bool> {
+    let mut stmt = tx
+        .prepare_cached("SELECT content FROM notes WHERE content_hash = ?1")
+        .context("failed to prepare content lookup")?;
+    let rows = stmt
+        .query_map(params![content_hash(content)], |row| {
+            row.get::<_, String>(0)
+        })
+        .context("failed to look up note content")?;
+    for row in rows {
+        if row.context("failed to decode note content")?.trim() == content.trim() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Replaces the text of a note, keeping the previous one as a revision.
+/// Hashtags in `content` are added to the note's tags.
+pub fn update_note_content(
+    conn: &mut Connection,
+    note_id: &str,
+    content: &str,
+    hashtags: HashtagMode,
+) -> Result<()> {
     let now = now_unix_seconds()?;
+    let (content, inline_tags) = take_hashtags(content, hashtags);
+    let content = content.as_str();
     let tx = conn
         .transaction()
         .context("failed to start note update transaction")?;
 
+    // Only hashtags new to this save become tags, so a tag renamed, merged or
+    // deleted since is not brought back by the text still mentioning it.
+    let previous: Option<String> = tx
+        .query_row(
+            "SELECT content FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+            params![note_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .context("failed to load note before update")?;
+    let previous_tags = previous
+        .as_deref()
+        .map(hashtags::extract)
+        .unwrap_or_default();
+    let new_tags: Vec<String> = inline_tags
+        .into_iter()
+        .filter(|tag| !previous_tags.contains(tag))
+        .collect();
+
+    let saved = tx
+        .execute(
+            "INSERT INTO note_revisions (note_id, content, created_at)
+             SELECT id, content, updated_at FROM notes
+             WHERE id = ?1 AND deleted_at IS NULL AND content <> ?2",
+            params![note_id, content],
+        )
+        .context("failed to save note revision")?;
+
+    if saved == 0 {
+        return Ok(());
+    }
+
     tx.execute(
-        "UPDATE notes SET content = ?2, updated_at = ?3
+        "UPDATE notes SET content = ?2, title = ?3, excerpt = ?4, content_hash = ?5,
+             updated_at = ?6
          WHERE id = ?1 AND deleted_at IS NULL",
-        params![note_id, content, now],
+        params![
+            note_id,
+            content,
+            note_title(content),
+            note_excerpt(content),
+            content_hash(content),
+            now
+        ],
     )
     .context("failed to update note")?;
 
-    tx.execute("DELETE FROM notes_fts WHERE note_id = ?1", params![note_id])
-        .context("failed to clear note FTS row")?;
-
-    tx.execute(
-        "INSERT INTO notes_fts (note_id, content) VALUES (?1, ?2)",
-        params![note_id, content],
-    )
-    .context("failed to refresh note FTS row")?;
+    unindex_note(&tx, note_id)?;
+    index_note(&tx, note_id, content)?;
+    link_tags(&tx, note_id, &new_tags)?;
+    link_notes(&tx, note_id, content)?;
+    index_tasks(&tx, note_id, content)?;
 
     tx.commit()
         .context("failed to commit note update transaction")?;
@@ -145,64 +853,145 @@ pub fn update_note_content(conn: &mut Connection, note_id: &str, content: &str)
     Ok(())
 }
 
-pub fn search_notes(
-    conn: &Connection,
-    query: &str,
-    tags: &[String],
-    limit: i64,
-) -> Result<Vec<NoteListItem>> {
-    let query = query.trim();
-    let normalized_tags = normalize_tags(tags);
-    let mut args: Vec<Value> = Vec::new();
-    let mut sql = String::from("SELECT n.id, n.content FROM notes n ");
+// Appends the FROM/WHERE part shared by every note search: live notes (or
+// trashed ones for `is:deleted`), optionally matching the FTS text, and
+// satisfying every condition of the query.
+fn push_note_filter(sql: &mut String, args: &mut Vec<Value>, query: &SearchQuery) {
+    let text = query.text.trim();
+    let fuzzy = query.mode == QueryMode::Fuzzy;
+    let deleted = query
+        .conditions
+        .iter()
+        .any(|condition| condition.filter == Filter::Deleted && !condition.negated);
 
-    if !query.is_empty() {
+    sql.push_str("FROM notes n ");
+
+    if !text.is_empty() && !fuzzy {
         sql.push_str("JOIN notes_fts ON notes_fts.note_id = n.id ");
     }
 
-    if !normalized_tags.is_empty() {
-        sql.push_str(
-            "JOIN notes_tags nt ON nt.note_id = n.id
-             JOIN tags t ON t.id = nt.tag_id ",
-        );
+    if deleted {
+        sql.push_str("WHERE n.deleted_at IS NOT NULL ");
+    } else {
+        sql.push_str("WHERE n.deleted_at IS NULL ");
     }
 
-    sql.push_str("WHERE n.deleted_at IS NULL ");
-
-    if !query.is_empty() {
+    if !text.is_empty() && fuzzy {
+        sql.push_str(
+            "AND n.id IN (
+                 SELECT note_id FROM notes_trigram
+                 WHERE notes_trigram MATCH ?
+                 ORDER BY rank
+                 LIMIT ?
+             ) ",
+        );
+        args.push(Value::Text(text.to_string()));
+        args.push(Value::Integer(FUZZY_CANDIDATES));
+    } else if !text.is_empty() {
         sql.push_str("AND notes_fts MATCH ? ");
-        args.push(Value::Text(query.to_string()));
+        args.push(Value::Text(text.to_string()));
     }
 
-    if !normalized_tags.is_empty() {
-        sql.push_str("AND t.name IN (");
-        for (index, tag) in normalized_tags.iter().enumerate() {
-            if index > 0 {
-                sql.push_str(", ");
+    for condition in &query.conditions {
+        let clause = match &condition.filter {
+            // Trash membership is already decided by the base condition.
+            Filter::Deleted => continue,
+            // Tag filters live in subqueries so the outer query is never
+            // grouped, which would make bm25() unusable in the ORDER BY. A
+            // tag also matches the notes of the tags nested under it.
+            Filter::Tag(names) => {
+                let mut clause = String::from(
+                    "n.id IN (
+                         SELECT nt.note_id
+                         FROM notes_tags nt
+                         JOIN tags t ON t.id = nt.tag_id
+                         WHERE ",
+                );
+                for (index, name) in normalize_tags(names).iter().enumerate() {
+                    if index > 0 {
+                        clause.push_str(" OR ");
+                    }
+                    push_tag_subtree(&mut clause, args, "t.name", name);
+                }
+                clause.push(')');
+                clause
+            }
+            Filter::Created { from, until } => {
+                time_range_clause("n.created_at", *from, *until, args)
             }
-            sql.push('?');
-            args.push(Value::Text(tag.clone()));
+            Filter::Updated { from, until } => {
+                time_range_clause("n.updated_at", *from, *until, args)
+            }
+            Filter::Pinned => String::from("n.pinned = 1"),
+            Filter::HasLink => String::from(
+                "(n.content LIKE '%http://%' OR n.content LIKE '%https://%'
+                  OR n.id IN (SELECT source_id FROM note_links))",
+            ),
+        };
+
+        if condition.negated {
+            sql.push_str(&format!("AND NOT ({}) ", clause));
+        } else {
+            sql.push_str(&format!("AND {} ", clause));
         }
-        sql.push_str(") ");
     }
+}
 
-    if !normalized_tags.is_empty() {
-        sql.push_str("GROUP BY n.id ");
-        sql.push_str("HAVING COUNT(DISTINCT t.name) = ? ");
-        args.push(Value::Integer(normalized_tags.len() as i64));
+fn time_range_clause(
+    column: &str,
+    from: Option<i64>,
+    until: Option<i64>,
+    args: &mut Vec<Value>,
+) -> String {
+    let mut parts = Vec::new();
+    if let Some(from) = from {
+        parts.push(format!("CAST({} AS INTEGER) >= ?", column));
+        args.push(Value::Integer(from));
+    }
+    if let Some(until) = until {
+        parts.push(format!("CAST({} AS INTEGER) < ?", column));
+        args.push(Value::Integer(until));
     }
 
-    if query.is_empty() {
-        sql.push_str("ORDER BY n.updated_at DESC ");
+    if parts.is_empty() {
+        String::from("1")
     } else {
-        sql.push_str("ORDER BY bm25(notes_fts), n.updated_at DESC ");
+        format!("({})", parts.join(" AND "))
     }
+}
 
-    sql.push_str("LIMIT ?");
-    args.push(Value::Integer(limit));
+pub fn search_notes(
+    conn: &Connection,
+    query: &SearchQuery,
+    limit: i64,
+) -> Result<Vec<NoteListItem>> {
+    if is_fuzzy(query) {
+        return Ok(fuzzy_search(conn, query)?
+            .into_iter()
+            .take(usize::try_from(limit).unwrap_or(usize::MAX))
+            .map(|hit| NoteListItem {
+                id: hit.item.id,
+                preview: hit.content,
+                pinned: hit.item.pinned,
+            })
+            .collect());
+    }
 
-    let mut stmt = conn
-        .prepare(&sql)
+    let mut args: Vec<Value> = Vec::new();
+    let mut sql = String::from("SELECT n.id, n.content, n.pinned ");
+    push_note_filter(&mut sql, &mut args, query);
+
+    if query.text.trim().is_empty() {
+        sql.push_str("ORDER BY n.pinned DESC, n.updated_at DESC ");
+    } else {
+        sql.push_str("ORDER BY n.pinned DESC, bm25(notes_fts), n.updated_at DESC ");
+    }
+
+    sql.push_str("LIMIT ?");
+    args.push(Value::Integer(limit));
+
+    let mut stmt = conn
+        .prepare(&sql)
         .context("failed to prepare note search query")?;
 
     let rows = stmt
@@ -210,6 +999,7 @@ pub fn search_notes(
             Ok(NoteListItem {
                 id: row.get(0)?,
                 preview: row.get(1)?,
+                pinned: row.get(2)?,
             })
         })
         .context("failed to execute note search query")?;
@@ -221,17 +1011,375 @@ pub fn search_notes(
     Ok(items)
 }
 
+pub fn count_notes(conn: &Connection, query: &SearchQuery) -> Result<i64> {
+    if is_fuzzy(query) {
+        return Ok(fuzzy_ranking(conn, query)?.len() as i64);
+    }
+
+    let mut args: Vec<Value> = Vec::new();
+    let mut sql = String::from("SELECT COUNT(*) ");
+    push_note_filter(&mut sql, &mut args, query);
+
+    conn.query_row(&sql, params_from_iter(args.iter()), |row| row.get(0))
+        .context("failed to count notes")
+}
+
+pub fn search_notes_page(
+    conn: &Connection,
+    query: &SearchQuery,
+    start: &PageStart,
+    limit: i64,
+) -> Result<NotePage> {
+    if is_fuzzy(query) {
+        return fuzzy_page(conn, query, start, limit);
+    }
+
+    let ranked = !query.text.trim().is_empty();
+    let mut args: Vec<Value> = Vec::new();
+    let (score, snippet) = if !ranked {
+        ("0.0", "n.excerpt")
+    } else {
+        (
+            "bm25(notes_fts)",
+            "snippet(notes_fts, 1, char(2), char(3), '…', 12)",
+        )
+    };
+
+    // bm25() cannot appear in a WHERE clause, so the ranked rows are wrapped
+    // and the keyset condition is applied to the outer query.
+    let mut sql = format!(
+        "SELECT id, title, snippet, created_at, updated_at, pinned, tags, score FROM (
+             SELECT n.id, n.title, {} AS snippet, CAST(n.created_at AS INTEGER) AS created_at,
+                    n.updated_at, n.pinned, {} AS score, {} AS tags ",
+        snippet, score, TAG_LIST
+    );
+    push_note_filter(&mut sql, &mut args, query);
+    sql.push_str(") ");
+
+    let mut offset = 0;
+    match start {
+        PageStart::Offset(rows) => offset = *rows,
+        PageStart::After(cursor) if !ranked => {
+            sql.push_str("WHERE (pinned, updated_at, id) < (?, ?, ?) ");
+            args.push(Value::Integer(i64::from(cursor.pinned)));
+            args.push(Value::Text(cursor.updated_at.clone()));
+            args.push(Value::Text(cursor.id.clone()));
+        }
+        PageStart::After(cursor) => {
+            sql.push_str(
+                "WHERE pinned < ?
+                    OR (pinned = ? AND (score > ?
+                        OR (score = ? AND (updated_at, id) < (?, ?)))) ",
+            );
+            let pinned = Value::Integer(i64::from(cursor.pinned));
+            args.push(pinned.clone());
+            args.push(pinned);
+            args.push(Value::Real(cursor.score));
+            args.push(Value::Real(cursor.score));
+            args.push(Value::Text(cursor.updated_at.clone()));
+            args.push(Value::Text(cursor.id.clone()));
+        }
+    }
+
+    sql.push_str(&format!("ORDER BY {} ", list_order(ranked)));
+
+    sql.push_str("LIMIT ? OFFSET ?");
+    args.push(Value::Integer(limit));
+    args.push(Value::Integer(offset));
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("failed to prepare note page query")?;
+
+    let mut page = NotePage::default();
+    let mut rows = stmt
+        .query(params_from_iter(args.iter()))
+        .context("failed to execute note page query")?;
+
+    while let Some(row) = rows.next().context("failed to decode note page")? {
+        let updated_at: String = row.get(4)?;
+        let tags = split_tag_list(row.get(6)?);
+
+        let snippet = row
+            .get::<_, Option<String>>(2)?
+            .unwrap_or_default()
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        let (snippet, snippet_matches) = split_matches(&snippet);
+
+        let item = NoteSummary {
+            id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            snippet,
+            snippet_matches,
+            created_at: row.get(3)?,
+            updated_at: updated_at.parse().unwrap_or_default(),
+            pinned: row.get(5)?,
+            tags,
+        };
+        page.next = Some(NoteCursor {
+            pinned: item.pinned,
+            score: row.get(7)?,
+            updated_at,
+            id: item.id.clone(),
+        });
+        page.items.push(item);
+    }
+
+    Ok(page)
+}
+
+// Order of the note list over the columns of the page query; ranked lists
+// put the best `score` first within pinned and unpinned notes.
+fn list_order(ranked: bool) -> &'static str {
+    if ranked {
+        "pinned DESC, score, updated_at DESC, id DESC"
+    } else {
+        "pinned DESC, updated_at DESC, id DESC"
+    }
+}
+
+const FUZZY_CANDIDATES: i64 = 500;
+
+// A note's tags joined with the ASCII unit separator, so commas in a name
+// survive, each as name, color and icon joined with the record separator.
+const TAG_LIST: &str = "(SELECT group_concat(
+         t.name || char(30) || ifnull(t.color, '') || char(30) || ifnull(t.icon, ''),
+         char(31))
+     FROM notes_tags nt
+     JOIN tags t ON t.id = nt.tag_id
+     WHERE nt.note_id = n.id)";
+
+fn split_tag_list(joined: Option<String>) -> Vec<NoteTag> {
+    let non_empty =
+        |value: Option<&str>| value.filter(|value| !value.is_empty()).map(str::to_string);
+    let mut tags: Vec<NoteTag> = joined
+        .map(|joined| {
+            joined
+                .split('\u{1f}')
+                .map(|tag| {
+                    let mut fields = tag.split('\u{1e}');
+                    NoteTag {
+                        name: fields.next().unwrap_or_default().to_string(),
+                        color: non_empty(fields.next()),
+                        icon: non_empty(fields.next()),
+                    }
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    tags.sort_by(|a, b| a.name.cmp(&b.name));
+    tags
+}
+
+pub fn is_fuzzy(query: &SearchQuery) -> bool {
+    query.mode == QueryMode::Fuzzy && !query.text.trim().is_empty()
+}
+
+struct FuzzyHit {
+    item: NoteSummary,
+    content: String,
+    cursor: NoteCursor,
+}
+
+// Same order as ranked FTS pages, with the fuzzy score in place of bm25.
+fn cursor_order(a: &NoteCursor, b: &NoteCursor) -> Ordering {
+    b.pinned
+        .cmp(&a.pinned)
+        .then(a.score.total_cmp(&b.score))
+        .then_with(|| b.updated_at.cmp(&a.updated_at))
+        .then_with(|| b.id.cmp(&a.id))
+}
+
+// Fuzzy results are ranked in Rust: the trigram index yields candidates,
+// which `fuzzy::score` keeps or drops. Pages read through
+// `search_notes_page` rank them again each time; the note list ranks once
+// with `fuzzy_ranking` instead.
+fn fuzzy_search(conn: &Connection, query: &SearchQuery) -> Result<Vec<FuzzyHit>> {
+    let mut args: Vec<Value> = Vec::new();
+    let mut sql = format!(
+        "SELECT n.id, n.title, n.content, CAST(n.created_at AS INTEGER), n.updated_at,
+                n.pinned, {} ",
+        TAG_LIST
+    );
+    push_note_filter(&mut sql, &mut args, query);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("failed to prepare fuzzy search query")?;
+    let mut rows = stmt
+        .query(params_from_iter(args.iter()))
+        .context("failed to execute fuzzy search query")?;
+
+    let mut hits = Vec::new();
+    while let Some(row) = rows.next().context("failed to decode fuzzy candidate")? {
+        let content: String = row.get(2)?;
+        let Some((score, matches)) = fuzzy::score(&content, &query.terms) else {
+            continue;
+        };
+
+        let (snippet, snippet_matches) = fuzzy::snippet(&content, &matches);
+        let updated_at: String = row.get(4)?;
+        let item = NoteSummary {
+            id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            snippet,
+            snippet_matches,
+            created_at: row.get(3)?,
+            updated_at: updated_at.parse().unwrap_or_default(),
+            pinned: row.get(5)?,
+            tags: split_tag_list(row.get(6)?),
+        };
+        let cursor = NoteCursor {
+            pinned: item.pinned,
+            score,
+            updated_at,
+            id: item.id.clone(),
+        };
+        hits.push(FuzzyHit {
+            item,
+            content,
+            cursor,
+        });
+    }
+
+    hits.sort_by(|a, b| cursor_order(&a.cursor, &b.cursor));
+    Ok(hits)
+}
+
+fn fuzzy_page(
+    conn: &Connection,
+    query: &SearchQuery,
+    start: &PageStart,
+    limit: i64,
+) -> Result<NotePage> {
+    let hits = fuzzy_search(conn, query)?;
+    let skip = match start {
+        PageStart::Offset(rows) => usize::try_from(*rows).unwrap_or(0),
+        PageStart::After(cursor) => hits
+            .iter()
+            .position(|hit| cursor_order(&hit.cursor, cursor) == Ordering::Greater)
+            .unwrap_or(hits.len()),
+    };
+
+    let mut page = NotePage::default();
+    for hit in hits
+        .into_iter()
+        .skip(skip)
+        .take(usize::try_from(limit).unwrap_or(usize::MAX))
+    {
+        page.next = Some(hit.cursor);
+        page.items.push(hit.item);
+    }
+
+    Ok(page)
+}
+
+/// Ids of the notes a fuzzy search finds, best first. The note list ranks
+/// once per search and reads its pages through `fuzzy_notes`, instead of
+/// scoring every candidate again for each page.
+pub fn fuzzy_ranking(conn: &Connection, query: &SearchQuery) -> Result<Vec<String>> {
+    let mut args: Vec<Value> = Vec::new();
+    let mut sql = String::from("SELECT n.id, n.content, n.updated_at, n.pinned ");
+    push_note_filter(&mut sql, &mut args, query);
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("failed to prepare fuzzy ranking query")?;
+    let mut rows = stmt
+        .query(params_from_iter(args.iter()))
+        .context("failed to execute fuzzy ranking query")?;
+
+    let mut ranked = Vec::new();
+    while let Some(row) = rows.next().context("failed to decode fuzzy candidate")? {
+        let content: String = row.get(1)?;
+        let Some((score, _)) = fuzzy::score(&content, &query.terms) else {
+            continue;
+        };
+        ranked.push(NoteCursor {
+            pinned: row.get(3)?,
+            score,
+            updated_at: row.get(2)?,
+            id: row.get(0)?,
+        });
+    }
+
+    ranked.sort_by(cursor_order);
+    Ok(ranked.into_iter().map(|cursor| cursor.id).collect())
+}
+
+/// List rows for `ids`, in that order, with the snippets of the fuzzy
+/// `query`. Notes purged since they were ranked are left out.
+pub fn fuzzy_notes(
+    conn: &Connection,
+    query: &SearchQuery,
+    ids: &[String],
+) -> Result<Vec<NoteSummary>> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = vec!["?"; ids.len()].join(", ");
+    let sql = format!(
+        "SELECT n.id, n.title, n.content, CAST(n.created_at AS INTEGER),
+                CAST(n.updated_at AS INTEGER), n.pinned, {}
+         FROM notes n
+         WHERE n.id IN ({})",
+        TAG_LIST, placeholders
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("failed to prepare fuzzy page query")?;
+    let mut rows = stmt
+        .query(params_from_iter(ids.iter()))
+        .context("failed to execute fuzzy page query")?;
+
+    let mut found = HashMap::new();
+    while let Some(row) = rows.next().context("failed to decode fuzzy page")? {
+        let content: String = row.get(2)?;
+        let matches = fuzzy::score(&content, &query.terms)
+            .map(|(_, matches)| matches)
+            .unwrap_or_default();
+        let (snippet, snippet_matches) = fuzzy::snippet(&content, &matches);
+        let item = NoteSummary {
+            id: row.get(0)?,
+            title: row.get::<_, Option<String>>(1)?.unwrap_or_default(),
+            snippet,
+            snippet_matches,
+            created_at: row.get(3)?,
+            updated_at: row.get(4)?,
+            pinned: row.get(5)?,
+            tags: split_tag_list(row.get(6)?),
+        };
+        found.insert(item.id.clone(), item);
+    }
+
+    Ok(ids.iter().filter_map(|id| found.remove(id)).collect())
+}
+
+/// The content of a live note.
 pub fn get_note_content(conn: &Connection, note_id: &str) -> Result<Option<String>> {
+    note_content(conn, note_id, false)
+}
+
+/// The content of a note, trashed or not, for reading trash search results.
+pub fn get_note_content_any(conn: &Connection, note_id: &str) -> Result<Option<String>> {
+    note_content(conn, note_id, true)
+}
+
+fn note_content(conn: &Connection, note_id: &str, include_deleted: bool) -> Result<Option<String>> {
     let mut stmt = conn
         .prepare(
             "SELECT content
              FROM notes
-             WHERE id = ?1 AND deleted_at IS NULL",
+             WHERE id = ?1 AND (?2 OR deleted_at IS NULL)",
         )
         .context("failed to prepare note lookup")?;
 
     let mut rows = stmt
-        .query(params![note_id])
+        .query(params![note_id, include_deleted])
         .context("failed to execute note lookup")?;
 
     if let Some(row) = rows.next().context("failed to fetch note row")? {
@@ -242,6 +1390,128 @@ pub fn get_note_content(conn: &Connection, note_id: &str) -> Result<Option<Strin
     Ok(None)
 }
 
+/// Byte ranges in the note's content of every term matched by `query`, or
+/// nothing when the query has no text or the note does not match it.
+pub fn note_matches(
+    conn: &Connection,
+    note_id: &str,
+    query: &SearchQuery,
+) -> Result<Vec<Range<usize>>> {
+    if is_fuzzy(query) {
+        let content = get_note_content_any(conn, note_id)?.unwrap_or_default();
+        return Ok(fuzzy::score(&content, &query.terms)
+            .map(|(_, matches)| matches)
+            .unwrap_or_default());
+    }
+
+    let query = query.text.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT highlight(notes_fts, 1, char(2), char(3))
+             FROM notes_fts
+             WHERE notes_fts MATCH ?2 AND note_id = ?1",
+        )
+        .context("failed to prepare match lookup")?;
+
+    let mut rows = stmt
+        .query(params![note_id, query])
+        .context("failed to locate search matches")?;
+
+    if let Some(row) = rows.next().context("failed to fetch match row")? {
+        let marked: String = row.get(0).context("failed to decode matches")?;
+        return Ok(split_matches(&marked).1);
+    }
+
+    Ok(Vec::new())
+}
+
+pub fn get_note(conn: &Connection, note_id: &str) -> Result<Option<NoteListItem>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, content, pinned
+             FROM notes
+             WHERE id = ?1 AND deleted_at IS NULL",
+        )
+        .context("failed to prepare note lookup")?;
+
+    let mut rows = stmt
+        .query(params![note_id])
+        .context("failed to execute note lookup")?;
+
+    if let Some(row) = rows.next().context("failed to fetch note row")? {
+        return Ok(Some(NoteListItem {
+            id: row.get(0).context("failed to decode note id")?,
+            preview: row.get(1).context("failed to decode note content")?,
+            pinned: row.get(2).context("failed to decode note pin state")?,
+        }));
+    }
+
+    Ok(None)
+}
+
+/// Loads complete notes for export, either every live note (oldest first) or
+/// the given ids in order, trashed or not, as a search for `is:deleted`
+/// yields them. Ids that match no note are skipped.
+pub fn get_note_records(conn: &Connection, note_ids: Option<&[String]>) -> Result<Vec<NoteRecord>> {
+    let ids = match note_ids {
+        Some(ids) => ids.to_vec(),
+        None => {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id FROM notes
+                     WHERE deleted_at IS NULL
+                     ORDER BY CAST(created_at AS INTEGER) ASC",
+                )
+                .context("failed to prepare note listing")?;
+
+            let rows = stmt
+                .query_map([], |row| row.get::<_, String>(0))
+                .context("failed to execute note listing")?;
+
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .context("failed to decode note ids")?
+        }
+    };
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, content, CAST(created_at AS INTEGER), CAST(updated_at AS INTEGER), pinned
+             FROM notes
+             WHERE id = ?1",
+        )
+        .context("failed to prepare note record lookup")?;
+
+    let mut records = Vec::with_capacity(ids.len());
+    for id in &ids {
+        let mut rows = stmt
+            .query(params![id])
+            .context("failed to execute note record lookup")?;
+
+        let Some(row) = rows.next().context("failed to fetch note record")? else {
+            continue;
+        };
+
+        records.push(NoteRecord {
+            id: row.get(0).context("failed to decode note id")?,
+            content: row.get(1).context("failed to decode note content")?,
+            created_at: row.get(2).context("failed to decode note creation date")?,
+            updated_at: row.get(3).context("failed to decode note update date")?,
+            pinned: row.get(4).context("failed to decode note pin state")?,
+            tags: Vec::new(),
+        });
+    }
+
+    for record in &mut records {
+        record.tags = get_note_tags(conn, &record.id)?;
+    }
+
+    Ok(records)
+}
+
 pub fn get_note_tags(conn: &Connection, note_id: &str) -> Result<Vec<String>> {
     let mut stmt = conn
         .prepare(
@@ -264,29 +1534,1417 @@ pub fn get_note_tags(conn: &Connection, note_id: &str) -> Result<Vec<String>> {
     Ok(tags)
 }
 
-pub fn list_tags_prefix(conn: &Connection, prefix: &str, limit: i64) -> Result<Vec<String>> {
-    let prefix = prefix.trim().to_lowercase();
-    if prefix.is_empty() {
-        return Ok(Vec::new());
-    }
+/// The tags of a note with their chip style, sorted by name.
+pub fn get_note_tag_styles(conn: &Connection, note_id: &str) -> Result<Vec<NoteTag>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT t.name, t.color, t.icon
+             FROM notes_tags nt
+             JOIN tags t ON t.id = nt.tag_id
+             WHERE nt.note_id = ?1
+             ORDER BY t.name ASC",
+        )
+        .context("failed to prepare note tag styles query")?;
+
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteTag {
+                name: row.get(0)?,
+                color: row.get(1)?,
+                icon: row.get(2)?,
+            })
+        })
+        .context("failed to execute note tag styles query")?;
+
+    let tags = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode note tag styles")?;
+
+    Ok(tags)
+}
+
+pub fn list_tags(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare("SELECT name FROM tags ORDER BY name ASC")
+        .context("failed to prepare tags query")?;
+
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .context("failed to execute tags query")?;
+
+    let tags = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode tags")?;
+
+    Ok(tags)
+}
+
+/// A tag and the number of live notes carrying it.
+#[derive(Debug, Clone)]
+pub struct TagUsage {
+    pub name: String,
+    pub notes: i64,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+}
 
+pub fn list_tag_usage(conn: &Connection) -> Result<Vec<TagUsage>> {
     let mut stmt = conn
         .prepare(
-            "SELECT name
-             FROM tags
-             WHERE name LIKE ?1 || '%'
-             ORDER BY name ASC
-             LIMIT ?2",
+            "SELECT t.name, COUNT(n.id), t.color, t.icon
+             FROM tags t
+             LEFT JOIN notes_tags nt ON nt.tag_id = t.id
+             LEFT JOIN notes n ON n.id = nt.note_id AND n.deleted_at IS NULL
+             GROUP BY t.id
+             ORDER BY t.name ASC",
         )
-        .context("failed to prepare tag prefix query")?;
+        .context("failed to prepare tag usage query")?;
 
     let rows = stmt
-        .query_map(params![prefix, limit], |row| row.get::<_, String>(0))
-        .context("failed to execute tag prefix query")?;
+        .query_map([], |row| {
+            Ok(TagUsage {
+                name: row.get(0)?,
+                notes: row.get(1)?,
+                color: row.get(2)?,
+                icon: row.get(3)?,
+            })
+        })
+        .context("failed to execute tag usage query")?;
 
     let tags = rows
         .collect::<rusqlite::Result<Vec<_>>>()
-        .context("failed to decode tag prefix results")?;
+        .context("failed to decode tag usage")?;
 
     Ok(tags)
 }
+
+fn tag_id(tx: &Transaction<'_>, name: &str) -> Result<Option<i64>> {
+    let mut stmt = tx
+        .prepare_cached("SELECT id FROM tags WHERE name = ?1")
+        .context("failed to prepare tag lookup")?;
+    let mut rows = stmt
+        .query(params![name])
+        .context("failed to execute tag lookup")?;
+
+    if let Some(row) = rows.next().context("failed to fetch tag row")? {
+        return Ok(Some(row.get(0).context("failed to decode tag id")?));
+    }
+
+    Ok(None)
+}
+
+// Moves every note of tag `from` to tag `to`; notes carrying both keep one
+// link.
+fn move_tag_links(tx: &Transaction<'_>, from: i64, to: i64) -> Result<()> {
+    tx.execute(
+        "INSERT OR IGNORE INTO notes_tags (note_id, tag_id)
+         SELECT note_id, ?2 FROM notes_tags WHERE tag_id = ?1",
+        params![from, to],
+    )
+    .context("failed to move tag links")?;
+
+    tx.execute("DELETE FROM notes_tags WHERE tag_id = ?1", params![from])
+        .context("failed to unlink tag")?;
+
+    Ok(())
+}
+
+/// Renames a tag, and the tags nested under it, on every note. Fails if a
+/// new name is already a tag; merge them instead.
+pub fn rename_tag(conn: &mut Connection, name: &str, new_name: &str) -> Result<()> {
+    let name = fold_tag(name);
+    let new_name = fold_tag(new_name);
+    if new_name.is_empty() {
+        bail!("tag name cannot be empty");
+    }
+    if name == new_name {
+        return Ok(());
+    }
+    if new_name.starts_with(&format!("{}/", name)) {
+        bail!("cannot move tag {} below itself", name);
+    }
+
+    let tx = conn
+        .transaction()
+        .context("failed to start tag rename transaction")?;
+
+    let mut args = vec![
+        Value::Text(new_name.clone()),
+        Value::Integer(name.chars().count() as i64 + 1),
+    ];
+    let mut subtree = String::new();
+    push_tag_subtree(&mut subtree, &mut args, "name", &name);
+
+    let clash: Option<String> = tx
+        .query_row(
+            &format!(
+                "SELECT ?1 || substr(name, ?2) FROM tags
+                 WHERE {} AND ?1 || substr(name, ?2) IN (SELECT name FROM tags)
+                 LIMIT 1",
+                subtree
+            ),
+            params_from_iter(args.iter()),
+            |row| row.get(0),
+        )
+        .map(Some)
+        .or_else(|err| match err {
+            rusqlite::Error::QueryReturnedNoRows => Ok(None),
+            err => Err(err),
+        })
+        .context("failed to check renamed tags")?;
+    if let Some(clash) = clash {
+        bail!("tag {} already exists", clash);
+    }
+
+    let renamed = tx
+        .execute(
+            &format!(
+                "UPDATE tags SET name = ?1 || substr(name, ?2) WHERE {}",
+                subtree
+            ),
+            params_from_iter(args.iter()),
+        )
+        .context("failed to rename tag")?;
+    if renamed == 0 {
+        bail!("no tag named {}", name);
+    }
+
+    tx.commit()
+        .context("failed to commit tag rename transaction")
+}
+
+/// Moves the notes of every tag in `sources` to `target`, created if needed,
+/// and removes the source tags.
+pub fn merge_tags(conn: &mut Connection, sources: &[String], target: &str) -> Result<()> {
+    let target = fold_tag(target);
+    if target.is_empty() {
+        bail!("tag name cannot be empty");
+    }
+
+    let tx = conn
+        .transaction()
+        .context("failed to start tag merge transaction")?;
+
+    tx.execute(
+        "INSERT OR IGNORE INTO tags (name) VALUES (?1)",
+        params![target],
+    )
+    .context("failed to upsert merge target tag")?;
+    let Some(target_id) = tag_id(&tx, &target)? else {
+        bail!("failed to create tag {}", target);
+    };
+
+    for source in normalize_tags(sources) {
+        if source == target {
+            continue;
+        }
+        let Some(source_id) = tag_id(&tx, &source)? else {
+            continue;
+        };
+
+        move_tag_links(&tx, source_id, target_id)?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", params![source_id])
+            .context("failed to remove merged tag")?;
+    }
+
+    tx.commit()
+        .context("failed to commit tag merge transaction")
+}
+
+/// Removes a tag from every note and deletes it.
+pub fn delete_tag(conn: &mut Connection, name: &str) -> Result<()> {
+    let tx = conn
+        .transaction()
+        .context("failed to start tag deletion transaction")?;
+
+    if let Some(id) = tag_id(&tx, &fold_tag(name))? {
+        tx.execute("DELETE FROM notes_tags WHERE tag_id = ?1", params![id])
+            .context("failed to unlink deleted tag")?;
+        tx.execute("DELETE FROM tags WHERE id = ?1", params![id])
+            .context("failed to delete tag")?;
+    }
+
+    tx.commit()
+        .context("failed to commit tag deletion transaction")
+}
+
+/// Sets the chip color (`#rgb` or `#rrggbb`) and emoji of a tag; `None` or
+/// blank clears them.
+pub fn set_tag_style(
+    conn: &Connection,
+    name: &str,
+    color: Option<&str>,
+    icon: Option<&str>,
+) -> Result<()> {
+    let color = match color.map(str::trim).filter(|color| !color.is_empty()) {
+        Some(color) => match colors::normalize(color) {
+            Some(color) => Some(color),
+            None => bail!("invalid color {}, expected #rrggbb", color),
+        },
+        None => None,
+    };
+    let icon = icon.map(str::trim).filter(|icon| !icon.is_empty());
+    if icon.is_some_and(|icon| icon.chars().count() > 8) {
+        bail!("tag icon must be a single emoji or symbol");
+    }
+
+    let name = fold_tag(name);
+    let updated = conn
+        .execute(
+            "UPDATE tags SET color = ?2, icon = ?3 WHERE name = ?1",
+            params![name, color, icon],
+        )
+        .context("failed to update tag style")?;
+    if updated == 0 {
+        bail!("no tag named {}", name);
+    }
+
+    Ok(())
+}
+
+/// Deletes the tags no note carries any more, trashed notes included, and
+/// returns how many were removed.
+pub fn prune_orphan_tags(conn: &Connection) -> Result<usize> {
+    conn.execute(
+        "DELETE FROM tags WHERE id NOT IN (SELECT tag_id FROM notes_tags)",
+        [],
+    )
+    .context("failed to prune unused tags")
+}
+
+/// A note on the other end of a `[[link]]`.
+#[derive(Debug, Clone)]
+pub struct LinkedNote {
+    pub id: String,
+    pub title: String,
+}
+
+/// The `[[link]]` targets of `note_id` that lead to a live note, with the id
+/// of the note each one opens.
+pub fn note_link_targets(conn: &Connection, note_id: &str) -> Result<HashMap<String, String>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT l.target, l.target_id
+             FROM note_links l
+             JOIN notes n ON n.id = l.target_id
+             WHERE l.source_id = ?1 AND n.deleted_at IS NULL",
+        )
+        .context("failed to prepare note links query")?;
+
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })
+        .context("failed to execute note links query")?;
+
+    rows.collect::<rusqlite::Result<HashMap<_, _>>>()
+        .context("failed to decode note links")
+}
+
+/// Live notes that link to `note_id`, most recently updated first.
+pub fn list_backlinks(conn: &Connection, note_id: &str) -> Result<Vec<LinkedNote>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT n.id, n.title
+             FROM notes n
+             WHERE n.deleted_at IS NULL
+               AND n.id IN (SELECT source_id FROM note_links WHERE target_id = ?1)
+             ORDER BY n.updated_at DESC",
+        )
+        .context("failed to prepare backlinks query")?;
+
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok(LinkedNote {
+                id: row.get(0)?,
+                title: row.get(1)?,
+            })
+        })
+        .context("failed to execute backlinks query")?;
+
+    let notes = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode backlinks")?;
+
+    Ok(notes)
+}
+
+/// Titles of live notes containing `fragment`, to complete a `[[link]]`;
+/// the most recently updated notes when `fragment` is empty.
+pub fn list_note_titles(conn: &Connection, fragment: &str, limit: i64) -> Result<Vec<String>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT title
+             FROM notes
+             WHERE deleted_at IS NULL
+               AND title <> ''
+               AND instr(lower(title), lower(?1)) > 0
+             GROUP BY title
+             ORDER BY max(updated_at) DESC
+             LIMIT ?2",
+        )
+        .context("failed to prepare note titles query")?;
+
+    let rows = stmt
+        .query_map(params![fragment.trim(), limit], |row| {
+            row.get::<_, String>(0)
+        })
+        .context("failed to execute note titles query")?;
+
+    let titles = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode note titles")?;
+
+    Ok(titles)
+}
+
+/// Row of `note_id` in the note list for `query`, or `None` if the note is
+/// not listed.
+pub fn note_position(conn: &Connection, query: &SearchQuery, note_id: &str) -> Result<Option<u32>> {
+    if is_fuzzy(query) {
+        let position = fuzzy_ranking(conn, query)?
+            .iter()
+            .position(|id| id == note_id);
+        return Ok(position.and_then(|position| u32::try_from(position).ok()));
+    }
+
+    let ranked = !query.text.trim().is_empty();
+    let score = if ranked { "bm25(notes_fts)" } else { "0.0" };
+    let mut args: Vec<Value> = Vec::new();
+    let mut sql = format!(
+        "SELECT position FROM (
+             SELECT id, ROW_NUMBER() OVER (ORDER BY {}) - 1 AS position FROM (
+                 SELECT n.id, n.pinned, n.updated_at, {} AS score ",
+        list_order(ranked),
+        score
+    );
+    push_note_filter(&mut sql, &mut args, query);
+    sql.push_str(")) WHERE id = ?");
+    args.push(Value::Text(note_id.to_string()));
+
+    let position: Option<i64> = conn
+        .query_row(&sql, params_from_iter(args.iter()), |row| row.get(0))
+        .optional()
+        .context("failed to locate note")?;
+
+    Ok(position.and_then(|position| u32::try_from(position).ok()))
+}
+
+/// Completions for a partly typed tag, one level at a time: for `cl` with
+/// tags `client` and `client/acme`, both `client` and `client/` are offered,
+/// the latter leading on to the next level.
+pub fn list_tags_prefix(conn: &Connection, prefix: &str, limit: i64) -> Result<Vec<String>> {
+    let prefix = fold_text(prefix.trim());
+    if prefix.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT name
+             FROM tags
+             WHERE name >= ?1 AND name < ?1 || char(1114111)
+             ORDER BY name ASC",
+        )
+        .context("failed to prepare tag prefix query")?;
+
+    let rows = stmt
+        .query_map(params![prefix], |row| row.get::<_, String>(0))
+        .context("failed to execute tag prefix query")?;
+
+    let mut completions: Vec<String> = Vec::new();
+
+    for name in rows {
+        let name = name.context("failed to decode tag prefix results")?;
+        let Some(rest) = name.strip_prefix(&prefix) else {
+            continue;
+        };
+        let completion = match rest.find('/') {
+            Some(end) => name[..prefix.len() + end + 1].to_string(),
+            None => name,
+        };
+        if !completions.contains(&completion) {
+            completions.push(completion);
+        }
+    }
+
+    completions.sort();
+    completions.truncate(usize::try_from(limit).unwrap_or(0));
+    Ok(completions)
+}
+
+pub fn set_pinned(conn: &Connection, note_id: &str, pinned: bool) -> Result<()> {
+    conn.execute(
+        "UPDATE notes SET pinned = ?2
+         WHERE id = ?1 AND deleted_at IS NULL",
+        params![note_id, pinned],
+    )
+    .context("failed to update note pin state")?;
+    Ok(())
+}
+
+pub fn soft_delete_note(conn: &Connection, note_id: &str) -> Result<()> {
+    let now = now_unix_seconds()?;
+    conn.execute(
+        "UPDATE notes SET deleted_at = ?2
+         WHERE id = ?1 AND deleted_at IS NULL",
+        params![note_id, now],
+    )
+    .context("failed to move note to trash")?;
+    Ok(())
+}
+
+pub fn restore_note(conn: &Connection, note_id: &str) -> Result<()> {
+    conn.execute(
+        "UPDATE notes SET deleted_at = NULL
+         WHERE id = ?1 AND deleted_at IS NOT NULL",
+        params![note_id],
+    )
+    .context("failed to restore note from trash")?;
+    Ok(())
+}
+
+fn purge_note_rows(tx: &Transaction<'_>, note_id: &str) -> Result<()> {
+    let purged = tx
+        .execute(
+            "DELETE FROM notes WHERE id = ?1 AND deleted_at IS NOT NULL",
+            params![note_id],
+        )
+        .context("failed to purge note")?;
+
+    if purged == 0 {
+        return Ok(());
+    }
+
+    unindex_note(tx, note_id)?;
+
+    tx.execute(
+        "DELETE FROM notes_tags WHERE note_id = ?1",
+        params![note_id],
+    )
+    .context("failed to remove note tags")?;
+
+    tx.execute(
+        "DELETE FROM note_links WHERE source_id = ?1 OR target_id = ?1",
+        params![note_id],
+    )
+    .context("failed to remove note links")?;
+
+    tx.execute(
+        "DELETE FROM note_tasks WHERE note_id = ?1",
+        params![note_id],
+    )
+    .context("failed to remove note tasks")?;
+
+    tx.execute("DELETE FROM reminders WHERE note_id = ?1", params![note_id])
+        .context("failed to remove note reminders")?;
+
+    tx.execute(
+        "DELETE FROM note_revisions WHERE note_id = ?1",
+        params![note_id],
+    )
+    .context("failed to remove note revisions")?;
+
+    Ok(())
+}
+
+pub fn purge_note(conn: &mut Connection, note_id: &str) -> Result<()> {
+    let tx = conn
+        .transaction()
+        .context("failed to start note purge transaction")?;
+
+    purge_note_rows(&tx, note_id)?;
+
+    tx.commit()
+        .context("failed to commit note purge transaction")?;
+    Ok(())
+}
+
+fn purge_deleted_where(conn: &mut Connection, filter: &str, args: &[Value]) -> Result<usize> {
+    let tx = conn
+        .transaction()
+        .context("failed to start trash purge transaction")?;
+
+    let ids = {
+        let mut stmt = tx
+            .prepare(&format!(
+                "SELECT id FROM notes WHERE deleted_at IS NOT NULL {}",
+                filter
+            ))
+            .context("failed to prepare trash purge query")?;
+
+        let rows = stmt
+            .query_map(params_from_iter(args.iter()), |row| row.get::<_, String>(0))
+            .context("failed to execute trash purge query")?;
+
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+            .context("failed to decode trashed note ids")?
+    };
+
+    for id in &ids {
+        purge_note_rows(&tx, id)?;
+    }
+
+    tx.commit()
+        .context("failed to commit trash purge transaction")?;
+    Ok(ids.len())
+}
+
+pub fn empty_trash(conn: &mut Connection) -> Result<usize> {
+    purge_deleted_where(conn, "", &[])
+}
+
+pub fn purge_deleted_older_than(conn: &mut Connection, days: u32) -> Result<usize> {
+    let cutoff = unix_seconds()? - i64::from(days) * 86_400;
+    purge_deleted_where(
+        conn,
+        "AND CAST(deleted_at AS INTEGER) < ?",
+        &[Value::Integer(cutoff)],
+    )
+}
+
+pub fn list_deleted_notes(conn: &Connection, limit: i64) -> Result<Vec<NoteListItem>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, content, pinned
+             FROM notes
+             WHERE deleted_at IS NOT NULL
+             ORDER BY CAST(deleted_at AS INTEGER) DESC
+             LIMIT ?1",
+        )
+        .context("failed to prepare trash query")?;
+
+    let rows = stmt
+        .query_map(params![limit], |row| {
+            Ok(NoteListItem {
+                id: row.get(0)?,
+                preview: row.get(1)?,
+                pinned: row.get(2)?,
+            })
+        })
+        .context("failed to execute trash query")?;
+
+    let items = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode trash results")?;
+
+    Ok(items)
+}
+
+/// Returns the saved revisions of a note, newest first. The current content
+/// is not included.
+pub fn list_revisions(conn: &Connection, note_id: &str) -> Result<Vec<NoteRevision>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, content, CAST(created_at AS INTEGER)
+             FROM note_revisions
+             WHERE note_id = ?1
+             ORDER BY id DESC",
+        )
+        .context("failed to prepare revisions query")?;
+
+    let rows = stmt
+        .query_map(params![note_id], |row| {
+            Ok(NoteRevision {
+                id: row.get(0)?,
+                content: row.get(1)?,
+                created_at: row.get(2)?,
+            })
+        })
+        .context("failed to execute revisions query")?;
+
+    let revisions = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode revisions")?;
+
+    Ok(revisions)
+}
+
+/// A checklist item of a live note.
+#[derive(Debug, Clone)]
+pub struct NoteTask {
+    pub note_id: String,
+    pub note_title: String,
+    pub line: usize,
+    pub text: String,
+    pub done: bool,
+}
+
+/// Checklist items across live notes, most recently updated notes first.
+/// `tag` keeps the notes carrying that tag or a tag below it.
+pub fn list_tasks(
+    conn: &Connection,
+    tag: Option<&str>,
+    include_done: bool,
+) -> Result<Vec<NoteTask>> {
+    let mut sql = String::from(
+        "SELECT t.note_id, n.title, t.line, t.text, t.done
+         FROM note_tasks t
+         JOIN notes n ON n.id = t.note_id
+         WHERE n.deleted_at IS NULL",
+    );
+    let mut args = Vec::new();
+
+    if !include_done {
+        sql.push_str(" AND t.done = 0");
+    }
+    if let Some(tag) = tag.map(fold_tag).filter(|tag| !tag.is_empty()) {
+        sql.push_str(
+            " AND t.note_id IN (
+                 SELECT nt.note_id
+                 FROM notes_tags nt
+                 JOIN tags g ON g.id = nt.tag_id
+                 WHERE ",
+        );
+        push_tag_subtree(&mut sql, &mut args, "g.name", &tag);
+        sql.push(')');
+    }
+    sql.push_str(" ORDER BY n.pinned DESC, n.updated_at DESC, t.line");
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .context("failed to prepare tasks query")?;
+    let rows = stmt
+        .query_map(params_from_iter(args), |row| {
+            Ok(NoteTask {
+                note_id: row.get(0)?,
+                note_title: row.get(1)?,
+                line: row.get(2)?,
+                text: row.get(3)?,
+                done: row.get(4)?,
+            })
+        })
+        .context("failed to execute tasks query")?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode tasks")
+}
+
+/// Checks or unchecks the checklist item on `line` of a note, saving the
+/// note like any other edit. Fails without saving when that line no longer
+/// holds the item `text`, as after an edit made since the task was listed.
+pub fn set_task_done(
+    conn: &mut Connection,
+    note_id: &str,
+    line: usize,
+    text: &str,
+    done: bool,
+    hashtags: HashtagMode,
+) -> Result<()> {
+    let Some(content) = get_note_content(conn, note_id)? else {
+        bail!("note not found");
+    };
+    if !markdown::tasks(&content)
+        .iter()
+        .any(|task| task.line == line && task.text == text)
+    {
+        bail!("the checklist item has changed since it was listed");
+    }
+    let Some(content) = markdown::set_task_done(&content, line, done) else {
+        bail!("line {} of the note is not a checklist item", line + 1);
+    };
+    update_note_content(conn, note_id, &content, hashtags)
+}
+
+/// A pending reminder of a live note.
+#[derive(Debug, Clone)]
+pub struct Reminder {
+    pub id: i64,
+    pub note_id: String,
+    pub note_title: String,
+    /// Unix seconds.
+    pub due_at: i64,
+}
+
+fn query_reminders(conn: &Connection, filter: &str, args: &[Value]) -> Result<Vec<Reminder>> {
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT r.id, r.note_id, n.title, r.due_at
+             FROM reminders r
+             JOIN notes n ON n.id = r.note_id
+             WHERE r.fired_at IS NULL AND n.deleted_at IS NULL {}
+             ORDER BY r.due_at, r.id",
+            filter
+        ))
+        .context("failed to prepare reminders query")?;
+    let rows = stmt
+        .query_map(params_from_iter(args), |row| {
+            Ok(Reminder {
+                id: row.get(0)?,
+                note_id: row.get(1)?,
+                note_title: row.get(2)?,
+                due_at: row.get(3)?,
+            })
+        })
+        .context("failed to execute reminders query")?;
+
+    rows.collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode reminders")
+}
+
+pub fn add_reminder(conn: &Connection, note_id: &str, due_at: i64) -> Result<i64> {
+    let added = conn
+        .execute(
+            "INSERT INTO reminders (note_id, due_at, created_at)
+             SELECT id, ?2, ?3 FROM notes WHERE id = ?1 AND deleted_at IS NULL",
+            params![note_id, due_at, now_unix_seconds()?],
+        )
+        .context("failed to add reminder")?;
+    if added == 0 {
+        bail!("note not found");
+    }
+    Ok(conn.last_insert_rowid())
+}
+
+/// Reminders not yet fired, soonest first. Reminders of trashed notes wait
+/// until the note is restored.
+pub fn list_upcoming_reminders(conn: &Connection) -> Result<Vec<Reminder>> {
+    query_reminders(conn, "", &[])
+}
+
+/// When the next pending reminder is due.
+pub fn next_reminder_due(conn: &Connection) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT MIN(r.due_at)
+         FROM reminders r
+         JOIN notes n ON n.id = r.note_id
+         WHERE r.fired_at IS NULL AND n.deleted_at IS NULL",
+        [],
+        |row| row.get(0),
+    )
+    .context("failed to find the next reminder")
+}
+
+/// Marks the reminders due by `now` as fired and returns them, including
+/// any that came due while the app was closed.
+pub fn take_due_reminders(conn: &mut Connection, now: i64) -> Result<Vec<Reminder>> {
+    let tx = conn
+        .transaction()
+        .context("failed to start reminders transaction")?;
+
+    let due = query_reminders(&tx, "AND r.due_at <= ?", &[Value::Integer(now)])?;
+    for reminder in &due {
+        tx.prepare_cached("UPDATE reminders SET fired_at = ?2 WHERE id = ?1")
+            .and_then(|mut stmt| stmt.execute(params![reminder.id, now]))
+            .context("failed to mark reminder as fired")?;
+    }
+
+    tx.commit()
+        .context("failed to commit reminders transaction")?;
+    Ok(due)
+}
+
+/// Makes a reminder due again at `due_at`, fired or not.
+pub fn snooze_reminder(conn: &Connection, id: i64, due_at: i64) -> Result<()> {
+    conn.execute(
+        "UPDATE reminders SET due_at = ?2, fired_at = NULL WHERE id = ?1",
+        params![id, due_at],
+    )
+    .context("failed to snooze reminder")?;
+    Ok(())
+}
+
+pub fn delete_reminder(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("DELETE FROM reminders WHERE id = ?1", params![id])
+        .context("failed to delete reminder")?;
+    Ok(())
+}
+
+/// Makes a revision the current content of its note and returns the note id.
+/// The content being replaced is itself saved as a revision, so a restore can
+/// be undone.
+pub fn restore_revision(conn: &mut Connection, revision_id: i64) -> Result<String> {
+    let (note_id, content): (String, String) = conn
+        .query_row(
+            "SELECT note_id, content FROM note_revisions WHERE id = ?1",
+            params![revision_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .context("failed to load revision")?;
+
+    update_note_content(conn, &note_id, &content, HashtagMode::Keep)?;
+    Ok(note_id)
+}
+
+pub fn prune_revisions(
+    conn: &Connection,
+    note_id: &str,
+    retention: RevisionRetention,
+) -> Result<()> {
+    if let Some(keep_last) = retention.keep_last {
+        conn.execute(
+            "DELETE FROM note_revisions
+             WHERE note_id = ?1 AND id NOT IN (
+                 SELECT id FROM note_revisions
+                 WHERE note_id = ?1
+                 ORDER BY id DESC
+                 LIMIT ?2
+             )",
+            params![note_id, keep_last],
+        )
+        .context("failed to prune old revisions")?;
+    }
+
+    if let Some(keep_days) = retention.keep_days {
+        let cutoff = unix_seconds()? - i64::from(keep_days) * 86_400;
+        conn.execute(
+            "DELETE FROM note_revisions
+             WHERE note_id = ?1 AND CAST(created_at AS INTEGER) < ?2",
+            params![note_id, cutoff],
+        )
+        .context("failed to prune expired revisions")?;
+    }
+
+    Ok(())
+}
+
+pub fn list_saved_searches(conn: &Connection) -> Result<Vec<SavedSearch>> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, name, query, mode
+             FROM saved_searches
+             ORDER BY name COLLATE NOCASE ASC",
+        )
+        .context("failed to prepare saved searches query")?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(SavedSearch {
+                id: row.get(0)?,
+                name: row.get(1)?,
+                query: row.get(2)?,
+                mode: QueryMode::from_name(&row.get::<_, String>(3)?),
+            })
+        })
+        .context("failed to execute saved searches query")?;
+
+    let searches = rows
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .context("failed to decode saved searches")?;
+
+    Ok(searches)
+}
+
+/// Saves a search under `name`, replacing the one already saved with that
+/// name.
+pub fn save_search(conn: &Connection, name: &str, query: &str, mode: QueryMode) -> Result<()> {
+    let name = name.trim();
+    if name.is_empty() {
+        bail!("saved search name cannot be empty");
+    }
+
+    conn.execute(
+        "INSERT INTO saved_searches (name, query, mode, created_at)
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(name) DO UPDATE SET query = excluded.query, mode = excluded.mode",
+        params![name, query.trim(), mode.name(), now_unix_seconds()?],
+    )
+    .context("failed to save search")?;
+
+    Ok(())
+}
+
+pub fn delete_saved_search(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("DELETE FROM saved_searches WHERE id = ?1", params![id])
+        .context("failed to delete saved search")?;
+    Ok(())
+}
+
+/// Number of notes the Notes list shows for `query`: a simple search that
+/// finds nothing counts its fuzzy fallback, which the list retries it with.
+pub fn count_listed_notes(conn: &Connection, query: &SearchQuery) -> Result<i64> {
+    let count = count_notes(conn, query)?;
+    match query.fallback() {
+        Some(fuzzy) if count == 0 => count_notes(conn, &fuzzy),
+        _ => Ok(count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::query;
+
+    fn open() -> Connection {
+        open_and_init(Path::new(":memory:")).unwrap()
+    }
+
+    fn record(id: &str, content: &str, updated_at: i64, tags: &[&str]) -> NoteRecord {
+        NoteRecord {
+            id: id.to_string(),
+            content: content.to_string(),
+            created_at: updated_at,
+            updated_at,
+            pinned: false,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn note_position_follows_the_query() {
+        let mut conn = open();
+        let records: Vec<NoteRecord> = (0..300)
+            .map(|index| {
+                let tags: &[&str] = if index % 2 == 0 { &["even"] } else { &[] };
+                record(
+                    &format!("note-{index:03}"),
+                    &format!("Note {index}"),
+                    1_700_000_000 + index,
+                    tags,
+                )
+            })
+            .collect();
+        insert_note_records(&mut conn, &records).unwrap();
+
+        let all = query::parse("", QueryMode::Simple, 0);
+        let even = query::parse("tag:even", QueryMode::Simple, 0);
+        assert_eq!(note_position(&conn, &all, "note-000").unwrap(), Some(299));
+        assert_eq!(note_position(&conn, &even, "note-000").unwrap(), Some(149));
+        assert_eq!(note_position(&conn, &even, "note-290").unwrap(), Some(4));
+        assert_eq!(note_position(&conn, &even, "note-291").unwrap(), None);
+
+        let ranked = query::parse("note", QueryMode::Simple, 0);
+        let page = search_notes_page(&conn, &ranked, &PageStart::Offset(0), 100).unwrap();
+        for (index, item) in page.items.iter().enumerate() {
+            let position = note_position(&conn, &ranked, &item.id).unwrap();
+            assert_eq!(position, Some(index as u32));
+        }
+
+        set_pinned(&conn, "note-000", true).unwrap();
+        assert_eq!(note_position(&conn, &even, "note-000").unwrap(), Some(0));
+    }
+
+    fn links_of(conn: &Connection, note_id: &str) -> Vec<String> {
+        let mut targets: Vec<String> = note_link_targets(conn, note_id)
+            .unwrap()
+            .into_keys()
+            .collect();
+        targets.sort();
+        targets
+    }
+
+    #[test]
+    fn reimport_keeps_existing_links() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha\nsee [[Beta]]", 1_700_000_000, &[]),
+            record("b", "Beta", 1_700_000_001, &[]),
+            record("g", "Gamma", 1_700_000_001, &[]),
+        ];
+        assert_eq!(insert_note_records(&mut conn, &records).unwrap(), 3);
+        update_note_content(&mut conn, "a", "Alpha\nsee [[Gamma]]", HashtagMode::Keep).unwrap();
+
+        // The same id with old content, and old content under a new id.
+        let again = [
+            record("a", "Alpha\nsee [[Beta]]", 1_700_000_000, &[]),
+            record("c", "Alpha\nsee [[Gamma]]", 1_700_000_002, &[]),
+            record("d", "Delta\nsee [[Beta]]", 1_700_000_003, &[]),
+        ];
+        assert_eq!(insert_note_records(&mut conn, &again).unwrap(), 1);
+
+        assert_eq!(links_of(&conn, "a"), ["Gamma"]);
+        assert_eq!(links_of(&conn, "d"), ["Beta"]);
+        let orphans: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM note_links WHERE source_id NOT IN (SELECT id FROM notes)",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(orphans, 0);
+    }
+
+    #[test]
+    fn import_skips_known_content_by_digest() {
+        let mut conn = open();
+        let id = insert_note(&mut conn, "Alpha", &[], HashtagMode::Keep).unwrap();
+        update_note_content(&mut conn, &id, "Beta", HashtagMode::Keep).unwrap();
+
+        let again = [
+            record("x", "Alpha", 1_700_000_000, &[]),
+            record("y", "  Beta\n", 1_700_000_000, &[]),
+            record("z", "Gamma", 1_700_000_000, &[]),
+            record("w", "Gamma", 1_700_000_001, &[]),
+        ];
+        assert_eq!(insert_note_records(&mut conn, &again).unwrap(), 2);
+        assert!(get_note_content(&conn, "x").unwrap().is_some());
+        assert!(get_note_content(&conn, "y").unwrap().is_none());
+        assert!(get_note_content(&conn, "w").unwrap().is_none());
+
+        // A digest shared with different content is not a duplicate.
+        conn.execute(
+            "UPDATE notes SET content_hash = ?1 WHERE id = 'z'",
+            params![content_hash("Delta")],
+        )
+        .unwrap();
+        let colliding = [record("d", "Delta", 1_700_000_002, &[])];
+        assert_eq!(insert_note_records(&mut conn, &colliding).unwrap(), 1);
+    }
+
+    #[test]
+    fn trashed_notes_are_only_read_on_request() {
+        let mut conn = open();
+        let id = insert_note(&mut conn, "- [ ] pain", &[], HashtagMode::Keep).unwrap();
+        soft_delete_note(&conn, &id).unwrap();
+
+        assert_eq!(get_note_content(&conn, &id).unwrap(), None);
+        assert_eq!(
+            get_note_content_any(&conn, &id).unwrap().as_deref(),
+            Some("- [ ] pain")
+        );
+        assert!(set_task_done(&mut conn, &id, 0, "pain", true, HashtagMode::Keep).is_err());
+        assert_eq!(
+            get_note_content_any(&conn, &id).unwrap().as_deref(),
+            Some("- [ ] pain")
+        );
+    }
+
+    #[test]
+    fn tags_fold_like_the_search_index() {
+        let sample = "Noël à l'œuvre : déjà Ærø, maïs façonné, Łódź, ÉTÉ naïf";
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "CREATE VIRTUAL TABLE sample USING fts5(content, tokenize = '{}');
+             CREATE VIRTUAL TABLE sample_terms USING fts5vocab(sample, 'row');",
+            SearchTokenizer::Unicode61.fts5_option()
+        ))
+        .unwrap();
+        conn.execute("INSERT INTO sample VALUES (?1)", params![sample])
+            .unwrap();
+
+        let mut indexed: Vec<String> = conn
+            .prepare("SELECT term FROM sample_terms")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        let mut folded: Vec<String> = sample
+            .split(|ch: char| !ch.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+            .map(fold_text)
+            .collect();
+        indexed.sort();
+        folded.sort();
+        folded.dedup();
+        assert_eq!(folded, indexed);
+    }
+
+    #[test]
+    fn saved_searches_keep_their_tag_filter() {
+        let mut conn = open();
+        let records = [
+            record("a", "Facture mars", 1_700_000_000, &["client acme"]),
+            record("b", "Facture avril", 1_700_000_001, &["autre"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+        save_search(
+            &conn,
+            "Acme",
+            "facture tag:\"client acme\"",
+            QueryMode::Simple,
+        )
+        .unwrap();
+
+        let saved = list_saved_searches(&conn).unwrap();
+        assert_eq!(saved.len(), 1);
+        let search = query::parse(&saved[0].query, saved[0].mode, 0);
+        assert_eq!(count_notes(&conn, &search).unwrap(), 1);
+    }
+
+    #[test]
+    fn listed_count_follows_the_fuzzy_fallback() {
+        let mut conn = open();
+        let records = [record("a", "Appeler le plombier", 1_700_000_000, &[])];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        let typo = query::parse("plomber", QueryMode::Simple, 0);
+        assert_eq!(count_notes(&conn, &typo).unwrap(), 0);
+        assert_eq!(count_listed_notes(&conn, &typo).unwrap(), 1);
+
+        let missing = query::parse("zzzz", QueryMode::Simple, 0);
+        assert_eq!(count_listed_notes(&conn, &missing).unwrap(), 0);
+    }
+
+    fn tags_of(conn: &Connection, note_id: &str) -> Vec<String> {
+        let mut tags = get_note_tags(conn, note_id).unwrap();
+        tags.sort();
+        tags
+    }
+
+    #[test]
+    fn renaming_onto_an_existing_tag_fails() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha", 1_700_000_000, &["perso"]),
+            record("b", "Beta", 1_700_000_001, &["prive"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        let err = rename_tag(&mut conn, "perso", "Privé").unwrap_err();
+        assert!(err.to_string().contains("already exists"));
+        assert_eq!(list_tags(&conn).unwrap(), ["perso", "prive"]);
+
+        rename_tag(&mut conn, "perso", "Maison").unwrap();
+        assert_eq!(tags_of(&conn, "a"), ["maison"]);
+    }
+
+    #[test]
+    fn merging_keeps_one_link_per_note() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha", 1_700_000_000, &["idee", "projet"]),
+            record("b", "Beta", 1_700_000_001, &["idee"]),
+            record("c", "Gamma", 1_700_000_002, &["todo"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        merge_tags(
+            &mut conn,
+            &["idee".to_string(), "todo".to_string()],
+            "projet",
+        )
+        .unwrap();
+        assert_eq!(list_tags(&conn).unwrap(), ["projet"]);
+        for id in ["a", "b", "c"] {
+            assert_eq!(tags_of(&conn, id), ["projet"]);
+        }
+        let usage = list_tag_usage(&conn).unwrap();
+        assert_eq!(usage[0].notes, 3);
+    }
+
+    #[test]
+    fn deleting_a_used_tag_unlinks_it() {
+        let mut conn = open();
+        let records = [record("a", "Alpha", 1_700_000_000, &["perso", "garder"])];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        delete_tag(&mut conn, "Perso").unwrap();
+        assert_eq!(list_tags(&conn).unwrap(), ["garder"]);
+        assert_eq!(tags_of(&conn, "a"), ["garder"]);
+        assert!(get_note_content(&conn, "a").unwrap().is_some());
+    }
+
+    #[test]
+    fn pruning_removes_only_unused_tags() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha", 1_700_000_000, &["vivant"]),
+            record("b", "Beta", 1_700_000_001, &["corbeille"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+        soft_delete_note(&conn, "b").unwrap();
+        conn.execute("INSERT INTO tags (name) VALUES ('orphelin')", [])
+            .unwrap();
+
+        assert_eq!(prune_orphan_tags(&conn).unwrap(), 1);
+        assert_eq!(list_tags(&conn).unwrap(), ["corbeille", "vivant"]);
+    }
+
+    fn ids_matching(conn: &Connection, input: &str) -> Vec<String> {
+        let search = query::parse(input, QueryMode::Simple, 0);
+        let mut ids: Vec<String> = search_notes(conn, &search, -1)
+            .unwrap()
+            .into_iter()
+            .map(|note| note.id)
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    #[test]
+    fn tag_filters_cover_the_subtree_only() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha", 1_700_000_000, &["client"]),
+            record("b", "Beta", 1_700_000_001, &["client/acme/billing"]),
+            record("c", "Gamma", 1_700_000_002, &["clientele"]),
+            record("d", "Delta", 1_700_000_003, &["client-old"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        assert_eq!(ids_matching(&conn, "tag:client"), ["a", "b"]);
+        assert_eq!(ids_matching(&conn, "tag:client/acme"), ["b"]);
+        assert_eq!(ids_matching(&conn, "-tag:client"), ["c", "d"]);
+    }
+
+    #[test]
+    fn renaming_a_parent_moves_its_subtree() {
+        let mut conn = open();
+        let records = [
+            record("a", "Alpha", 1_700_000_000, &["client"]),
+            record("b", "Beta", 1_700_000_001, &["client/acme/billing"]),
+            record("c", "Gamma", 1_700_000_002, &["clientele"]),
+        ];
+        insert_note_records(&mut conn, &records).unwrap();
+
+        rename_tag(&mut conn, "client", "customer").unwrap();
+        assert_eq!(
+            list_tags(&conn).unwrap(),
+            ["clientele", "customer", "customer/acme/billing"]
+        );
+        assert_eq!(tags_of(&conn, "b"), ["customer/acme/billing"]);
+
+        let clash = [record(
+            "d",
+            "Delta",
+            1_700_000_003,
+            &["client/acme/billing"],
+        )];
+        insert_note_records(&mut conn, &clash).unwrap();
+        let err = rename_tag(&mut conn, "customer", "client").unwrap_err();
+        assert!(err.to_string().contains("client/acme/billing"));
+        assert!(rename_tag(&mut conn, "customer", "customer/old").is_err());
+        assert_eq!(tags_of(&conn, "a"), ["customer"]);
+    }
+
+    #[test]
+    fn stale_tasks_are_not_toggled() {
+        let mut conn = open();
+        let id = insert_note(&mut conn, "- [ ] pain\n- [ ] lait", &[], HashtagMode::Keep).unwrap();
+        let tasks = list_tasks(&conn, None, false).unwrap();
+        let lait = tasks.iter().find(|task| task.text == "lait").unwrap();
+
+        update_note_content(
+            &mut conn,
+            &id,
+            "- [ ] beurre\n- [ ] pain",
+            HashtagMode::Keep,
+        )
+        .unwrap();
+        let result = set_task_done(
+            &mut conn,
+            &id,
+            lait.line,
+            &lait.text,
+            true,
+            HashtagMode::Keep,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            get_note_content(&conn, &id).unwrap().as_deref(),
+            Some("- [ ] beurre\n- [ ] pain")
+        );
+    }
+
+    #[test]
+    fn toggling_a_task_follows_the_hashtag_mode() {
+        let mut conn = open();
+        let id = insert_note(&mut conn, "Courses\n- [ ] pain", &[], HashtagMode::Keep).unwrap();
+        update_note_content(
+            &mut conn,
+            &id,
+            "Courses #maison\n- [ ] pain",
+            HashtagMode::Keep,
+        )
+        .unwrap();
+
+        set_task_done(&mut conn, &id, 1, "pain", true, HashtagMode::Strip).unwrap();
+        assert_eq!(
+            get_note_content(&conn, &id).unwrap().as_deref(),
+            Some("Courses\n- [x] pain")
+        );
+    }
+
+    #[test]
+    fn saving_does_not_restore_removed_hashtags() {
+        let mut conn = open();
+        let id = insert_note(
+            &mut conn,
+            "Courses #maison\n- [ ] pain",
+            &[],
+            HashtagMode::Keep,
+        )
+        .unwrap();
+        assert_eq!(get_note_tags(&conn, &id).unwrap(), ["maison"]);
+
+        delete_tag(&mut conn, "maison").unwrap();
+        set_task_done(&mut conn, &id, 1, "pain", true, HashtagMode::Keep).unwrap();
+        assert!(get_note_tags(&conn, &id).unwrap().is_empty());
+
+        update_note_content(
+            &mut conn,
+            &id,
+            "Courses #maison #urgent\n- [x] pain",
+            HashtagMode::Keep,
+        )
+        .unwrap();
+        assert_eq!(get_note_tags(&conn, &id).unwrap(), ["urgent"]);
+    }
+
+    #[test]
+    fn exports_the_trash_when_searching_it() {
+        let mut conn = open();
+        let kept = insert_note(&mut conn, "Kept", &[], HashtagMode::Keep).unwrap();
+        let trashed = insert_note(&mut conn, "Trashed", &[], HashtagMode::Keep).unwrap();
+        soft_delete_note(&conn, &trashed).unwrap();
+
+        let trash = query::parse("is:deleted", QueryMode::Simple, 0);
+        let ids: Vec<String> = search_notes(&conn, &trash, -1)
+            .unwrap()
+            .into_iter()
+            .map(|note| note.id)
+            .collect();
+        let records = get_note_records(&conn, Some(&ids)).unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].content, "Trashed");
+
+        let all = get_note_records(&conn, None).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].id, kept);
+    }
+
+    #[test]
+    fn fuzzy_ranking_pages_like_fuzzy_search() {
+        let mut conn = open();
+        for index in 0..40 {
+            let content = format!("Invoice INV-{index:04} for client {}", index % 3);
+            insert_note(&mut conn, &content, &[], HashtagMode::Keep).unwrap();
+        }
+
+        let query = query::parse("invoce", QueryMode::Fuzzy, 0);
+        let ranking = fuzzy_ranking(&conn, &query).unwrap();
+        assert_eq!(ranking.len(), 40);
+        assert_eq!(count_notes(&conn, &query).unwrap(), 40);
+
+        let page = search_notes_page(&conn, &query, &PageStart::Offset(10), 10).unwrap();
+        let notes = fuzzy_notes(&conn, &query, &ranking[10..20]).unwrap();
+        let expected: Vec<&str> = page.items.iter().map(|item| item.id.as_str()).collect();
+        let found: Vec<&str> = notes.iter().map(|item| item.id.as_str()).collect();
+        assert_eq!(found, expected);
+        assert_eq!(notes[0].snippet, page.items[0].snippet);
+    }
+
+    #[test]
+    fn fuzzy_text_too_short_for_trigrams_still_filters() {
+        let mut conn = open();
+        insert_note(&mut conn, "Abricots secs", &[], HashtagMode::Keep).unwrap();
+        insert_note(&mut conn, "Poires", &[], HashtagMode::Keep).unwrap();
+
+        let query = query::parse("ab", QueryMode::Fuzzy, 0);
+        assert_eq!(query.mode, QueryMode::Simple);
+        assert_eq!(count_notes(&conn, &query).unwrap(), 1);
+
+        let query = query::parse("v2 c++ ", QueryMode::Fuzzy, 0);
+        assert_eq!(count_notes(&conn, &query).unwrap(), 0);
+    }
+
+    #[test]
+    fn tag_prefix_wildcards_match_literally() {
+        let mut conn = open();
+        let tags = [
+            "réunion/x".to_string(),
+            "€/x".to_string(),
+            "a_b/c".to_string(),
+        ];
+        insert_note(&mut conn, "Note", &tags, HashtagMode::Keep).unwrap();
+
+        assert!(list_tags_prefix(&conn, "%/", 10).unwrap().is_empty());
+        assert!(list_tags_prefix(&conn, "_/", 10).unwrap().is_empty());
+        assert_eq!(list_tags_prefix(&conn, "a_", 10).unwrap(), ["a_b/"]);
+        assert_eq!(
+            list_tags_prefix(&conn, "reunion/", 10).unwrap(),
+            ["reunion/x"]
+        );
+    }
+}
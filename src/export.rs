@@ -0,0 +1,184 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{bail, Context, Result};
+
+use crate::dates;
+use crate::db::{self, NoteRecord};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportMode {
+    /// One `.md` file per note inside the target directory.
+    Directory,
+    /// Every note concatenated into the target file. This is one-way: the
+    /// importer reads such a file back as a single note.
+    SingleFile,
+}
+
+fn yaml_string(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len() + 2);
+    quoted.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn front_matter(note: &NoteRecord) -> String {
+    let tags = note
+        .tags
+        .iter()
+        .map(|tag| yaml_string(tag))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    format!(
+        "---\nid: {}\ncreated_at: {}\nupdated_at: {}\npinned: {}\ntags: [{}]\n---\n",
+        yaml_string(&note.id),
+        dates::format_rfc3339(note.created_at),
+        dates::format_rfc3339(note.updated_at),
+        note.pinned,
+        tags
+    )
+}
+
+fn render_note(note: &NoteRecord) -> String {
+    let mut out = front_matter(note);
+    out.push('\n');
+    out.push_str(note.content.trim_end());
+    out.push('\n');
+    out
+}
+
+fn file_name(note: &NoteRecord) -> String {
+    let mut slug = String::new();
+    for ch in db::note_title(&note.content).chars() {
+        if ch.is_alphanumeric() {
+            slug.extend(ch.to_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.chars().count() >= 40 {
+            break;
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    let short_id: String = note.id.chars().take(8).collect();
+
+    if slug.is_empty() {
+        format!("note-{}.md", short_id)
+    } else {
+        format!("{}-{}.md", slug, short_id)
+    }
+}
+
+/// Writes `notes` to `target`. In directory mode no existing file is
+/// replaced: the export stops before writing anything if one would be.
+pub fn export_notes(notes: &[NoteRecord], target: &Path, mode: ExportMode) -> Result<usize> {
+    match mode {
+        ExportMode::Directory => {
+            fs::create_dir_all(target).context("failed to create export directory")?;
+
+            let paths: Vec<_> = notes
+                .iter()
+                .map(|note| target.join(file_name(note)))
+                .collect();
+            if let Some(existing) = paths.iter().find(|path| path.exists()) {
+                bail!(
+                    "{} already exists; export to an empty folder",
+                    existing.display()
+                );
+            }
+
+            for (note, path) in notes.iter().zip(&paths) {
+                OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .open(path)
+                    .and_then(|mut file| file.write_all(render_note(note).as_bytes()))
+                    .with_context(|| format!("failed to write {}", path.display()))?;
+            }
+        }
+        ExportMode::SingleFile => {
+            let joined = notes.iter().map(render_note).collect::<Vec<_>>().join("\n");
+            fs::write(target, joined)
+                .with_context(|| format!("failed to write {}", target.display()))?;
+        }
+    }
+
+    Ok(notes.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(id: &str, content: &str) -> NoteRecord {
+        NoteRecord {
+            id: id.to_string(),
+            content: content.to_string(),
+            created_at: 1_700_000_000,
+            updated_at: 1_700_000_060,
+            pinned: true,
+            tags: vec!["projet/memo".to_string(), "a \"b\"".to_string()],
+        }
+    }
+
+    #[test]
+    fn front_matter_lists_every_field() {
+        let note = note("0b6c1c1e-0000-4000-8000-000000000000", "Titre\n\nCorps\n\n");
+        assert_eq!(
+            render_note(&note),
+            "---\n\
+             id: \"0b6c1c1e-0000-4000-8000-000000000000\"\n\
+             created_at: 2023-11-14T22:13:20Z\n\
+             updated_at: 2023-11-14T22:14:20Z\n\
+             pinned: true\n\
+             tags: [\"projet/memo\", \"a \\\"b\\\"\"]\n\
+             ---\n\
+             \n\
+             Titre\n\nCorps\n"
+        );
+    }
+
+    #[test]
+    fn file_names_are_slugs_of_the_title() {
+        let id = "0b6c1c1e-0000-4000-8000-000000000000";
+        assert_eq!(
+            file_name(&note(id, "  Idée : l'école, demain !\nsuite")),
+            "idée-l-école-demain-0b6c1c1e.md"
+        );
+        assert_eq!(file_name(&note(id, "?!")), "note-0b6c1c1e.md");
+        assert_eq!(
+            file_name(&note(id, &"mot ".repeat(30))),
+            "mot-mot-mot-mot-mot-mot-mot-mot-mot-mot-0b6c1c1e.md"
+        );
+    }
+
+    #[test]
+    fn directory_export_never_overwrites() {
+        let dir = std::env::temp_dir().join(format!("memo-tori-export-{}", uuid::Uuid::new_v4()));
+        let notes = [note("0b6c1c1e-0000-4000-8000-000000000000", "Une note")];
+        assert_eq!(
+            export_notes(&notes, &dir, ExportMode::Directory).unwrap(),
+            1
+        );
+
+        let path = dir.join("une-note-0b6c1c1e.md");
+        fs::write(&path, "mine").unwrap();
+        let result = export_notes(&notes, &dir, ExportMode::Directory);
+        let kept = fs::read_to_string(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.is_err());
+        assert_eq!(kept, "mine");
+    }
+}
@@ -0,0 +1,236 @@
+// Typo-tolerant matching for the fuzzy search mode. The trigram index only
+// narrows the notes down to candidates; each one is then scored here by how
+// far its closest words are from the search terms.
+
+use std::ops::Range;
+
+use crate::db::fold_text;
+
+fn levenshtein(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+// Typos allowed for a term of `len` characters.
+fn max_distance(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=5 => 1,
+        6..=8 => 2,
+        _ => 3,
+    }
+}
+
+// Byte ranges of the runs of letters and digits in `content`.
+fn words(content: &str) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start = None;
+
+    for (index, ch) in content.char_indices() {
+        if ch.is_alphanumeric() {
+            start.get_or_insert(index);
+        } else if let Some(begin) = start.take() {
+            words.push(begin..index);
+        }
+    }
+
+    if let Some(begin) = start {
+        words.push(begin..content.len());
+    }
+
+    words
+}
+
+/// Scores `content` against folded `terms`: the sum of each term's distance
+/// to its closest word, where containing the term counts as no distance.
+/// Returns `None` when a term has no close enough word, otherwise the score
+/// (lower is better) and the byte ranges of the words that matched.
+pub fn score(content: &str, terms: &[String]) -> Option<(f64, Vec<Range<usize>>)> {
+    let words: Vec<(Range<usize>, String)> = words(content)
+        .into_iter()
+        .map(|range| {
+            let folded = fold_text(&content[range.clone()]);
+            (range, folded)
+        })
+        .collect();
+
+    let mut total = 0;
+    let mut matched = vec![false; words.len()];
+
+    for term in terms {
+        let term_chars: Vec<char> = term.chars().collect();
+        let allowed = max_distance(term_chars.len());
+        let mut best = None;
+
+        for (index, (_, word)) in words.iter().enumerate() {
+            let distance = if word.contains(term.as_str()) {
+                0
+            } else {
+                let word_chars: Vec<char> = word.chars().collect();
+                levenshtein(&term_chars, &word_chars)
+            };
+
+            if distance <= allowed {
+                matched[index] = true;
+                best = Some(best.map_or(distance, |best: usize| best.min(distance)));
+            }
+        }
+
+        total += best?;
+    }
+
+    let ranges = words
+        .into_iter()
+        .zip(matched)
+        .filter(|(_, matched)| *matched)
+        .map(|((range, _), _)| range)
+        .collect();
+
+    Some((total as f64, ranges))
+}
+
+/// A one-line extract of `content` around the first of `matches`, with the
+/// matches moved to their position in the extract.
+pub fn snippet(content: &str, matches: &[Range<usize>]) -> (String, Vec<Range<usize>>) {
+    let Some(first) = matches.first() else {
+        return (String::new(), Vec::new());
+    };
+
+    // About 30 characters of context before the match and 90 after, cut at
+    // whitespace so no word is split.
+    let start = content[..first.start]
+        .char_indices()
+        .rev()
+        .take(30)
+        .last()
+        .map_or(first.start, |(index, _)| index);
+    let start = if start == 0 {
+        0
+    } else {
+        content[start..first.start]
+            .find(char::is_whitespace)
+            .map_or(start, |offset| start + offset)
+    };
+    let end = content[first.start..]
+        .char_indices()
+        .nth(90)
+        .map_or(content.len(), |(index, _)| first.start + index);
+    let end = if end == content.len() {
+        end
+    } else {
+        content[first.end.min(end)..end]
+            .rfind(char::is_whitespace)
+            .map_or(end, |offset| first.end.min(end) + offset)
+    };
+
+    let mut text = String::new();
+    if start > 0 {
+        text.push('…');
+    }
+
+    let mut ranges = Vec::new();
+    let mut open = None;
+    let mut pending_space = false;
+
+    for (offset, ch) in content[start..end].char_indices() {
+        let index = start + offset;
+        if ch.is_whitespace() {
+            pending_space = !text.is_empty();
+            continue;
+        }
+        if pending_space {
+            text.push(' ');
+            pending_space = false;
+        }
+        if matches.iter().any(|range| range.start == index) {
+            open = Some(text.len());
+        }
+        text.push(ch);
+        if let Some(begin) = open {
+            if matches
+                .iter()
+                .any(|range| range.end == index + ch.len_utf8())
+            {
+                ranges.push(begin..text.len());
+                open = None;
+            }
+        }
+    }
+
+    if end < content.len() {
+        text.push('…');
+    }
+
+    (text, ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn terms(words: &[&str]) -> Vec<String> {
+        words.iter().map(|word| word.to_string()).collect()
+    }
+
+    #[test]
+    fn tolerates_typos_in_proportion_to_length() {
+        assert!(score("une idée géniale", &terms(&["genaile"])).is_some());
+        assert!(score("une idée géniale", &terms(&["idee"])).is_some());
+        assert!(score("rendez-vous plombier", &terms(&["plombeir"])).is_some());
+        assert!(score("rendez-vous plombier", &terms(&["facture"])).is_none());
+        assert!(score("abc", &terms(&["abd"])).is_none());
+    }
+
+    #[test]
+    fn finds_fragments_inside_words() {
+        let content = "voir https://example.org/ticket/AB-4521 demain";
+        let (score, ranges) = score(content, &terms(&["4521", "exampl"])).unwrap();
+        assert_eq!(score, 0.0);
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|range| &content[range.clone()])
+                .collect::<Vec<_>>(),
+            vec!["example", "4521"]
+        );
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        assert!(score("courses du weekend", &terms(&["courses", "lundi"])).is_none());
+        let closer = score("courses", &terms(&["courses"])).unwrap().0;
+        let farther = score("cources", &terms(&["courses"])).unwrap().0;
+        assert!(closer < farther);
+    }
+
+    #[test]
+    fn snippet_keeps_match_ranges_aligned() {
+        let content =
+            "Titre\n\nUn   long texte avant la partie qui parle du plombier et de la facture, \
+                       puis encore beaucoup de texte qui ne tiendra pas dans l'extrait affiche.";
+        let matches = score(content, &terms(&["plombier", "facture"])).unwrap().1;
+        let (text, ranges) = snippet(content, &matches);
+
+        assert!(text.starts_with('…'));
+        assert!(text.ends_with('…'));
+        assert!(!text.contains("  "));
+        assert_eq!(
+            ranges
+                .iter()
+                .map(|range| &text[range.clone()])
+                .collect::<Vec<_>>(),
+            vec!["plombier", "facture"]
+        );
+    }
+}
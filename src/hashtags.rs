@@ -0,0 +1,192 @@
+// Inline `#tag` detection. A hashtag starts at the beginning of a line or
+// after whitespace/opening punctuation, may contain letters, digits, `_`, `-`
+// and `/` (for nested tags), and must contain at least one letter so that
+// `#1` or Markdown headings (`# Title`) are not mistaken for tags. Code spans
+// and Markdown anchors such as `[see](#setup)` hold no hashtags.
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+fn is_tag_char(ch: char) -> bool {
+    ch.is_alphanumeric() || matches!(ch, '_' | '-' | '/')
+}
+
+fn is_boundary(ch: Option<char>) -> bool {
+    match ch {
+        None => true,
+        Some(ch) => ch.is_whitespace() || matches!(ch, '[' | '{' | ',' | ';' | '"' | '\''),
+    }
+}
+
+/// What happens to hashtags in the text once they are turned into tags.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashtagMode {
+    /// The text is saved as typed.
+    #[default]
+    Keep,
+    /// Hashtags are removed from the saved text.
+    Strip,
+}
+
+// Byte ranges of the hashtags in `content`, `#` included, with their
+// lowercase names.
+fn find(content: &str) -> Vec<(Range<usize>, String)> {
+    let mut found = Vec::new();
+    let mut in_fence = false;
+    let mut line_start = 0;
+
+    for line in content.split_inclusive('\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut previous = None;
+        let mut chars = line.char_indices().peekable();
+        while let Some((index, ch)) = chars.next() {
+            if ch == '`' {
+                let ticks = line[index..].len() - line[index..].trim_start_matches('`').len();
+                let fence = &line[index..index + ticks];
+                let after = index + ticks;
+                if let Some(close) = line[after..].find(fence) {
+                    let end = after + close + ticks;
+                    while chars.peek().is_some_and(|(i, _)| *i < end) {
+                        chars.next();
+                    }
+                }
+                previous = Some('`');
+                continue;
+            }
+            if ch == '#' && is_boundary(previous) {
+                let rest = &line[index + 1..];
+                let end = rest.find(|c: char| !is_tag_char(c)).unwrap_or(rest.len());
+                let tag = rest[..end].trim_end_matches(['/', '-', '_']);
+
+                if tag.chars().any(char::is_alphabetic) && !tag.starts_with('/') {
+                    let start = offset + index;
+                    found.push((start..start + 1 + tag.len(), tag.to_lowercase()));
+                }
+
+                while chars.peek().is_some_and(|(i, _)| *i <= index + end) {
+                    chars.next();
+                }
+                previous = rest[..end].chars().last().or(Some('#'));
+                continue;
+            }
+            previous = Some(ch);
+        }
+    }
+
+    found
+}
+
+/// The partly typed hashtag `text` ends with, without its `#`.
+pub fn fragment_at_end(text: &str) -> Option<&str> {
+    let start = text
+        .char_indices()
+        .rev()
+        .find(|(_, ch)| !is_tag_char(*ch))
+        .map_or(0, |(index, ch)| index + ch.len_utf8());
+    let before = text[..start].strip_suffix('#')?;
+    let fragment = &text[start..];
+
+    (!fragment.is_empty() && is_boundary(before.chars().last())).then_some(fragment)
+}
+
+/// Returns the lowercase hashtags found in `content`, in order of first
+/// appearance, skipping code blocks and code spans.
+pub fn extract(content: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for (_, tag) in find(content) {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    }
+    tags
+}
+
+/// Removes the hashtags from `content`. Spaces left doubled on those lines
+/// are collapsed, and lines that held nothing but hashtags are dropped.
+pub fn strip(content: &str) -> String {
+    let found = find(content);
+    if found.is_empty() {
+        return content.to_string();
+    }
+
+    let mut kept = String::with_capacity(content.len());
+    let mut last = 0;
+    for (range, _) in &found {
+        kept.push_str(&content[last..range.start]);
+        last = range.end;
+    }
+    kept.push_str(&content[last..]);
+
+    let mut stripped = String::with_capacity(kept.len());
+    for (line, original) in kept
+        .split_inclusive('\n')
+        .zip(content.split_inclusive('\n'))
+    {
+        if line == original {
+            stripped.push_str(line);
+            continue;
+        }
+
+        let text = line.trim_end_matches('\n');
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let indent = &text[..text.len() - text.trim_start().len()];
+        stripped.push_str(indent);
+        stripped.push_str(&text.split_whitespace().collect::<Vec<_>>().join(" "));
+        if line.ends_with('\n') {
+            stripped.push('\n');
+        }
+    }
+
+    stripped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn extracts_nested_tags_outside_code() {
+        let content = "Appel #Client/Acme, voir #todo\n```\n#include <x>\n```\n# Titre #1 #todo";
+        assert_eq!(extract(content), vec!["client/acme", "todo"]);
+    }
+
+    #[test]
+    fn skips_code_spans_and_anchors() {
+        let content = "Voir [install](#setup) et `#define X` ou ``a ` #b``, puis #todo";
+        assert_eq!(extract(content), vec!["todo"]);
+        assert_eq!(extract("(#idee) `inachevé #tag"), vec!["tag"]);
+    }
+
+    #[test]
+    fn finds_the_hashtag_being_typed() {
+        assert_eq!(fragment_at_end("idee #cli"), Some("cli"));
+        assert_eq!(fragment_at_end("#client/ac"), Some("client/ac"));
+        assert_eq!(fragment_at_end("idee #"), None);
+        assert_eq!(fragment_at_end("page#ancre"), None);
+        assert_eq!(fragment_at_end("#todo fini"), None);
+    }
+
+    #[test]
+    fn strip_tidies_only_the_lines_it_touched() {
+        let content = "Appeler Bob #urgent demain\n#perso #todo\n  code  aligne\n```\n#keep\n```";
+        assert_eq!(
+            strip(content),
+            "Appeler Bob demain\n  code  aligne\n```\n#keep\n```"
+        );
+    }
+}
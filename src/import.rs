@@ -0,0 +1,423 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use uuid::Uuid;
+
+use crate::dates;
+use crate::db::{self, NoteRecord};
+use crate::hashtags;
+
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    pub scanned: usize,
+    pub imported: usize,
+    pub skipped: usize,
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+#[derive(Debug)]
+enum FrontMatterValue {
+    Scalar(String),
+    List(Vec<String>),
+}
+
+impl FrontMatterValue {
+    fn as_scalar(&self) -> Option<&str> {
+        match self {
+            Self::Scalar(value) => Some(value),
+            Self::List(_) => None,
+        }
+    }
+
+    fn to_list(&self) -> Vec<String> {
+        match self {
+            Self::Scalar(value) => value
+                .split(',')
+                .map(|part| part.trim().to_string())
+                .filter(|part| !part.is_empty())
+                .collect(),
+            Self::List(values) => values.clone(),
+        }
+    }
+}
+
+type FrontMatter = HashMap<String, FrontMatterValue>;
+
+fn unquote_yaml(raw: &str) -> String {
+    let raw = raw.trim();
+
+    if let Some(inner) = raw.strip_prefix('"').and_then(|r| r.strip_suffix('"')) {
+        let mut out = String::with_capacity(inner.len());
+        let mut chars = inner.chars();
+        while let Some(ch) = chars.next() {
+            if ch != '\\' {
+                out.push(ch);
+                continue;
+            }
+            match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            }
+        }
+        return out;
+    }
+
+    if let Some(inner) = raw.strip_prefix('\'').and_then(|r| r.strip_suffix('\'')) {
+        return inner.replace("''", "'");
+    }
+
+    raw.to_string()
+}
+
+fn split_flow_list(inner: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut quote = None;
+
+    for ch in inner.chars() {
+        match (quote, ch) {
+            (None, '"' | '\'') => {
+                quote = Some(ch);
+                current.push(ch);
+            }
+            (Some(open), _) if ch == open => {
+                quote = None;
+                current.push(ch);
+            }
+            (None, ',') => items.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
+    }
+    items.push(current);
+
+    items
+        .iter()
+        .map(|item| unquote_yaml(item))
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+// Only the flat subset of YAML that note front matter uses is understood:
+// `key: value`, `key: [a, b]` and block lists of `- item` lines.
+fn parse_yaml(raw: &str) -> FrontMatter {
+    let mut map = FrontMatter::new();
+    let mut list_key: Option<String> = None;
+
+    for line in raw.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let (Some(key), Some(item)) = (&list_key, trimmed.strip_prefix("- ")) {
+            if let Some(FrontMatterValue::List(items)) = map.get_mut(key) {
+                items.push(unquote_yaml(item));
+            }
+            continue;
+        }
+
+        let Some((key, value)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        if value.is_empty() {
+            map.insert(key.clone(), FrontMatterValue::List(Vec::new()));
+            list_key = Some(key);
+        } else if let Some(inner) = value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            map.insert(key, FrontMatterValue::List(split_flow_list(inner)));
+            list_key = None;
+        } else {
+            map.insert(key, FrontMatterValue::Scalar(unquote_yaml(value)));
+            list_key = None;
+        }
+    }
+
+    map
+}
+
+fn toml_scalar(value: &toml::Value) -> String {
+    match value {
+        toml::Value::String(text) => text.clone(),
+        toml::Value::Datetime(datetime) => datetime.to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn parse_toml(raw: &str) -> Result<FrontMatter> {
+    let table = raw
+        .parse::<toml::Table>()
+        .context("invalid TOML front matter")?;
+
+    Ok(table
+        .into_iter()
+        .map(|(key, value)| {
+            let value = match &value {
+                toml::Value::Array(items) => {
+                    FrontMatterValue::List(items.iter().map(toml_scalar).collect())
+                }
+                other => FrontMatterValue::Scalar(toml_scalar(other)),
+            };
+            (key.to_lowercase(), value)
+        })
+        .collect())
+}
+
+/// Splits a document into its front matter (YAML between `---` fences or
+/// TOML between `+++` fences) and body.
+fn split_front_matter(document: &str) -> Result<(FrontMatter, &str)> {
+    let document = document.strip_prefix('\u{feff}').unwrap_or(document);
+
+    for (fence, is_toml) in [("---", false), ("+++", true)] {
+        let Some(after_open) = document.strip_prefix(fence).and_then(|rest| {
+            rest.strip_prefix('\n')
+                .or_else(|| rest.strip_prefix("\r\n"))
+        }) else {
+            continue;
+        };
+
+        let mut offset = 0;
+        for line in after_open.split_inclusive('\n') {
+            if line.trim_end() == fence {
+                let raw = &after_open[..offset];
+                let body = &after_open[offset + line.len()..];
+                let front_matter = if is_toml {
+                    parse_toml(raw)?
+                } else {
+                    parse_yaml(raw)
+                };
+                return Ok((front_matter, body));
+            }
+            offset += line.len();
+        }
+    }
+
+    Ok((FrontMatter::new(), document))
+}
+
+fn first_timestamp(front_matter: &FrontMatter, keys: &[&str]) -> Option<i64> {
+    keys.iter()
+        .filter_map(|key| front_matter.get(*key))
+        .filter_map(FrontMatterValue::as_scalar)
+        .find_map(|value| {
+            dates::parse_timestamp(value).filter(|secs| (0..=db::MAX_TIMESTAMP).contains(secs))
+        })
+}
+
+fn file_modified(path: &Path) -> Option<i64> {
+    let modified = fs::metadata(path).and_then(|meta| meta.modified()).ok()?;
+    let secs = modified.duration_since(UNIX_EPOCH).ok()?.as_secs();
+    i64::try_from(secs).ok()
+}
+
+fn parse_note(path: &Path) -> Result<Option<NoteRecord>> {
+    let raw = fs::read_to_string(path).context("failed to read file")?;
+    let (front_matter, body) = split_front_matter(&raw)?;
+
+    let content = body.trim().to_string();
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    let id = front_matter
+        .get("id")
+        .and_then(FrontMatterValue::as_scalar)
+        .and_then(|id| Uuid::parse_str(id.trim()).ok())
+        .unwrap_or_else(Uuid::new_v4)
+        .to_string();
+
+    let modified = file_modified(path);
+    let created_at = first_timestamp(&front_matter, &["created_at", "created", "date"])
+        .or(modified)
+        .unwrap_or(0);
+    let updated_at = first_timestamp(&front_matter, &["updated_at", "updated", "modified"])
+        .or(modified)
+        .unwrap_or(created_at)
+        .max(created_at);
+
+    let pinned = front_matter
+        .get("pinned")
+        .and_then(FrontMatterValue::as_scalar)
+        .is_some_and(|value| matches!(value.trim(), "true" | "yes" | "1"));
+
+    let mut tags = front_matter
+        .get("tags")
+        .map(FrontMatterValue::to_list)
+        .unwrap_or_default();
+    tags.extend(hashtags::extract(&content));
+
+    Ok(Some(NoteRecord {
+        id,
+        content,
+        created_at,
+        updated_at,
+        pinned,
+        tags,
+    }))
+}
+
+// Symlinked directories are not followed, so a link back to a parent cannot
+// make the walk loop; symlinked files are read like any other.
+fn collect_markdown_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+    let entries = fs::read_dir(dir).with_context(|| format!("failed to read {}", dir.display()))?;
+
+    for entry in entries {
+        let entry = entry.context("failed to read directory entry")?;
+        let file_type = entry
+            .file_type()
+            .context("failed to read directory entry type")?;
+        let path = entry.path();
+        let hidden = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.starts_with('.'));
+
+        if hidden {
+            continue;
+        }
+
+        if file_type.is_dir() {
+            collect_markdown_files(&path, files)?;
+        } else if path.is_file()
+            && path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| matches!(ext.to_lowercase().as_str(), "md" | "markdown"))
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Imports every Markdown file under `source` (or `source` itself if it is a
+/// file). Files that cannot be parsed are reported instead of aborting.
+pub fn import_path(conn: &mut Connection, source: &Path) -> Result<ImportReport> {
+    let mut files = Vec::new();
+    if source.is_dir() {
+        collect_markdown_files(source, &mut files)?;
+        files.sort();
+    } else {
+        files.push(source.to_path_buf());
+    }
+
+    let mut report = ImportReport {
+        scanned: files.len(),
+        ..ImportReport::default()
+    };
+
+    let mut records = Vec::with_capacity(files.len());
+    for path in files {
+        match parse_note(&path) {
+            Ok(Some(record)) => records.push(record),
+            Ok(None) => report.skipped += 1,
+            Err(err) => report.failed.push((path, format!("{:#}", err))),
+        }
+    }
+
+    report.imported = db::insert_note_records(conn, &records)?;
+    report.skipped += records.len() - report.imported;
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PageStart;
+    use crate::export::{self, ExportMode};
+    use crate::hashtags::HashtagMode;
+    use crate::query::{self, QueryMode};
+
+    fn temp_dir(label: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("memo-tori-{}-{}", label, Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn old_dates_sort_below_recent_ones() {
+        let dir = temp_dir("import");
+        let notes = [
+            (
+                "old.md",
+                "---\ncreated_at: 1999-12-01\nupdated_at: 1999-12-31\n---\nOld note",
+            ),
+            (
+                "recent.md",
+                "---\ncreated_at: 2026-01-05\nupdated_at: 2026-01-05\n---\nRecent note",
+            ),
+            (
+                "bad.md",
+                "---\ncreated: -5\nupdated: 99999999999\n---\nBad dates",
+            ),
+        ];
+        for (name, text) in notes {
+            fs::write(dir.join(name), text).unwrap();
+        }
+
+        let mut conn = db::open_and_init(Path::new(":memory:")).unwrap();
+        let report = import_path(&mut conn, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(report.imported, 3);
+
+        let all = query::parse("", QueryMode::Simple, 0);
+        let page = db::search_notes_page(&conn, &all, &PageStart::Offset(0), 10).unwrap();
+        let titles: Vec<&str> = page.items.iter().map(|item| item.title.as_str()).collect();
+        // Out-of-range dates fall back to the file's modification time.
+        assert_eq!(titles, ["Bad dates", "Recent note", "Old note"]);
+        assert_eq!(page.items[2].updated_at, 946_598_400);
+    }
+
+    #[test]
+    fn exported_notes_import_once() {
+        let mut source = db::open_and_init(Path::new(":memory:")).unwrap();
+        let first = db::insert_note(
+            &mut source,
+            "Courses\n- [ ] pain \"complet\"",
+            &["maison".to_string()],
+            HashtagMode::Keep,
+        )
+        .unwrap();
+        db::insert_note(&mut source, "Idée #projet/memo", &[], HashtagMode::Keep).unwrap();
+        db::set_pinned(&source, &first, true).unwrap();
+        let records = db::get_note_records(&source, None).unwrap();
+
+        let dir = temp_dir("roundtrip");
+        export::export_notes(&records, &dir, ExportMode::Directory).unwrap();
+
+        let mut target = db::open_and_init(Path::new(":memory:")).unwrap();
+        let report = import_path(&mut target, &dir).unwrap();
+        assert_eq!((report.imported, report.skipped), (2, 0));
+        let again = import_path(&mut target, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((again.imported, again.skipped), (0, 2));
+
+        let mut imported = db::get_note_records(&target, None).unwrap();
+        let mut expected = records;
+        imported.sort_by(|a, b| a.id.cmp(&b.id));
+        expected.sort_by(|a, b| a.id.cmp(&b.id));
+        assert_eq!(imported, expected);
+    }
+
+    #[test]
+    fn symlinked_directories_are_not_followed() {
+        let dir = temp_dir("symlink");
+        fs::create_dir(dir.join("inner")).unwrap();
+        fs::write(dir.join("inner/note.md"), "A note").unwrap();
+        std::os::unix::fs::symlink(&dir, dir.join("inner/loop")).unwrap();
+
+        let mut conn = db::open_and_init(Path::new(":memory:")).unwrap();
+        let report = import_path(&mut conn, &dir).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!((report.scanned, report.imported), (1, 1));
+    }
+}
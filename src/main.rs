@@ -1,20 +1,45 @@
 mod app;
+mod cli;
+mod colors;
 mod config;
+mod dates;
 mod db;
+mod export;
+mod fuzzy;
+mod hashtags;
+mod import;
+mod markdown;
+mod migrations;
+mod note_list;
 mod paths;
+mod query;
+mod reminders;
 mod version;
+mod wikilinks;
+mod worker;
 
 use anyhow::Result;
 
 fn main() -> Result<()> {
-    if std::env::args().any(|arg| arg == "--version") {
-        println!("{}", version::VERSION);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let command = cli::parse(&args)?;
+    if command.as_ref().is_some_and(cli::run_without_database) {
         return Ok(());
     }
 
     let paths = paths::AppPaths::resolve()?;
     let config = config::AppConfig::load_or_create(&paths.config_path)?;
-    let connection = db::open_and_init(&paths.db_path)?;
+    let mut connection = db::open_and_init(&paths.db_path)?;
+    db::set_search_tokenizer(&mut connection, config.search_tokenizer)?;
+
+    if let Some(days) = config.trash_auto_purge_days {
+        db::purge_deleted_older_than(&mut connection, days)?;
+    }
+
+    if let Some(command) = command {
+        return cli::run(command, &mut connection, config.hashtags);
+    }
 
     app::run(config, connection)
 }
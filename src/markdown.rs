@@ -0,0 +1,474 @@
+// Markdown preview for the reader. Notes are stored as plain Markdown; this
+// turns a note into the text shown in preview mode plus the styled ranges the
+// reader draws over it. Only the subset our notes use is understood, anything
+// else is shown as written.
+
+use std::ops::Range;
+
+use crate::wikilinks;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Style {
+    Heading(u8),
+    Bold,
+    Italic,
+    Strikethrough,
+    Code,
+    CodeBlock,
+    Quote,
+    /// A list bullet or number.
+    ListMarker,
+    /// The box of the `- [ ]` item on a line of the source, checked or not.
+    Checkbox {
+        line: usize,
+        checked: bool,
+    },
+    /// The text of a checked `- [x]` item.
+    Done,
+    /// A `[label](url)` or bare URL.
+    Link(String),
+    /// A `[[target]]` link to another note.
+    NoteLink(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    /// Byte range into `Rendered::text`.
+    pub range: Range<usize>,
+    pub style: Style,
+}
+
+/// A `- [ ]` or `- [x]` checklist item.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Task {
+    /// Line of the item in the note, from 0.
+    pub line: usize,
+    pub done: bool,
+    /// The item text without its Markdown.
+    pub text: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Rendered {
+    pub text: String,
+    pub spans: Vec<Span>,
+}
+
+impl Rendered {
+    fn styled(&mut self, start: usize, style: Style) {
+        if start < self.text.len() {
+            self.spans.push(Span {
+                range: start..self.text.len(),
+                style,
+            });
+        }
+    }
+}
+
+pub fn render(source: &str) -> Rendered {
+    let mut out = Rendered::default();
+    let mut first_line = true;
+    let mut code_block: Option<Option<usize>> = None;
+
+    for (index, line) in source.lines().enumerate() {
+        let trimmed = line.trim_start();
+
+        if trimmed.starts_with("```") {
+            match code_block.take() {
+                Some(Some(start)) => out.styled(start, Style::CodeBlock),
+                Some(None) => {}
+                None => code_block = Some(None),
+            }
+            continue;
+        }
+
+        if !first_line {
+            out.text.push('\n');
+        }
+        first_line = false;
+
+        if let Some(start) = code_block.as_mut() {
+            start.get_or_insert(out.text.len());
+            out.text.push_str(line);
+        } else {
+            render_line(&mut out, index, line);
+        }
+    }
+
+    if let Some(Some(start)) = code_block {
+        out.styled(start, Style::CodeBlock);
+    }
+    out
+}
+
+fn render_line(out: &mut Rendered, index: usize, line: &str) {
+    let trimmed = line.trim_start();
+    let start = out.text.len();
+
+    let level = trimmed.chars().take_while(|&ch| ch == '#').count();
+    if (1..=6).contains(&level) {
+        let text = &trimmed[level..];
+        if text.is_empty() || text.starts_with(' ') {
+            render_inline(out, text.trim());
+            out.styled(start, Style::Heading(level as u8));
+            return;
+        }
+    }
+
+    if let Some(quoted) = trimmed.strip_prefix('>') {
+        let quoted = quoted.trim_start_matches(['>', ' ']);
+        render_inline(out, quoted);
+        out.styled(start, Style::Quote);
+        return;
+    }
+
+    if let Some((marker, item)) = list_item(trimmed) {
+        out.text.push_str(&line[..line.len() - trimmed.len()]);
+
+        let (checked, item) = match checkbox(item) {
+            Some((checked, item)) => (Some(checked), item),
+            None => (None, item),
+        };
+
+        let marker_start = out.text.len();
+        match checked {
+            Some(checked) => {
+                out.text.push(if checked { '☑' } else { '☐' });
+                out.styled(
+                    marker_start,
+                    Style::Checkbox {
+                        line: index,
+                        checked,
+                    },
+                );
+            }
+            None => {
+                out.text.push_str(if marker.ends_with(['.', ')']) {
+                    marker
+                } else {
+                    "•"
+                });
+                out.styled(marker_start, Style::ListMarker);
+            }
+        }
+        out.text.push(' ');
+
+        let item_start = out.text.len();
+        render_inline(out, item.trim_start());
+        if checked == Some(true) {
+            out.styled(item_start, Style::Done);
+        }
+        return;
+    }
+
+    render_inline(out, line);
+}
+
+// The marker (`-`, `*`, `+`, `1.` or `1)`) and text of a list item line.
+fn list_item(line: &str) -> Option<(&str, &str)> {
+    let digits = line.chars().take_while(char::is_ascii_digit).count();
+    let marker_len = if digits > 0 {
+        line[digits..]
+            .starts_with(['.', ')'])
+            .then_some(digits + 1)?
+    } else {
+        line.starts_with(['-', '*', '+']).then_some(1)?
+    };
+
+    let (marker, item) = line.split_at(marker_len);
+    if item.is_empty() {
+        Some((marker, item))
+    } else {
+        Some((marker, item.strip_prefix(' ')?))
+    }
+}
+
+// Whether a list item is a checklist item, checked or not, and its text.
+fn checkbox(item: &str) -> Option<(bool, &str)> {
+    let checked = match item.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
+    };
+    let text = &item[3..];
+    (text.is_empty() || text.starts_with(' ')).then_some((checked, text.trim_start()))
+}
+
+/// The checklist items of `content`, outside code blocks.
+pub fn tasks(content: &str) -> Vec<Task> {
+    let mut tasks = Vec::new();
+    let mut in_code_block = false;
+
+    for (line, text) in content.lines().enumerate() {
+        let trimmed = text.trim_start();
+        if trimmed.starts_with("```") {
+            in_code_block = !in_code_block;
+            continue;
+        }
+        if in_code_block {
+            continue;
+        }
+
+        let Some((done, item)) = list_item(trimmed).and_then(|(_, item)| checkbox(item)) else {
+            continue;
+        };
+        let mut plain = Rendered::default();
+        render_inline(&mut plain, item);
+        tasks.push(Task {
+            line,
+            done,
+            text: plain.text,
+        });
+    }
+
+    tasks
+}
+
+/// `content` with the checklist item on `line` checked or unchecked, or
+/// `None` if that line holds no checklist item.
+pub fn set_task_done(content: &str, line: usize, done: bool) -> Option<String> {
+    tasks(content).iter().find(|task| task.line == line)?;
+
+    let mut updated = String::with_capacity(content.len());
+    for (index, text) in content.split_inclusive('\n').enumerate() {
+        if index != line {
+            updated.push_str(text);
+            continue;
+        }
+        let open = text.find('[')?;
+        updated.push_str(&text[..open]);
+        updated.push_str(if done { "[x]" } else { "[ ]" });
+        updated.push_str(&text[open + 3..]);
+    }
+    Some(updated)
+}
+
+fn render_inline(out: &mut Rendered, text: &str) {
+    let mut index = 0;
+    let mut previous = None;
+
+    while let Some(ch) = text[index..].chars().next() {
+        let rest = &text[index..];
+        let after_word = previous.is_some_and(char::is_alphanumeric);
+
+        if let Some(len) = inline_element(out, rest, after_word) {
+            index += len;
+            previous = text[..index].chars().next_back();
+            continue;
+        }
+
+        if ch == '\\' {
+            if let Some(escaped) = rest[1..].chars().next().filter(char::is_ascii_punctuation) {
+                out.text.push(escaped);
+                index += 1 + escaped.len_utf8();
+                previous = Some(escaped);
+                continue;
+            }
+        }
+
+        out.text.push(ch);
+        index += ch.len_utf8();
+        previous = Some(ch);
+    }
+}
+
+// Renders the inline element `rest` starts with, if any, and returns how many
+// bytes of `rest` it used.
+fn inline_element(out: &mut Rendered, rest: &str, after_word: bool) -> Option<usize> {
+    let start = out.text.len();
+
+    if let Some(inner) = rest.strip_prefix('`') {
+        let end = inner.find('`').filter(|&end| end > 0)?;
+        out.text.push_str(&inner[..end]);
+        out.styled(start, Style::Code);
+        return Some(end + 2);
+    }
+
+    if rest.starts_with("[[") {
+        let (range, target) = wikilinks::find(rest)
+            .into_iter()
+            .next()
+            .filter(|(range, _)| range.start == 0)?;
+        out.text.push_str(&target);
+        out.styled(start, Style::NoteLink(target));
+        return Some(range.end);
+    }
+
+    if let Some(inner) = rest.strip_prefix('[') {
+        let label_end = inner.find(']')?;
+        let target = inner[label_end + 1..].strip_prefix('(')?;
+        let target_end = target.find(')')?;
+        let url = target[..target_end].split_whitespace().next()?;
+
+        let label = &inner[..label_end];
+        if label.trim().is_empty() {
+            out.text.push_str(url);
+        } else {
+            render_inline(out, label);
+        }
+        out.styled(start, Style::Link(url.to_string()));
+        return Some(1 + label_end + 2 + target_end + 1);
+    }
+
+    for (delimiter, style) in [
+        ("**", Style::Bold),
+        ("__", Style::Bold),
+        ("~~", Style::Strikethrough),
+        ("*", Style::Italic),
+        ("_", Style::Italic),
+    ] {
+        if !rest.starts_with(delimiter) || (delimiter.starts_with('_') && after_word) {
+            continue;
+        }
+        let inner = &rest[delimiter.len()..];
+        let Some(end) = inner.find(delimiter) else {
+            continue;
+        };
+        let emphasized = &inner[..end];
+        let closes_word = inner[end + delimiter.len()..]
+            .chars()
+            .next()
+            .is_some_and(char::is_alphanumeric);
+        if emphasized.is_empty()
+            || emphasized.starts_with(char::is_whitespace)
+            || emphasized.ends_with(char::is_whitespace)
+            || (delimiter.starts_with('_') && closes_word)
+        {
+            continue;
+        }
+
+        render_inline(out, emphasized);
+        out.styled(start, style);
+        return Some(delimiter.len() * 2 + end);
+    }
+
+    let url = rest.strip_prefix('<').unwrap_or(rest);
+    if !after_word && (url.starts_with("https://") || url.starts_with("http://")) {
+        if rest.starts_with('<') {
+            let end = url.find('>')?;
+            out.text.push_str(&url[..end]);
+            out.styled(start, Style::Link(url[..end].to_string()));
+            return Some(end + 2);
+        }
+
+        let end = url.find(char::is_whitespace).unwrap_or(url.len());
+        let url = url[..end].trim_end_matches(['.', ',', ';', ':', '!', '?', ')']);
+        out.text.push_str(url);
+        out.styled(start, Style::Link(url.to_string()));
+        return Some(url.len());
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn styled(rendered: &Rendered) -> Vec<(&str, Style)> {
+        rendered
+            .spans
+            .iter()
+            .map(|span| (&rendered.text[span.range.clone()], span.style.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn renders_headings_and_emphasis() {
+        let rendered = render("# Budget *2026*\nUn **gros** mot, `code` et snake_case_name.");
+        assert_eq!(
+            rendered.text,
+            "Budget 2026\nUn gros mot, code et snake_case_name."
+        );
+        assert_eq!(
+            styled(&rendered),
+            vec![
+                ("2026", Style::Italic),
+                ("Budget 2026", Style::Heading(1)),
+                ("gros", Style::Bold),
+                ("code", Style::Code),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_lists_checkboxes_and_quotes() {
+        let rendered = render("- lait\n  2. pain\n- [ ] appeler\n- [x] payer\n> cite");
+        assert_eq!(rendered.text, "• lait\n  2. pain\n☐ appeler\n☑ payer\ncite");
+        assert_eq!(
+            styled(&rendered),
+            vec![
+                ("•", Style::ListMarker),
+                ("2.", Style::ListMarker),
+                (
+                    "☐",
+                    Style::Checkbox {
+                        line: 2,
+                        checked: false
+                    }
+                ),
+                (
+                    "☑",
+                    Style::Checkbox {
+                        line: 3,
+                        checked: true
+                    }
+                ),
+                ("payer", Style::Done),
+                ("cite", Style::Quote),
+            ]
+        );
+    }
+
+    #[test]
+    fn renders_code_blocks_and_links() {
+        let rendered = render(
+            "Voir [le site](https://example.org) et [[Budget]].\n```\n**brut**\n```\nhttps://a.b/c.",
+        );
+        assert_eq!(
+            rendered.text,
+            "Voir le site et Budget.\n**brut**\nhttps://a.b/c."
+        );
+        assert_eq!(
+            styled(&rendered),
+            vec![
+                ("le site", Style::Link("https://example.org".to_string())),
+                ("Budget", Style::NoteLink("Budget".to_string())),
+                ("**brut**", Style::CodeBlock),
+                ("https://a.b/c", Style::Link("https://a.b/c".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_and_toggles_tasks() {
+        let content = "Je ne dois pas oublier :\r\n- [ ] appeler **Paul**\n  * [x] payer\n```\n- [ ] code\n```\n- [y] non\n";
+        assert_eq!(
+            tasks(content),
+            vec![
+                Task {
+                    line: 1,
+                    done: false,
+                    text: "appeler Paul".to_string()
+                },
+                Task {
+                    line: 2,
+                    done: true,
+                    text: "payer".to_string()
+                },
+            ]
+        );
+
+        let checked = set_task_done(content, 1, true).unwrap();
+        assert_eq!(
+            checked,
+            content.replacen("- [ ] appeler", "- [x] appeler", 1)
+        );
+        assert_eq!(
+            set_task_done(&checked, 2, false).unwrap(),
+            checked.replacen("* [x] payer", "* [ ] payer", 1)
+        );
+        assert_eq!(set_task_done(content, 0, true), None);
+        assert_eq!(set_task_done(content, 4, true), None);
+    }
+}
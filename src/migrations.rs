@@ -0,0 +1,289 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use rusqlite::{params, Connection};
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
+
+// Append new files here in order; the version is the numeric prefix of the
+// file name and becomes `PRAGMA user_version` once applied.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "001_init",
+        sql: include_str!("../migrations/001_init.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "002_note_revisions",
+        sql: include_str!("../migrations/002_note_revisions.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "003_note_list_order",
+        sql: include_str!("../migrations/003_note_list_order.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "004_note_summaries",
+        sql: include_str!("../migrations/004_note_summaries.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "005_fts_fold_diacritics",
+        sql: include_str!("../migrations/005_fts_fold_diacritics.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "006_notes_trigram",
+        sql: include_str!("../migrations/006_notes_trigram.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "007_saved_searches",
+        sql: include_str!("../migrations/007_saved_searches.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "008_tag_style",
+        sql: include_str!("../migrations/008_tag_style.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "009_note_links",
+        sql: include_str!("../migrations/009_note_links.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "010_note_tasks",
+        sql: include_str!("../migrations/010_note_tasks.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "011_reminders",
+        sql: include_str!("../migrations/011_reminders.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "012_fixed_width_timestamps",
+        sql: include_str!("../migrations/012_fixed_width_timestamps.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "013_note_content_hash",
+        sql: include_str!("../migrations/013_note_content_hash.sql"),
+    },
+];
+
+fn latest_version(migrations: &[Migration]) -> i64 {
+    migrations.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn schema_version(conn: &Connection) -> Result<i64> {
+    conn.pragma_query_value(None, "user_version", |row| row.get(0))
+        .context("failed to read schema version")
+}
+
+fn has_user_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master
+             WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+            [],
+            |row| row.get(0),
+        )
+        .context("failed to inspect existing schema")?;
+    Ok(count > 0)
+}
+
+fn backup_path(db_path: &Path, version: i64) -> PathBuf {
+    let mut name = db_path
+        .file_name()
+        .map(|name| name.to_os_string())
+        .unwrap_or_else(|| "memo-tori.db".into());
+    name.push(format!(".v{}.bak", version));
+    db_path.with_file_name(name)
+}
+
+fn backup_database(conn: &Connection, db_path: &Path, version: i64) -> Result<PathBuf> {
+    let target = backup_path(db_path, version);
+    if target.exists() {
+        fs::remove_file(&target).context("failed to replace previous database backup")?;
+    }
+
+    conn.execute("VACUUM INTO ?1", params![target.to_string_lossy()])
+        .context("failed to back up database before migration")?;
+
+    Ok(target)
+}
+
+pub fn run(conn: &mut Connection, db_path: &Path) -> Result<()> {
+    apply(conn, db_path, MIGRATIONS)
+}
+
+fn apply(conn: &mut Connection, db_path: &Path, migrations: &[Migration]) -> Result<()> {
+    let current = schema_version(conn)?;
+    let latest = latest_version(migrations);
+
+    if current > latest {
+        bail!(
+            "database schema version {} is newer than this build supports ({}); \
+             please upgrade memo-tori-gtk",
+            current,
+            latest
+        );
+    }
+
+    if current == latest {
+        return Ok(());
+    }
+
+    if current > 0 || has_user_tables(conn)? {
+        backup_database(conn, db_path, current)?;
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current) {
+        let tx = conn
+            .transaction()
+            .context("failed to start migration transaction")?;
+
+        tx.execute_batch(migration.sql)
+            .with_context(|| format!("failed to apply migration {}", migration.name))?;
+
+        tx.pragma_update(None, "user_version", migration.version)
+            .context("failed to record schema version")?;
+
+        tx.commit()
+            .with_context(|| format!("failed to commit migration {}", migration.name))?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_db() -> PathBuf {
+        std::env::temp_dir().join(format!("memo-tori-migrations-{}.db", uuid::Uuid::new_v4()))
+    }
+
+    fn table_exists(conn: &Connection, name: &str) -> bool {
+        conn.query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            params![name],
+            |row| row.get::<_, i64>(0),
+        )
+        .unwrap()
+            > 0
+    }
+
+    #[test]
+    fn applies_every_migration_from_an_empty_database() {
+        let path = temp_db();
+        let mut conn = Connection::open(&path).unwrap();
+        run(&mut conn, &path).unwrap();
+
+        assert_eq!(schema_version(&conn).unwrap(), latest_version(MIGRATIONS));
+        assert!(table_exists(&conn, "notes"));
+        assert!(table_exists(&conn, "reminders"));
+        // A new database has nothing worth backing up.
+        assert!(!backup_path(&path, 0).exists());
+
+        drop(conn);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn versions_follow_the_file_order() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1);
+            assert!(migration
+                .name
+                .starts_with(&format!("{:03}_", migration.version)));
+        }
+    }
+
+    #[test]
+    fn backs_up_before_upgrading() {
+        let path = temp_db();
+        let mut conn = Connection::open(&path).unwrap();
+        apply(&mut conn, &path, &MIGRATIONS[..2]).unwrap();
+        conn.execute(
+            "INSERT INTO notes (id, content, created_at, updated_at) VALUES ('a', 'Avant', 1, 1)",
+            [],
+        )
+        .unwrap();
+
+        run(&mut conn, &path).unwrap();
+        assert_eq!(schema_version(&conn).unwrap(), latest_version(MIGRATIONS));
+
+        let backup = backup_path(&path, 2);
+        assert_eq!(
+            backup.file_name().unwrap().to_string_lossy(),
+            format!("{}.v2.bak", path.file_name().unwrap().to_string_lossy())
+        );
+        let saved = Connection::open(&backup).unwrap();
+        assert_eq!(schema_version(&saved).unwrap(), 2);
+        let content: String = saved
+            .query_row("SELECT content FROM notes WHERE id = 'a'", [], |row| {
+                row.get(0)
+            })
+            .unwrap();
+        assert_eq!(content, "Avant");
+
+        drop((conn, saved));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&backup).unwrap();
+    }
+
+    #[test]
+    fn refuses_a_newer_database() {
+        let path = temp_db();
+        let mut conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", latest_version(MIGRATIONS) + 1)
+            .unwrap();
+
+        let err = run(&mut conn, &path).unwrap_err();
+        assert!(err.to_string().contains("newer than this build supports"));
+        assert_eq!(
+            schema_version(&conn).unwrap(),
+            latest_version(MIGRATIONS) + 1
+        );
+
+        drop(conn);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn failed_migration_is_rolled_back() {
+        let migrations = [
+            Migration {
+                version: 1,
+                name: "001_first",
+                sql: "CREATE TABLE first (id INTEGER);",
+            },
+            Migration {
+                version: 2,
+                name: "002_broken",
+                sql: "CREATE TABLE second (id INTEGER); INSERT INTO missing VALUES (1);",
+            },
+        ];
+        let path = temp_db();
+        let mut conn = Connection::open(&path).unwrap();
+
+        let err = apply(&mut conn, &path, &migrations).unwrap_err();
+        assert!(format!("{:#}", err).contains("002_broken"));
+        assert_eq!(schema_version(&conn).unwrap(), 1);
+        assert!(table_exists(&conn, "first"));
+        assert!(!table_exists(&conn, "second"));
+
+        drop(conn);
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,397 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use gtk::gio;
+use gtk::glib;
+use gtk::prelude::*;
+use gtk::subclass::prelude::*;
+use rusqlite::Connection;
+
+use crate::db::{self, NoteCursor, NotePage, NoteSummary, PageStart};
+use crate::query::SearchQuery;
+use crate::worker::{DbWorker, LatestRequest};
+
+const PAGE_SIZE: u32 = 100;
+// Pages kept in memory; older ones are fetched again when scrolled back to.
+const CACHED_PAGES: usize = 8;
+// Wait before asking the view to fetch a page that failed to load again.
+const PAGE_RETRY_DELAY: Duration = Duration::from_secs(2);
+
+mod imp {
+    use super::*;
+    use glib::subclass::Signal;
+
+    #[derive(Default)]
+    pub struct NoteObject {
+        pub item: RefCell<Option<NoteSummary>>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NoteObject {
+        const NAME: &'static str = "MemoToriNoteObject";
+        type Type = super::NoteObject;
+    }
+
+    impl ObjectImpl for NoteObject {}
+
+    #[derive(Default)]
+    pub struct NoteListModel {
+        pub worker: RefCell<Option<DbWorker>>,
+        pub reload_request: LatestRequest,
+        pub state: RefCell<PageState>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for NoteListModel {
+        const NAME: &'static str = "MemoToriNoteListModel";
+        type Type = super::NoteListModel;
+        type Interfaces = (gio::ListModel,);
+    }
+
+    impl ObjectImpl for NoteListModel {
+        fn signals() -> &'static [Signal] {
+            static SIGNALS: OnceLock<Vec<Signal>> = OnceLock::new();
+            SIGNALS.get_or_init(|| vec![Signal::builder("stale").build()])
+        }
+    }
+
+    impl ListModelImpl for NoteListModel {
+        fn item_type(&self) -> glib::Type {
+            super::NoteObject::static_type()
+        }
+
+        fn n_items(&self) -> u32 {
+            self.state.borrow().total
+        }
+
+        fn item(&self, position: u32) -> Option<glib::Object> {
+            self.obj().note_at(position).map(Cast::upcast)
+        }
+    }
+}
+
+glib::wrapper! {
+    pub struct NoteObject(ObjectSubclass<imp::NoteObject>);
+}
+
+impl NoteObject {
+    fn new(item: Option<NoteSummary>) -> Self {
+        let object: Self = glib::Object::new();
+        *object.imp().item.borrow_mut() = item;
+        object
+    }
+
+    /// `None` while the page holding this row is still being fetched.
+    pub fn item(&self) -> Option<NoteSummary> {
+        self.imp().item.borrow().clone()
+    }
+}
+
+#[derive(Default)]
+pub struct PageState {
+    query: SearchQuery,
+    total: u32,
+    generation: u64,
+    /// Ids of a fuzzy search in rank order, scored once per reload.
+    ranking: Option<Arc<Vec<String>>>,
+    pages: HashMap<u32, Vec<NoteObject>>,
+    recent: VecDeque<u32>,
+    cursors: HashMap<u32, NoteCursor>,
+    loading: HashSet<u32>,
+    live: HashMap<String, glib::WeakRef<NoteObject>>,
+}
+
+impl PageState {
+    // Reuses the object already handed out for a note so that a selection
+    // holding it survives its page being evicted and fetched again.
+    fn object_for(&mut self, item: NoteSummary) -> NoteObject {
+        if let Some(object) = self.live.get(&item.id).and_then(|weak| weak.upgrade()) {
+            *object.imp().item.borrow_mut() = Some(item);
+            return object;
+        }
+
+        let id = item.id.clone();
+        let object = NoteObject::new(Some(item));
+        self.live.insert(id, object.downgrade());
+        object
+    }
+}
+
+// Number of notes matching `query`. A fuzzy search is ranked here once, and
+// its pages are then cut from the ranking.
+fn count(conn: &Connection, query: &SearchQuery) -> Result<(i64, Option<Arc<Vec<String>>>)> {
+    if db::is_fuzzy(query) {
+        let ranking = db::fuzzy_ranking(conn, query)?;
+        return Ok((ranking.len() as i64, Some(Arc::new(ranking))));
+    }
+    Ok((db::count_notes(conn, query)?, None))
+}
+
+fn fetch_page(
+    conn: &Connection,
+    query: &SearchQuery,
+    ranking: Option<&Vec<String>>,
+    page: u32,
+    start: &PageStart,
+) -> Result<NotePage> {
+    let Some(ranking) = ranking else {
+        return db::search_notes_page(conn, query, start, i64::from(PAGE_SIZE));
+    };
+
+    let from = ranking.len().min((page * PAGE_SIZE) as usize);
+    let to = ranking.len().min(from + PAGE_SIZE as usize);
+    Ok(NotePage {
+        items: db::fuzzy_notes(conn, query, &ranking[from..to])?,
+        next: None,
+    })
+}
+
+glib::wrapper! {
+    /// Library list model. Only the total is known up front; rows are fetched
+    /// from SQLite a page at a time as the view asks for them, and only the most
+    /// recently used pages are kept.
+    pub struct NoteListModel(ObjectSubclass<imp::NoteListModel>)
+        @implements gio::ListModel;
+}
+
+impl NoteListModel {
+    pub fn new(worker: DbWorker) -> Self {
+        let model: Self = glib::Object::new();
+        *model.imp().worker.borrow_mut() = Some(worker);
+        model
+    }
+
+    fn worker(&self) -> Result<DbWorker> {
+        self.imp()
+            .worker
+            .borrow()
+            .clone()
+            .ok_or_else(|| anyhow!("note list has no database worker"))
+    }
+
+    /// Calls `f` when a page holds fewer notes than the total announced, as
+    /// when notes were deleted since the list was counted. The list should
+    /// then be reloaded, or those rows would stay empty.
+    pub fn connect_stale<F: Fn(&Self) + 'static>(&self, f: F) {
+        self.connect_local("stale", false, move |values| {
+            if let Some(model) = values.first().and_then(|value| value.get::<Self>().ok()) {
+                f(&model);
+            }
+            None
+        });
+    }
+
+    /// Search currently listed.
+    pub fn filter(&self) -> SearchQuery {
+        self.imp().state.borrow().query.clone()
+    }
+
+    /// Replaces the contents with the notes matching `query` and resolves to
+    /// the new total, or to `None` if a newer reload superseded it. A simple
+    /// search that finds nothing is retried in fuzzy mode, which `filter()`
+    /// then reports.
+    pub async fn reload(&self, query: SearchQuery) -> Option<Result<u32>> {
+        let worker = match self.worker() {
+            Ok(worker) => worker,
+            Err(err) => return Some(Err(err)),
+        };
+
+        let request = worker.call_latest(&self.imp().reload_request, move |conn| {
+            let mut query = query;
+            let (mut total, mut ranking) = count(conn, &query)?;
+            if total == 0 {
+                if let Some(fuzzy) = query.fallback() {
+                    (total, ranking) = count(conn, &fuzzy)?;
+                    query = fuzzy;
+                }
+            }
+
+            let first = fetch_page(conn, &query, ranking.as_deref(), 0, &PageStart::Offset(0))?;
+            Ok((query, total, ranking, first))
+        });
+
+        let (query, total, ranking, first) = match request.await? {
+            Ok(result) => result,
+            Err(err) => return Some(Err(err)),
+        };
+        let total = u32::try_from(total).unwrap_or(u32::MAX);
+
+        let removed = {
+            let mut state = self.imp().state.borrow_mut();
+            let removed = state.total;
+            state.query = query;
+            state.total = total;
+            state.ranking = ranking;
+            state.generation += 1;
+            state.pages.clear();
+            state.recent.clear();
+            state.cursors.clear();
+            state.loading.clear();
+            removed
+        };
+
+        self.store_page(0, first);
+        // Removing and re-adding in two steps keeps a selection model from
+        // scanning (and so fetching) every new row looking for its old item.
+        self.items_changed(0, removed, 0);
+        self.items_changed(0, 0, total);
+        Some(Ok(total))
+    }
+
+    /// Position of `note_id` if the page holding it is currently loaded, or
+    /// anywhere in a fuzzy search's ranking.
+    pub fn position_of(&self, note_id: &str) -> Option<u32> {
+        let state = self.imp().state.borrow();
+        if let Some(ranking) = &state.ranking {
+            let index = ranking.iter().position(|id| id == note_id)?;
+            return u32::try_from(index).ok();
+        }
+        state.pages.iter().find_map(|(page, objects)| {
+            objects
+                .iter()
+                .position(|object| {
+                    object
+                        .imp()
+                        .item
+                        .borrow()
+                        .as_ref()
+                        .is_some_and(|item| item.id == note_id)
+                })
+                .map(|index| page * PAGE_SIZE + index as u32)
+        })
+    }
+
+    fn note_at(&self, position: u32) -> Option<NoteObject> {
+        let page = position / PAGE_SIZE;
+
+        {
+            let mut state = self.imp().state.borrow_mut();
+            if position >= state.total {
+                return None;
+            }
+            if let Some(objects) = state.pages.get(&page) {
+                let index = (position % PAGE_SIZE) as usize;
+                let object = objects
+                    .get(index)
+                    .cloned()
+                    .unwrap_or_else(|| NoteObject::new(None));
+                state.recent.retain(|cached| *cached != page);
+                state.recent.push_back(page);
+                return Some(object);
+            }
+        }
+
+        self.load_page(page);
+        Some(NoteObject::new(None))
+    }
+
+    fn store_page(&self, page: u32, notes: NotePage) -> u32 {
+        let mut state = self.imp().state.borrow_mut();
+
+        let objects: Vec<NoteObject> = notes
+            .items
+            .into_iter()
+            .map(|item| state.object_for(item))
+            .collect();
+        let len = objects.len() as u32;
+
+        if let Some(cursor) = notes.next {
+            state.cursors.insert(page, cursor);
+        }
+        state.pages.insert(page, objects);
+        state.loading.remove(&page);
+        state.recent.retain(|cached| *cached != page);
+        state.recent.push_back(page);
+
+        while state.recent.len() > CACHED_PAGES {
+            if let Some(evicted) = state.recent.pop_front() {
+                state.pages.remove(&evicted);
+            }
+        }
+        state.live.retain(|_, object| object.upgrade().is_some());
+
+        len
+    }
+
+    fn load_page(&self, page: u32) {
+        let Ok(worker) = self.worker() else {
+            return;
+        };
+
+        let (query, ranking, start, generation) = {
+            let mut state = self.imp().state.borrow_mut();
+            if !state.loading.insert(page) {
+                return;
+            }
+
+            // Continue from the previous page's last row when it is known;
+            // jumps further ahead (dragging the scrollbar) fall back to OFFSET.
+            let start = match page
+                .checked_sub(1)
+                .and_then(|prev| state.cursors.get(&prev))
+            {
+                Some(cursor) => PageStart::After(cursor.clone()),
+                None => PageStart::Offset(i64::from(page * PAGE_SIZE)),
+            };
+            (
+                state.query.clone(),
+                state.ranking.clone(),
+                start,
+                state.generation,
+            )
+        };
+
+        let request =
+            worker.call(move |conn| fetch_page(conn, &query, ranking.as_deref(), page, &start));
+
+        let model = self.downgrade();
+        glib::spawn_future_local(async move {
+            let result = request.await;
+            let Some(model) = model.upgrade() else {
+                return;
+            };
+            if model.imp().state.borrow().generation != generation {
+                return;
+            }
+
+            let expected = model.page_len(page);
+            match result {
+                Ok(notes) => {
+                    let len = model.store_page(page, notes);
+                    model.items_changed(page * PAGE_SIZE, len, len);
+                    if len < expected {
+                        model.emit_by_name::<()>("stale", &[]);
+                    }
+                }
+                Err(_) => {
+                    model.imp().state.borrow_mut().loading.remove(&page);
+                    model.retry_page(page, expected, generation);
+                }
+            }
+        });
+    }
+
+    // Rows the page should hold given the current total.
+    fn page_len(&self, page: u32) -> u32 {
+        let total = self.imp().state.borrow().total;
+        total.saturating_sub(page * PAGE_SIZE).min(PAGE_SIZE)
+    }
+
+    // The view does not ask again for rows it was already given, so after a
+    // failed fetch the page is announced as changed, once the pause is over,
+    // for its placeholders to be requested anew.
+    fn retry_page(&self, page: u32, len: u32, generation: u64) {
+        let model = self.downgrade();
+        glib::timeout_add_local_once(PAGE_RETRY_DELAY, move || {
+            let Some(model) = model.upgrade() else {
+                return;
+            };
+            if model.imp().state.borrow().generation == generation {
+                model.items_changed(page * PAGE_SIZE, len, len);
+            }
+        });
+    }
+}
@@ -0,0 +1,619 @@
+// Parses the search language: free text plus `key:value` operators such as
+// `tag:a|b`, `-tag:x`, `created:>2026-01-01`, `updated:<7d`, `is:pinned`,
+// `is:deleted` and `has:link`. The operators become conditions that `db`
+// compiles into SQL; the remaining text becomes an FTS5 MATCH expression.
+// In simple mode every word is quoted, so no input can produce an FTS5
+// syntax error; advanced mode hands the text to FTS5 untouched. Fuzzy mode
+// instead looks the words up in the trigram index and leaves ranking to
+// `fuzzy`.
+
+use crate::dates;
+use crate::db;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueryMode {
+    /// Words are matched literally and the word being typed as a prefix.
+    #[default]
+    Simple,
+    /// Raw FTS5 syntax: `AND`, `OR`, `NOT`, `NEAR()`, `"phrases"`, `prefix*`.
+    Advanced,
+    /// Words are found inside longer words and despite typos.
+    Fuzzy,
+}
+
+impl QueryMode {
+    /// Stable name used when a search is stored.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Simple => "simple",
+            Self::Advanced => "advanced",
+            Self::Fuzzy => "fuzzy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "advanced" => Self::Advanced,
+            "fuzzy" => Self::Fuzzy,
+            _ => Self::Simple,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    /// Notes carrying at least one of the tags.
+    Tag(Vec<String>),
+    /// Created within `[from, until)`, in unix seconds.
+    Created {
+        from: Option<i64>,
+        until: Option<i64>,
+    },
+    /// Last updated within `[from, until)`, in unix seconds.
+    Updated {
+        from: Option<i64>,
+        until: Option<i64>,
+    },
+    Pinned,
+    /// Searches the trash instead of live notes.
+    Deleted,
+    /// Content contains a web link.
+    HasLink,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    pub filter: Filter,
+    /// Written with a leading `-`.
+    pub negated: bool,
+}
+
+/// A parsed search: every condition must hold and, when `text` is not
+/// empty, the note must match it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub mode: QueryMode,
+    /// MATCH expression; empty when only operators were given. In fuzzy mode
+    /// it runs against the trigram index and only selects candidates.
+    pub text: String,
+    /// Folded words of three characters or more from the free text, which
+    /// fuzzy mode compares against the words of each candidate.
+    pub terms: Vec<String>,
+    pub conditions: Vec<Condition>,
+}
+
+impl SearchQuery {
+    /// The same search in fuzzy mode, to try when a simple search finds
+    /// nothing.
+    pub fn fallback(&self) -> Option<SearchQuery> {
+        if self.mode != QueryMode::Simple || self.terms.is_empty() {
+            return None;
+        }
+
+        Some(SearchQuery {
+            mode: QueryMode::Fuzzy,
+            text: trigram_fts(&self.terms),
+            terms: self.terms.clone(),
+            conditions: self.conditions.clone(),
+        })
+    }
+
+    /// Adds one tag condition per tag, so that all of them are required.
+    pub fn require_tags(&mut self, tags: &[String]) {
+        for tag in tags {
+            self.conditions.push(Condition {
+                filter: Filter::Tag(vec![tag.clone()]),
+                negated: false,
+            });
+        }
+    }
+}
+
+// Splits on whitespace outside double quotes; a quoted phrase is one term
+// and an unterminated quote runs to the end of the input. The flag is set
+// when the input ends inside the last term, i.e. it is still being typed.
+fn tokenize(input: &str) -> (Vec<String>, bool) {
+    let mut terms = Vec::new();
+    let mut current = String::new();
+    let mut in_phrase = false;
+    let mut open_at_end = false;
+
+    for ch in input.chars() {
+        open_at_end = true;
+        if ch == '"' {
+            if in_phrase || !current.is_empty() {
+                terms.push(std::mem::take(&mut current));
+            }
+            open_at_end = !in_phrase;
+            in_phrase = !in_phrase;
+        } else if ch.is_whitespace() && !in_phrase {
+            if !current.is_empty() {
+                terms.push(std::mem::take(&mut current));
+            }
+            open_at_end = false;
+        } else {
+            current.push(ch);
+        }
+    }
+
+    if in_phrase || !current.is_empty() {
+        terms.push(current);
+    }
+
+    (terms, open_at_end)
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('"', "\"\""))
+}
+
+// Builds the MATCH expression for free text. An empty result means there is
+// nothing to match on.
+fn to_fts(input: &str, mode: QueryMode) -> String {
+    match mode {
+        QueryMode::Advanced => return input.trim().to_string(),
+        QueryMode::Fuzzy => return trigram_fts(&fuzzy_terms(input)),
+        QueryMode::Simple => {}
+    }
+
+    let (terms, open_at_end) = tokenize(input);
+    let last = terms.len().saturating_sub(1);
+
+    terms
+        .iter()
+        .enumerate()
+        // Terms without a letter or digit hold no token for FTS5 to match
+        // on (`-`, `:`, `()`), so they are dropped rather than searched for.
+        .filter(|(_, term)| term.chars().any(char::is_alphanumeric))
+        .map(|(index, term)| {
+            let typing = open_at_end && index == last && !term.ends_with(char::is_whitespace);
+            if typing {
+                format!("{}*", quote(term))
+            } else {
+                quote(term)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn fuzzy_terms(input: &str) -> Vec<String> {
+    let mut terms: Vec<String> = Vec::new();
+
+    for word in input.split(|ch: char| !ch.is_alphanumeric()) {
+        let term = db::fold_text(word);
+        if term.chars().count() >= 3 && !terms.contains(&term) {
+            terms.push(term);
+        }
+    }
+
+    terms
+}
+
+// Any shared trigram makes a note a candidate; bm25 then favours the notes
+// sharing the most.
+fn trigram_fts(terms: &[String]) -> String {
+    let mut trigrams: Vec<String> = Vec::new();
+
+    for term in terms {
+        let chars: Vec<char> = term.chars().collect();
+        for window in chars.windows(3) {
+            let trigram = quote(&window.iter().collect::<String>());
+            if !trigrams.contains(&trigram) {
+                trigrams.push(trigram);
+            }
+        }
+    }
+
+    trigrams.join(" OR ")
+}
+
+// Like the FTS tokenizer above, but keeps each whitespace-separated chunk
+// as written (quotes included) so operators can be told apart from text.
+fn split_chunks(input: &str) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut start = None;
+    let mut in_quotes = false;
+
+    for (index, ch) in input.char_indices() {
+        if ch == '"' {
+            in_quotes = !in_quotes;
+        }
+        if ch.is_whitespace() && !in_quotes {
+            if let Some(begin) = start.take() {
+                chunks.push(&input[begin..index]);
+            }
+        } else if start.is_none() {
+            start = Some(index);
+        }
+    }
+
+    if let Some(begin) = start {
+        chunks.push(&input[begin..]);
+    }
+
+    chunks
+}
+
+fn relative_age(operand: &str) -> Option<i64> {
+    let unit = operand.chars().last()?;
+    let amount: i64 = operand[..operand.len() - unit.len_utf8()].parse().ok()?;
+    let seconds = match unit.to_ascii_lowercase() {
+        'h' => 3_600,
+        'd' => 86_400,
+        'w' => 7 * 86_400,
+        'm' => 30 * 86_400,
+        'y' => 365 * 86_400,
+        _ => return None,
+    };
+    amount.checked_mul(seconds)
+}
+
+// `>`/`<` compare against a date (`2026-01-01`, a whole UTC day, or an RFC
+// 3339 instant) or against an age (`7d`): `<7d` means less than 7 days old.
+fn date_range(value: &str, now: i64) -> Option<(Option<i64>, Option<i64>)> {
+    let (op, operand) = ["<=", ">=", "<", ">", "="]
+        .iter()
+        .find_map(|op| value.strip_prefix(op).map(|rest| (*op, rest.trim())))
+        .unwrap_or(("", value.trim()));
+
+    if let Some(age) = relative_age(operand) {
+        let instant = now - age;
+        return Some(match op {
+            ">" | ">=" => (None, Some(instant)),
+            _ => (Some(instant), None),
+        });
+    }
+
+    // A bare number is more likely a year being typed than unix seconds.
+    if operand.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let start = dates::parse_timestamp(operand)?;
+    let end = if operand.len() == 10 {
+        start + 86_400
+    } else {
+        start + 1
+    };
+
+    Some(match op {
+        ">" => (Some(end), None),
+        ">=" => (Some(start), None),
+        "<" => (None, Some(start)),
+        "<=" => (None, Some(end)),
+        _ => (Some(start), Some(end)),
+    })
+}
+
+// `None` when the chunk is not an operator at all; `Some(None)` when it is
+// one but its value is unusable (often because it is still being typed).
+fn parse_condition(chunk: &str, now: i64) -> Option<Option<Condition>> {
+    let (negated, body) = match chunk.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, chunk),
+    };
+    let (key, value) = body.split_once(':')?;
+    let key = key.to_lowercase();
+    let value = value.replace('"', "");
+
+    let filter = match key.as_str() {
+        "tag" => {
+            let tags: Vec<String> = value
+                .split('|')
+                .map(|tag| tag.trim().to_lowercase())
+                .filter(|tag| !tag.is_empty())
+                .collect();
+            (!tags.is_empty()).then_some(Filter::Tag(tags))
+        }
+        "created" => date_range(&value, now).map(|(from, until)| Filter::Created { from, until }),
+        "updated" => date_range(&value, now).map(|(from, until)| Filter::Updated { from, until }),
+        "is" => match value.to_lowercase().as_str() {
+            "pinned" => Some(Filter::Pinned),
+            "deleted" => Some(Filter::Deleted),
+            _ => None,
+        },
+        "has" => match value.to_lowercase().as_str() {
+            "link" => Some(Filter::HasLink),
+            _ => None,
+        },
+        _ => return None,
+    };
+
+    Some(filter.map(|filter| Condition { filter, negated }))
+}
+
+/// Parses search input. `now` (unix seconds) anchors relative dates.
+pub fn parse(input: &str, mode: QueryMode, now: i64) -> SearchQuery {
+    let mut query = SearchQuery::default();
+    let mut text = Vec::new();
+    let mut last_is_text = false;
+
+    for chunk in split_chunks(input) {
+        match parse_condition(chunk, now) {
+            Some(condition) => {
+                query.conditions.extend(condition);
+                last_is_text = false;
+            }
+            None => {
+                text.push(chunk);
+                last_is_text = true;
+            }
+        }
+    }
+
+    let mut text = text.join(" ");
+    // Only the word under the cursor is matched as a prefix.
+    if !last_is_text || input.ends_with(char::is_whitespace) {
+        text.push(' ');
+    }
+
+    query.mode = mode;
+    query.text = to_fts(&text, mode);
+    query.terms = fuzzy_terms(&text);
+    // Words shorter than three characters have no trigram to look for, so
+    // fuzzy text made only of those (`ab`, `c++`) is searched as words
+    // instead of listing every note.
+    if mode == QueryMode::Fuzzy && query.text.is_empty() {
+        query.mode = QueryMode::Simple;
+        query.text = to_fts(&text, QueryMode::Simple);
+    }
+    query
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn simple(input: &str) -> String {
+        to_fts(input, QueryMode::Simple)
+    }
+
+    #[test]
+    fn quotes_each_word_and_prefixes_the_one_being_typed() {
+        assert_eq!(simple("rust gtk"), "\"rust\" \"gtk\"*");
+        assert_eq!(simple("rust gtk "), "\"rust\" \"gtk\"");
+        assert_eq!(simple("  "), "");
+        assert_eq!(simple(""), "");
+    }
+
+    #[test]
+    fn keeps_phrases_together() {
+        assert_eq!(simple("\"exact words\" more"), "\"exact words\" \"more\"*");
+        assert_eq!(simple("\"exact words\""), "\"exact words\"");
+        assert_eq!(simple("\"still typ"), "\"still typ\"*");
+        assert_eq!(simple("a\"b c\"d"), "\"a\" \"b c\" \"d\"*");
+    }
+
+    #[test]
+    fn operators_are_searched_as_plain_words() {
+        assert_eq!(simple("cats AND dogs"), "\"cats\" \"AND\" \"dogs\"*");
+        assert_eq!(simple("NOT"), "\"NOT\"*");
+        assert_eq!(simple("NEAR(a b)"), "\"NEAR(a\" \"b)\"*");
+        assert_eq!(simple("title:foo"), "\"title:foo\"*");
+    }
+
+    #[test]
+    fn drops_terms_without_searchable_characters() {
+        assert_eq!(simple("-"), "");
+        assert_eq!(simple("( ) : * ^ \"\""), "");
+        assert_eq!(simple("foo -"), "\"foo\"");
+        assert_eq!(simple("\""), "");
+    }
+
+    #[test]
+    fn advanced_mode_passes_text_through() {
+        assert_eq!(to_fts(" a OR b* ", QueryMode::Advanced), "a OR b*");
+    }
+
+    #[test]
+    fn hostile_input_never_breaks_fts5() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE notes_fts USING fts5(note_id UNINDEXED, content);
+             INSERT INTO notes_fts VALUES ('1', 'l''école du C++ : \"rust-gtk\" AND (notes) 😀');",
+        )
+        .unwrap();
+
+        let inputs = [
+            "\"",
+            "\"\"",
+            "\"\"\"",
+            "-",
+            "--",
+            "-foo",
+            "foo-bar",
+            "a:b",
+            ":",
+            "col:",
+            "AND",
+            "OR",
+            "NOT",
+            "AND OR NOT",
+            "a AND",
+            "(",
+            ")",
+            "(a",
+            "a)",
+            "()",
+            "*",
+            "a*",
+            "*a",
+            "^a",
+            "a^",
+            "NEAR",
+            "NEAR(",
+            "NEAR(a b, 2)",
+            "{a b}",
+            "+",
+            "a + b",
+            "\\",
+            "'",
+            "l'école",
+            "C++",
+            "\"rust-gtk",
+            "😀",
+            "🦀 crab",
+            "\u{0}",
+            "a\u{2}b",
+            "\t\n",
+            "é",
+            "ß",
+            "a\"b\"c\"",
+            "x\"\"y",
+        ];
+
+        for input in inputs {
+            let expression = simple(input);
+            if expression.is_empty() {
+                continue;
+            }
+            let result = conn.query_row(
+                "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH ?1",
+                [&expression],
+                |row| row.get::<_, i64>(0),
+            );
+            assert!(
+                result.is_ok(),
+                "{:?} -> {:?} failed: {:?}",
+                input,
+                expression,
+                result
+            );
+        }
+    }
+
+    #[test]
+    fn prefix_search_finds_partial_words() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE VIRTUAL TABLE notes_fts USING fts5(note_id UNINDEXED, content);
+             INSERT INTO notes_fts VALUES ('1', 'groceries for the weekend');",
+        )
+        .unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM notes_fts WHERE notes_fts MATCH ?1",
+                [simple("week")],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    const NOW: i64 = 1_790_000_000;
+
+    fn conditions(input: &str) -> Vec<Condition> {
+        parse(input, QueryMode::Simple, NOW).conditions
+    }
+
+    fn condition(filter: Filter, negated: bool) -> Condition {
+        Condition { filter, negated }
+    }
+
+    #[test]
+    fn separates_operators_from_text() {
+        let query = parse("rust tag:dev is:pinned gtk", QueryMode::Simple, NOW);
+        assert_eq!(query.text, "\"rust\" \"gtk\"*");
+        assert_eq!(
+            query.conditions,
+            vec![
+                condition(Filter::Tag(vec!["dev".into()]), false),
+                condition(Filter::Pinned, false),
+            ]
+        );
+
+        // The word before a trailing operator is complete, not a prefix.
+        assert_eq!(
+            parse("rust tag:dev", QueryMode::Simple, NOW).text,
+            "\"rust\""
+        );
+        assert_eq!(parse("tag:dev", QueryMode::Simple, NOW).text, "");
+    }
+
+    #[test]
+    fn parses_tag_alternatives_and_negation() {
+        assert_eq!(
+            conditions("tag:Work|idee -tag:archive"),
+            vec![
+                condition(Filter::Tag(vec!["work".into(), "idee".into()]), false),
+                condition(Filter::Tag(vec!["archive".into()]), true),
+            ]
+        );
+        assert_eq!(
+            conditions("-is:pinned has:link is:deleted"),
+            vec![
+                condition(Filter::Pinned, true),
+                condition(Filter::HasLink, false),
+                condition(Filter::Deleted, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn relative_dates_count_back_from_now() {
+        assert_eq!(
+            conditions("updated:<7d created:>2w"),
+            vec![
+                condition(
+                    Filter::Updated {
+                        from: Some(NOW - 7 * 86_400),
+                        until: None,
+                    },
+                    false,
+                ),
+                condition(
+                    Filter::Created {
+                        from: None,
+                        until: Some(NOW - 14 * 86_400),
+                    },
+                    false,
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn absolute_dates_cover_whole_days() {
+        let day = 1_767_225_600; // 2026-01-01
+        let created = |from, until| vec![condition(Filter::Created { from, until }, false)];
+
+        assert_eq!(
+            conditions("created:>2026-01-01"),
+            created(Some(day + 86_400), None)
+        );
+        assert_eq!(conditions("created:>=2026-01-01"), created(Some(day), None));
+        assert_eq!(conditions("created:<2026-01-01"), created(None, Some(day)));
+        assert_eq!(
+            conditions("created:<=2026-01-01"),
+            created(None, Some(day + 86_400))
+        );
+        assert_eq!(
+            conditions("created:2026-01-01"),
+            created(Some(day), Some(day + 86_400))
+        );
+    }
+
+    #[test]
+    fn incomplete_operators_are_ignored_and_unknown_keys_searched() {
+        let query = parse("created:>2026 is:nothing tag: note", QueryMode::Simple, NOW);
+        assert!(query.conditions.is_empty());
+        assert_eq!(query.text, "\"note\"*");
+
+        let query = parse("title:foo", QueryMode::Simple, NOW);
+        assert!(query.conditions.is_empty());
+        assert_eq!(query.text, "\"title:foo\"*");
+    }
+
+    #[test]
+    fn advanced_mode_still_extracts_operators() {
+        let query = parse("a OR b* tag:x", QueryMode::Advanced, NOW);
+        assert_eq!(query.text, "a OR b*");
+        assert_eq!(
+            query.conditions,
+            vec![condition(Filter::Tag(vec!["x".into()]), false)]
+        );
+    }
+}
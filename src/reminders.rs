@@ -0,0 +1,247 @@
+// Due times for reminders, typed the way one would say them: `in 2h`,
+// `dans 30 min`, `demain 9h`, `lundi 14:30`, `2026-11-02 8h`. Days and times
+// are local, so parsing needs the current UTC offset.
+
+use crate::dates;
+
+const MINUTE: i64 = 60;
+const HOUR: i64 = 3600;
+const DAY: i64 = 86_400;
+
+// Time of day given to a day without one.
+const DEFAULT_TIME: i64 = 9 * HOUR;
+
+const WEEKDAYS: [[&str; 2]; 7] = [
+    ["sunday", "dimanche"],
+    ["monday", "lundi"],
+    ["tuesday", "mardi"],
+    ["wednesday", "mercredi"],
+    ["thursday", "jeudi"],
+    ["friday", "vendredi"],
+    ["saturday", "samedi"],
+];
+
+fn unit_seconds(unit: &str) -> Option<i64> {
+    Some(match unit {
+        "m" | "min" | "mins" | "minute" | "minutes" => MINUTE,
+        "h" | "hr" | "hrs" | "hour" | "hours" | "heure" | "heures" => HOUR,
+        "d" | "day" | "days" | "j" | "jour" | "jours" => DAY,
+        "w" | "week" | "weeks" | "sem" | "semaine" | "semaines" => 7 * DAY,
+        _ => return None,
+    })
+}
+
+// A duration such as `2h30`, `1h 15min` or `3 jours`, in seconds.
+fn parse_duration(text: &str) -> Option<i64> {
+    let text: String = text.split_whitespace().collect();
+    let mut rest = text.as_str();
+    let mut total: i64 = 0;
+    let mut last_unit = None;
+
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount: i64 = rest[..digits].parse().ok()?;
+        rest = &rest[digits..];
+
+        let letters = rest
+            .find(|ch: char| ch.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let unit = match &rest[..letters] {
+            // `2h30`: a bare number after hours counts minutes.
+            "" if last_unit == Some(HOUR) => MINUTE,
+            unit => unit_seconds(unit)?,
+        };
+        rest = &rest[letters..];
+
+        total = total.checked_add(amount.checked_mul(unit)?)?;
+        last_unit = Some(unit);
+    }
+
+    (total > 0).then_some(total)
+}
+
+// `9h`, `9h30`, `09:30`, `9` or `9pm`, as seconds after midnight.
+fn parse_time(word: &str) -> Option<i64> {
+    let (word, half) = match (word.strip_suffix("am"), word.strip_suffix("pm")) {
+        (Some(word), _) => (word, Some(0)),
+        (_, Some(word)) => (word, Some(12)),
+        _ => (word, None),
+    };
+    let (hours, minutes) = word.split_once(['h', ':']).unwrap_or((word, ""));
+
+    let number = |text: &str| -> Option<i64> {
+        (text.len() <= 2 && text.chars().all(|ch| ch.is_ascii_digit()))
+            .then(|| text.parse().ok())
+            .flatten()
+    };
+    let mut hours = number(hours)?;
+    let minutes = if minutes.is_empty() {
+        0
+    } else {
+        number(minutes)?
+    };
+
+    if let Some(half) = half {
+        if !(1..=12).contains(&hours) {
+            return None;
+        }
+        hours = hours % 12 + half;
+    }
+    (hours < 24 && minutes < 60).then_some(hours * HOUR + minutes * MINUTE)
+}
+
+// `YYYY-MM-DD` as days since the epoch.
+fn parse_date(word: &str) -> Option<i64> {
+    let mut parts = word.split('-');
+    let year: i64 = parts.next().filter(|part| part.len() == 4)?.parse().ok()?;
+    let month: u32 = parts.next().filter(|part| part.len() == 2)?.parse().ok()?;
+    let day: u32 = parts.next().filter(|part| part.len() == 2)?.parse().ok()?;
+    if parts.next().is_some() || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    Some(dates::days_from_civil(year, month, day))
+}
+
+fn fold(input: &str) -> String {
+    input
+        .trim()
+        .to_lowercase()
+        .chars()
+        .map(|ch| match ch {
+            'à' | 'â' => 'a',
+            'é' | 'è' | 'ê' => 'e',
+            '\'' | '’' => ' ',
+            ch => ch,
+        })
+        .collect::<String>()
+        .replace("apres-demain", "apres_demain")
+        .replace("apres demain", "apres_demain")
+        .replace("aujourd hui", "aujourdhui")
+}
+
+/// Unix seconds for a due time typed as text, or `None` if it can't be read.
+/// `now` is the current time in unix seconds and `utc_offset` the local
+/// offset from UTC in seconds. A time alone means its next occurrence and a
+/// weekday its next one; a day without a time means 9:00. The result may be
+/// in the past, e.g. for `today 8h` typed at noon.
+pub fn parse_due(input: &str, now: i64, utc_offset: i64) -> Option<i64> {
+    let text = fold(input);
+
+    for prefix in ["in ", "dans ", "+"] {
+        if let Some(duration) = text.strip_prefix(prefix) {
+            return now.checked_add(parse_duration(duration)?);
+        }
+    }
+
+    let today = (now + utc_offset).div_euclid(DAY);
+    let mut day = None;
+    let mut weekday = None;
+    let mut time = None;
+
+    for word in text.split_whitespace() {
+        match word {
+            "at" | "a" | "le" | "on" | "next" | "prochain" => continue,
+            "today" | "aujourdhui" => day = Some(today),
+            "tomorrow" | "demain" => day = Some(today + 1),
+            "apres_demain" => day = Some(today + 2),
+            "midi" | "noon" => time = Some(12 * HOUR),
+            _ => {
+                if let Some(index) = WEEKDAYS.iter().position(|names| names.contains(&word)) {
+                    weekday = Some(index as i64);
+                } else if let Some(date) = parse_date(word) {
+                    day = Some(date);
+                } else {
+                    time = Some(parse_time(word)?);
+                }
+            }
+        }
+    }
+
+    let at = |day: i64, time: i64| day * DAY + time - utc_offset;
+    match (day, weekday, time) {
+        (Some(day), None, time) => Some(at(day, time.unwrap_or(DEFAULT_TIME))),
+        (None, Some(weekday), time) => {
+            // 1970-01-01 was a Thursday.
+            let ahead = (weekday - (today + 4).rem_euclid(7)).rem_euclid(7);
+            let due = at(today + ahead, time.unwrap_or(DEFAULT_TIME));
+            Some(if due <= now { due + 7 * DAY } else { due })
+        }
+        (None, None, Some(time)) => {
+            let due = at(today, time);
+            Some(if due <= now { due + DAY } else { due })
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Saturday 2026-10-17 15:20 in Paris (UTC+2).
+    const NOW: i64 = 1_792_243_200;
+    const OFFSET: i64 = 2 * HOUR;
+
+    fn local(input: &str) -> Option<String> {
+        parse_due(input, NOW, OFFSET).map(|due| dates::format_rfc3339(due + OFFSET))
+    }
+
+    #[test]
+    fn reads_relative_delays() {
+        assert_eq!(parse_due("in 2h", NOW, OFFSET), Some(NOW + 2 * HOUR));
+        assert_eq!(parse_due("dans 1h30", NOW, OFFSET), Some(NOW + 90 * MINUTE));
+        assert_eq!(parse_due("Dans 3 jours", NOW, OFFSET), Some(NOW + 3 * DAY));
+        assert_eq!(parse_due("+45 min", NOW, OFFSET), Some(NOW + 45 * MINUTE));
+        assert_eq!(
+            parse_due("in 1 week 2 days", NOW, OFFSET),
+            Some(NOW + 9 * DAY)
+        );
+        assert_eq!(parse_due("in soon", NOW, OFFSET), None);
+        assert_eq!(parse_due("dans 0h", NOW, OFFSET), None);
+    }
+
+    #[test]
+    fn rejects_delays_too_long_to_count() {
+        // Each fits in an i64 on its own; added to now, or to each other,
+        // they no longer do.
+        assert_eq!(parse_due("in 153722867280912930m", NOW, OFFSET), None);
+        assert_eq!(
+            parse_due("in 153722867280912930m 153722867280912930m", 0, 0),
+            None
+        );
+    }
+
+    #[test]
+    fn reads_local_days_and_times() {
+        assert_eq!(local("demain 9h").as_deref(), Some("2026-10-18T09:00:00Z"));
+        assert_eq!(
+            local("Tomorrow at 6:45pm").as_deref(),
+            Some("2026-10-18T18:45:00Z")
+        );
+        assert_eq!(
+            local("après-demain").as_deref(),
+            Some("2026-10-19T09:00:00Z")
+        );
+        assert_eq!(local("18h30").as_deref(), Some("2026-10-17T18:30:00Z"));
+        assert_eq!(local("9h").as_deref(), Some("2026-10-18T09:00:00Z"));
+        assert_eq!(
+            local("lundi 14:30").as_deref(),
+            Some("2026-10-19T14:30:00Z")
+        );
+        assert_eq!(local("samedi 10h").as_deref(), Some("2026-10-24T10:00:00Z"));
+        assert_eq!(local("samedi 16h").as_deref(), Some("2026-10-17T16:00:00Z"));
+        assert_eq!(
+            local("2026-11-02 8h").as_deref(),
+            Some("2026-11-02T08:00:00Z")
+        );
+        assert_eq!(
+            local("aujourd'hui midi").as_deref(),
+            Some("2026-10-17T12:00:00Z")
+        );
+        assert_eq!(local("demain 25h"), None);
+        assert_eq!(local("bientot"), None);
+        assert_eq!(local(""), None);
+    }
+}
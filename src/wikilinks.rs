@@ -0,0 +1,116 @@
+// `[[target]]` links between notes. The target is a note title or id; `db`
+// resolves it when the note is saved and remembers the id it found, so a link
+// keeps pointing at the same note after that note is retitled.
+
+use std::ops::Range;
+
+fn is_label_char(ch: char) -> bool {
+    !matches!(ch, '[' | ']' | '\n')
+}
+
+/// Byte ranges of the `[[...]]` links in `content`, brackets included, with
+/// their trimmed targets. Fenced code blocks and code spans are skipped.
+pub fn find(content: &str) -> Vec<(Range<usize>, String)> {
+    let mut found = Vec::new();
+    let mut in_fence = false;
+    let mut line_start = 0;
+
+    for line in content.split_inclusive('\n') {
+        let offset = line_start;
+        line_start += line.len();
+
+        if line.trim_start().starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence {
+            continue;
+        }
+
+        let mut search = 0;
+        while let Some(open) = line[search..].find(['[', '`']).map(|index| search + index) {
+            // A code span runs to the next run of as many backticks; an
+            // unmatched run is plain text.
+            if line[open..].starts_with('`') {
+                let ticks = line[open..].len() - line[open..].trim_start_matches('`').len();
+                let after = open + ticks;
+                search = line[after..]
+                    .find(&line[open..after])
+                    .map_or(after, |close| after + close + ticks);
+                continue;
+            }
+            if !line[open..].starts_with("[[") {
+                search = open + 1;
+                continue;
+            }
+
+            let inner = &line[open + 2..];
+            let end = inner.find(|ch| !is_label_char(ch)).unwrap_or(inner.len());
+            if !inner[end..].starts_with("]]") {
+                search = open + 2 + end;
+                continue;
+            }
+
+            let target = inner[..end].trim();
+            if !target.is_empty() {
+                found.push((offset + open..offset + open + end + 4, target.to_string()));
+            }
+            search = open + end + 4;
+        }
+    }
+
+    found
+}
+
+/// The targets linked from `content`, in order of first appearance.
+pub fn targets(content: &str) -> Vec<String> {
+    let mut targets: Vec<String> = Vec::new();
+    for (_, target) in find(content) {
+        if !targets.contains(&target) {
+            targets.push(target);
+        }
+    }
+    targets
+}
+
+/// The partly typed link target `text` ends with, after an unclosed `[[`.
+pub fn fragment_at_end(text: &str) -> Option<&str> {
+    let open = text.rfind("[[")?;
+    let fragment = &text[open + 2..];
+    fragment
+        .chars()
+        .all(is_label_char)
+        .then_some(fragment.trim_start())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_links_outside_code() {
+        let content = "Voir [[Budget 2026]] et [[ a1b2 ]].\n```\n[[pas un lien]]\n```\n[[]] [[x";
+        let found = find(content);
+        assert_eq!(
+            found
+                .iter()
+                .map(|(range, target)| (&content[range.clone()], target.as_str()))
+                .collect::<Vec<_>>(),
+            vec![("[[Budget 2026]]", "Budget 2026"), ("[[ a1b2 ]]", "a1b2")]
+        );
+    }
+
+    #[test]
+    fn skips_code_spans() {
+        let content = "`[[pas un lien]]` puis [[Budget]] et ``a ` [[code]]`` ` [[Notes]]";
+        assert_eq!(targets(content), ["Budget", "Notes"]);
+    }
+
+    #[test]
+    fn finds_the_link_being_typed() {
+        assert_eq!(fragment_at_end("voir [[Bud"), Some("Bud"));
+        assert_eq!(fragment_at_end("voir [["), Some(""));
+        assert_eq!(fragment_at_end("voir [[Budget]] et"), None);
+        assert_eq!(fragment_at_end("voir"), None);
+    }
+}
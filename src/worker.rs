@@ -0,0 +1,112 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+use futures_channel::oneshot;
+use rusqlite::Connection;
+
+type Job = Box<dyn FnOnce(&mut Connection) + Send>;
+
+/// Handle to the thread that owns the SQLite connection. Every database call
+/// from the UI is queued here so the GTK main loop never blocks on SQLite;
+/// results come back as futures to be awaited with `glib::spawn_future_local`.
+#[derive(Clone)]
+pub struct DbWorker {
+    jobs: mpsc::Sender<Job>,
+}
+
+/// Tracks the most recent request issued through it. Queued requests that
+/// have been superseded are dropped before they reach SQLite, and late
+/// results are discarded, so only the latest search is ever rendered.
+#[derive(Clone, Default)]
+pub struct LatestRequest {
+    generation: Arc<AtomicU64>,
+}
+
+impl LatestRequest {
+    fn next(&self) -> u64 {
+        self.generation.fetch_add(1, Ordering::SeqCst) + 1
+    }
+
+    fn is_current(&self, generation: u64) -> bool {
+        self.generation.load(Ordering::SeqCst) == generation
+    }
+
+    /// Drops any request still pending without issuing a new one.
+    pub fn cancel(&self) {
+        self.next();
+    }
+}
+
+impl DbWorker {
+    pub fn spawn(mut conn: Connection) -> Result<Self> {
+        let (jobs, queue) = mpsc::channel::<Job>();
+
+        thread::Builder::new()
+            .name("memo-tori-db".to_string())
+            .spawn(move || {
+                for job in queue {
+                    job(&mut conn);
+                }
+            })
+            .context("failed to start database thread")?;
+
+        Ok(Self { jobs })
+    }
+
+    fn submit<T, F>(&self, job: F) -> oneshot::Receiver<Result<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let (reply, response) = oneshot::channel();
+        let _ = self.jobs.send(Box::new(move |conn| {
+            let _ = reply.send(job(conn));
+        }));
+        response
+    }
+
+    pub fn call<T, F>(&self, job: F) -> impl Future<Output = Result<T>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let response = self.submit(job);
+        async move {
+            response
+                .await
+                .map_err(|_| anyhow!("database thread stopped"))?
+        }
+    }
+
+    /// Like [`DbWorker::call`], but resolves to `None` when a newer request
+    /// was issued through `latest` before this one completed.
+    pub fn call_latest<T, F>(
+        &self,
+        latest: &LatestRequest,
+        job: F,
+    ) -> impl Future<Output = Option<Result<T>>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T> + Send + 'static,
+    {
+        let generation = latest.next();
+        let latest = latest.clone();
+        let guard = latest.clone();
+
+        let response = self.submit(move |conn| {
+            if !guard.is_current(generation) {
+                return Err(anyhow!("request superseded"));
+            }
+            job(conn)
+        });
+
+        async move {
+            let result = response.await.ok()?;
+            latest.is_current(generation).then_some(result)
+        }
+    }
+}